@@ -59,17 +59,18 @@ class RustProcessInterface:
         
         print("Starting player:", color)
         
+        # The engine is told the color of its opponent
+        human_color = BLACK if color == WHITE else WHITE
+        # Same bots as the interactive binary: pignite as white, tepig as black
+        bot = "pignite" if color == WHITE else "tepig"
+
         self.proc = subprocess.Popen(
-            self.path_executable, 
+            [self.path_executable, "play", "--quiet", "--color", human_color, "--bot", bot],
             stdin=subprocess.PIPE,
             stdout=subprocess.PIPE, 
             stderr=subprocess.PIPE,
             universal_newlines=True
         )
-        
-        color = f"{color}\n"
-        self.proc.stdin.write(color)
-        self.proc.stdin.flush()
 
     def receive_move(self, move: str) -> None:
         
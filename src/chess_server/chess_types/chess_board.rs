@@ -1,3 +1,5 @@
+use std::cmp::max;
+
 use smallvec::{smallvec, SmallVec};
 
 use super::color::Color;
@@ -19,6 +21,10 @@ pub const MOVE_CONTAINER_SIZE: usize = 64;
 pub type MoveContainer = SmallVec<[Move; MOVE_CONTAINER_SIZE]>;
 pub type SquareContainer = SmallVec<[Square; 16]>;
 
+/// Values of pawn, bishop, knight, rook, queen and king used by the static
+/// exchange evaluation, in centipawns.
+pub const SEE_VALUES: [i32; 6] = [100, 300, 300, 500, 900, 0];
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct SmallVecChessBoard {
     board: [Option<ColorPiece>; 64],
@@ -84,6 +90,13 @@ impl SmallVecChessBoard {
         next_board
     }
 
+    // Passes the turn to the other player without moving a piece
+    pub fn null_move_state(&self) -> Self {
+        let mut next_board = *self;
+        next_board.turn_color = self.turn_color.opposite();
+        next_board
+    }
+
     pub fn starting_position() -> ChessBoard {
         let board: [Option<ColorPiece>; 64] = [
             Some(BLACK_ROOK),
@@ -158,6 +171,122 @@ impl SmallVecChessBoard {
         }
     }
 
+    /// Reads the piece placement and the side to move of a FEN (or EPD)
+    /// string. Castling rights, en passant and the move counters are
+    /// ignored since the board doesn't keep track of them.
+    pub fn from_fen(fen: &str) -> Result<ChessBoard, String> {
+        let mut fields = fen.split_whitespace();
+        let placement = fields.next().ok_or("Empty FEN")?;
+
+        let mut board: [Option<ColorPiece>; 64] = [None; 64];
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(format!("Expected 8 ranks in FEN: {}", fen));
+        }
+
+        for (row, rank) in ranks.iter().enumerate() {
+            let mut col = 0;
+            for ch in rank.chars() {
+                if let Some(empty) = ch.to_digit(10) {
+                    col += empty as usize;
+                    continue;
+                }
+
+                let color = if ch.is_ascii_uppercase() {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+
+                let piece = match ch.to_ascii_lowercase() {
+                    'p' => Piece::Pawn,
+                    'n' => Piece::Knight,
+                    'b' => Piece::Bishop,
+                    'r' => Piece::Rook,
+                    'q' => Piece::Queen,
+                    'k' => Piece::King,
+                    _ => return Err(format!("Invalid piece '{}' in FEN: {}", ch, fen)),
+                };
+
+                if col >= 8 {
+                    return Err(format!("Rank too long in FEN: {}", fen));
+                }
+                board[row * 8 + col] = Some(ColorPiece::new(color, piece));
+                col += 1;
+            }
+
+            if col != 8 {
+                return Err(format!("Rank of the wrong length in FEN: {}", fen));
+            }
+        }
+
+        let turn_color = match fields.next() {
+            None | Some("w") => Color::White,
+            Some("b") => Color::Black,
+            Some(other) => return Err(format!("Invalid side to move '{}' in FEN", other)),
+        };
+
+        let chess_board = ChessBoard { board, turn_color };
+        if chess_board.find_piece(WHITE_KING).is_none() || chess_board.find_piece(BLACK_KING).is_none()
+        {
+            return Err(format!("Both kings are needed in FEN: {}", fen));
+        }
+
+        Ok(chess_board)
+    }
+
+    // Castling and en passant are never available
+    pub fn to_fen(self) -> String {
+        let mut placement = String::new();
+
+        for row in 0..8 {
+            let mut empty = 0;
+            for col in 0..8 {
+                match self.board[row * 8 + col] {
+                    None => empty += 1,
+                    Some(color_piece) => {
+                        if empty > 0 {
+                            placement += &empty.to_string();
+                            empty = 0;
+                        }
+                        placement += &ColorPiece::to_str(&Some(color_piece));
+                    }
+                }
+            }
+
+            if empty > 0 {
+                placement += &empty.to_string();
+            }
+            if row < 7 {
+                placement += "/";
+            }
+        }
+
+        let turn = match self.turn_color {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        format!("{} {} - - 0 1", placement, turn)
+    }
+
+    // Same position with the board upside down and the colors swapped
+    #[cfg(test)]
+    pub fn mirrored(&self) -> ChessBoard {
+        let mut board = [None; 64];
+        for (index, content) in self.board.iter().enumerate() {
+            let (row, col) = (index / 8, index % 8);
+            board[(7 - row) * 8 + col] = content.map(|color_piece| {
+                ColorPiece::new(color_piece.get_color().opposite(), color_piece.get_piece())
+            });
+        }
+
+        ChessBoard {
+            board,
+            turn_color: self.turn_color.opposite(),
+        }
+    }
+
     pub fn board_string(self) -> String {
         let mut s = format!(
             "{}'s turn",
@@ -494,6 +623,53 @@ impl SmallVecChessBoard {
         }
     }
 
+    /// Whether `color` could mate with any sequence of legal moves, the
+    /// pieces of the other color being allowed to help by blocking the king.
+    ///
+    /// A single knight needs a blocker the queen can't be, and bishops need
+    /// bishops on both square colors, or a knight or pawn to block.
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        let mut knights = 0;
+        let mut bishops = 0;
+        // Square colors of all the bishops on the board, light then dark
+        let mut bishop_squares = [false; 2];
+        let mut opponent_blockers = false;
+        let mut opponent_minor_or_pawn = false;
+
+        for (square, color_piece) in self.iter_coordinates() {
+            let Some(color_piece) = color_piece else {
+                continue;
+            };
+            let piece = color_piece.get_piece();
+
+            if piece == Piece::Bishop {
+                let index = square.get_index();
+                bishop_squares[((index / 8 + index % 8) % 2) as usize] = true;
+            }
+
+            if color_piece.get_color() != color {
+                opponent_blockers |= !matches!(piece, Piece::King | Piece::Queen);
+                opponent_minor_or_pawn |= matches!(piece, Piece::Knight | Piece::Pawn);
+                continue;
+            }
+
+            match piece {
+                Piece::Pawn | Piece::Rook | Piece::Queen => return true,
+                Piece::Knight => knights += 1,
+                Piece::Bishop => bishops += 1,
+                Piece::King => (),
+            }
+        }
+
+        match (knights, bishops) {
+            (0, 0) => false,
+            (1, 0) => opponent_blockers,
+            (_, 0) => true,
+            (0, _) => bishop_squares == [true, true] || opponent_minor_or_pawn,
+            _ => true,
+        }
+    }
+
     pub fn is_king_in_check(&self, color: Color) -> bool {
         // find king
 
@@ -628,4 +804,243 @@ impl SmallVecChessBoard {
             )
         })
     }
+
+    // Least valuable piece of the color attacking the square. Pieces taken
+    // off the board uncover the sliders behind them.
+    fn least_valuable_attacker(&self, coordinate: &Square, color: Color) -> Option<Square> {
+        let pawn_row = match color {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        for d_col in [-1, 1] {
+            if let Some(square) = coordinate.add(pawn_row, d_col) {
+                if self.get_square_content(&square) == Some(ColorPiece::new(color, Piece::Pawn)) {
+                    return Some(square);
+                }
+            }
+        }
+
+        let mut knights = SquareContainer::new();
+        let mut diagonals = SquareContainer::new();
+        let mut lines = SquareContainer::new();
+        let mut kings = SquareContainer::new();
+        self.inplace_squares_attacked_by_knight(coordinate, &mut knights);
+        self.inplace_squares_attacked_by_bishop(coordinate, &mut diagonals);
+        self.inplace_squares_attacked_by_rook(coordinate, &mut lines);
+        self.inplace_squares_attacked_by_king(coordinate, &mut kings);
+
+        let find = |squares: &SquareContainer, piece: Piece| {
+            squares
+                .iter()
+                .find(|square| {
+                    self.get_square_content(square) == Some(ColorPiece::new(color, piece))
+                })
+                .copied()
+        };
+
+        find(&knights, Piece::Knight)
+            .or_else(|| find(&diagonals, Piece::Bishop))
+            .or_else(|| find(&lines, Piece::Rook))
+            .or_else(|| find(&diagonals, Piece::Queen))
+            .or_else(|| find(&lines, Piece::Queen))
+            .or_else(|| find(&kings, Piece::King))
+    }
+
+    /// Static exchange evaluation of a move, in centipawns for the side
+    /// playing it.
+    ///
+    /// Both sides keep capturing on the destination square with their least
+    /// valuable piece, x-ray attackers joining once the pieces in front of
+    /// them are gone, and either side may stop when going on would lose
+    /// material. Pins are not taken into account and the king only captures
+    /// on a square the other side no longer attacks.
+    pub fn see(&self, mv: &Move) -> i32 {
+        self.see_with_values(mv, &SEE_VALUES)
+    }
+
+    // Same as see, with the values of pawn, bishop, knight, rook, queen and
+    // king in any unit
+    pub fn see_with_values(&self, mv: &Move, values: &[i32; 6]) -> i32 {
+        let see_value = |piece: Piece| values[piece as usize];
+
+        let target = mv.get_next_square();
+        let Some(mover) = self.get_square_content(&mv.get_current_square()) else {
+            return 0;
+        };
+
+        let promotion_row = |color: Color| match color {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+
+        let mut board = *self;
+        let mut gains = [0; 32];
+
+        gains[0] = board
+            .get_square_content(&target)
+            .map_or(0, |victim| see_value(victim.get_piece()));
+        let mut on_target = match mv.get_promotion_piece() {
+            Some(piece) => {
+                gains[0] += see_value(piece) - see_value(Piece::Pawn);
+                ColorPiece::new(mover.get_color(), piece)
+            }
+            None => mover,
+        };
+        board.set_square_content(&mv.get_current_square(), &None);
+        board.set_square_content(&target, &Some(on_target));
+
+        let mut depth = 0;
+        let mut color = mover.get_color().opposite();
+        while depth + 1 < gains.len() {
+            let Some(square) = board.least_valuable_attacker(&target, color) else {
+                break;
+            };
+            let mut attacker = board.get_square_content(&square).unwrap();
+
+            board.set_square_content(&square, &None);
+            if attacker.get_piece() == Piece::King
+                && board
+                    .least_valuable_attacker(&target, color.opposite())
+                    .is_some()
+            {
+                break;
+            }
+
+            depth += 1;
+            gains[depth] = see_value(on_target.get_piece()) - gains[depth - 1];
+
+            // Pawns recapturing on the last row promote to a queen
+            if attacker.get_piece() == Piece::Pawn
+                && target.get_coordinates().0 == promotion_row(color)
+            {
+                attacker = ColorPiece::new(color, Piece::Queen);
+                gains[depth] += see_value(Piece::Queen) - see_value(Piece::Pawn);
+            }
+
+            on_target = attacker;
+            board.set_square_content(&target, &Some(on_target));
+            color = color.opposite();
+        }
+
+        // Every side only goes on with the exchange when it pays off
+        while depth > 0 {
+            gains[depth - 1] = -max(-gains[depth - 1], gains[depth]);
+            depth -= 1;
+        }
+
+        gains[0]
+    }
+
+    /// Whether the static exchange evaluation of the move is at least the
+    /// threshold, without resolving the exchange when the material at stake
+    /// already decides it.
+    pub fn see_ge(&self, mv: &Move, threshold: i32) -> bool {
+        let see_value = |piece: Piece| SEE_VALUES[piece as usize];
+
+        let Some(mover) = self.get_square_content(&mv.get_current_square()) else {
+            return threshold <= 0;
+        };
+
+        let mut best = self
+            .get_square_content(&mv.get_next_square())
+            .map_or(0, |victim| see_value(victim.get_piece()));
+        let mut at_stake = see_value(mover.get_piece());
+        if let Some(piece) = mv.get_promotion_piece() {
+            best += see_value(piece) - see_value(Piece::Pawn);
+            at_stake = see_value(piece);
+        }
+
+        // The other side can at best win the piece that moved
+        if best < threshold {
+            return false;
+        }
+        // Unless a pawn of the other side recaptures on its last row, which
+        // also promotes it
+        let last_row = match mover.get_color() {
+            Color::White => 7,
+            Color::Black => 0,
+        };
+        let recapture_may_promote = mv.get_next_square().get_coordinates().0 == last_row;
+        if best - at_stake >= threshold && !recapture_may_promote {
+            return true;
+        }
+
+        self.see(mv) >= threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_server::io::utils::parse_uci_move;
+
+    fn see_of(fen: &str, move_str: &str) -> i32 {
+        let chess_board = ChessBoard::from_fen(fen).unwrap();
+        chess_board.see(&parse_uci_move(&chess_board, move_str).unwrap())
+    }
+
+    #[test]
+    fn see_resolves_known_exchanges() {
+        let cases = [
+            // The rook behind joins the exchange once the first one is gone
+            ("4r1k1/8/8/4p3/8/8/4R3/4R1K1 w - - 0 1", "e2e5", 100),
+            ("4k3/8/3p4/4p3/8/8/8/4QK2 w - - 0 1", "e1e5", -800),
+            // The pawn recaptures on the last row as a queen, which the
+            // queen behind it then takes
+            ("3n3r/2P5/7k/q7/8/8/8/3R3K w - - 0 1", "d1d8", 200),
+            // The king doesn't recapture on a square that is still attacked
+            ("4k3/3p4/8/1B6/8/8/8/3QK3 w - - 0 1", "d1d7", 100),
+            ("4k3/3p4/8/8/8/8/8/3QK3 w - - 0 1", "d1d7", -800),
+        ];
+
+        for (fen, move_str, expected) in cases {
+            assert_eq!(see_of(fen, move_str), expected, "{} {}", fen, move_str);
+        }
+    }
+
+    #[test]
+    fn see_ge_agrees_with_see() {
+        let fens = [
+            "4r1k1/8/8/4p3/8/8/4R3/4R1K1 w - - 0 1",
+            "3n3r/2P5/7k/q7/8/8/8/3R3K w - - 0 1",
+            "4k3/3p4/8/1B6/8/8/8/3QK3 w - - 0 1",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w - - 4 4",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            // Taking the knight loses the rook to a pawn that promotes
+            "4k3/8/8/8/8/8/4p3/R2n2K1 w - - 0 1",
+            "r2N2k1/4P3/8/8/8/8/8/4K3 b - - 0 1",
+        ];
+        let thresholds = [-1000, -900, -500, -300, -100, -1, 0, 1, 100, 200, 300, 500, 900, 1000];
+
+        for fen in fens {
+            let chess_board = ChessBoard::from_fen(fen).unwrap();
+
+            for mv in chess_board.get_allowed_moves(chess_board.get_turn_color()).iter() {
+                let see = chess_board.see(mv);
+
+                for threshold in thresholds {
+                    assert_eq!(
+                        chess_board.see_ge(mv, threshold),
+                        see >= threshold,
+                        "{} {} {}",
+                        fen,
+                        mv,
+                        threshold
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mirrored_boards_swap_the_colors() {
+        let fen = "r3k2r/pp3ppp/2n5/3q4/8/5N2/PPP2PPP/R2QK2R b - - 0 1";
+        let chess_board = ChessBoard::from_fen(fen).unwrap();
+
+        assert_eq!(
+            chess_board.mirrored().to_fen(),
+            "r2qk2r/ppp2ppp/5n2/8/3Q4/2N5/PP3PPP/R3K2R w - - 0 1"
+        );
+        assert_eq!(chess_board.mirrored().mirrored(), chess_board);
+    }
 }
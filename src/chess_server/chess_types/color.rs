@@ -12,6 +12,13 @@ impl Color {
         }
     }
 
+    pub fn opposite(&self) -> Color {
+        match *self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+
     pub fn as_sign(&self) -> f64 {
         match *self {
             Color::White => 1.,
@@ -0,0 +1,236 @@
+use crate::chess_server::chess_types::{ChessBoard, ChessStatus, Color};
+use crate::engines::time_manager::ClockState;
+
+use std::time::Duration;
+
+/// Time given back to a player for every move.
+///
+/// With `Bronstein` the time used for the move is given back, up to the
+/// delay. With `SimpleDelay` the clock only starts running once the delay
+/// has passed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeBonus {
+    None,
+    Fischer(Duration),
+    Bronstein(Duration),
+    SimpleDelay(Duration),
+}
+
+/// With `moves_per_period`, `base_time` is added to the clock again every
+/// time a player completes that many moves, as in classical time controls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    pub base_time: Duration,
+    pub bonus: TimeBonus,
+    pub moves_per_period: Option<u32>,
+}
+
+impl TimeControl {
+    pub fn sudden_death(base_time: Duration) -> TimeControl {
+        TimeControl {
+            base_time,
+            bonus: TimeBonus::None,
+            moves_per_period: None,
+        }
+    }
+
+    pub fn fischer(base_time: Duration, increment: Duration) -> TimeControl {
+        TimeControl {
+            bonus: TimeBonus::Fischer(increment),
+            ..TimeControl::sudden_death(base_time)
+        }
+    }
+
+    pub fn bronstein(base_time: Duration, delay: Duration) -> TimeControl {
+        TimeControl {
+            bonus: TimeBonus::Bronstein(delay),
+            ..TimeControl::sudden_death(base_time)
+        }
+    }
+
+    pub fn simple_delay(base_time: Duration, delay: Duration) -> TimeControl {
+        TimeControl {
+            bonus: TimeBonus::SimpleDelay(delay),
+            ..TimeControl::sudden_death(base_time)
+        }
+    }
+
+    pub fn moves_per_period(moves: u32, period_time: Duration) -> TimeControl {
+        TimeControl {
+            moves_per_period: Some(moves.max(1)),
+            ..TimeControl::sudden_death(period_time)
+        }
+    }
+
+    // Delays count as increments since a player can spend them on every move
+    pub fn get_increment(&self) -> Duration {
+        match self.bonus {
+            TimeBonus::None => Duration::ZERO,
+            TimeBonus::Fischer(increment) => increment,
+            TimeBonus::Bronstein(delay) | TimeBonus::SimpleDelay(delay) => delay,
+        }
+    }
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ChessClock {
+    time_control: TimeControl,
+    remaining: [Duration; 2],
+    moves_made: [u32; 2],
+}
+
+impl ChessClock {
+    pub fn new(time_control: TimeControl) -> ChessClock {
+        ChessClock {
+            time_control,
+            remaining: [time_control.base_time; 2],
+            moves_made: [0; 2],
+        }
+    }
+
+    pub fn get_remaining(&self, color: Color) -> Duration {
+        self.remaining[color_index(color)]
+    }
+
+    /// Charges `elapsed` to the clock of `color` after one of its moves.
+    /// Returns true when the player ran out of time.
+    pub fn charge(&mut self, color: Color, elapsed: Duration) -> bool {
+        let index = color_index(color);
+
+        let (charged, bonus) = match self.time_control.bonus {
+            TimeBonus::None => (elapsed, Duration::ZERO),
+            TimeBonus::Fischer(increment) => (elapsed, increment),
+            TimeBonus::Bronstein(delay) => (elapsed, elapsed.min(delay)),
+            TimeBonus::SimpleDelay(delay) => (elapsed.saturating_sub(delay), Duration::ZERO),
+        };
+
+        if charged >= self.remaining[index] {
+            self.remaining[index] = Duration::ZERO;
+            return true;
+        }
+
+        self.remaining[index] = self.remaining[index] - charged + bonus;
+        self.moves_made[index] += 1;
+
+        if let Some(moves) = self.time_control.moves_per_period {
+            if self.moves_made[index].is_multiple_of(moves) {
+                self.remaining[index] += self.time_control.base_time;
+            }
+        }
+
+        false
+    }
+
+    /// Takes `elapsed` off the clock of `color` for a decision that wasn't a
+    /// move: no bonus is given and the move doesn't count towards the period.
+    /// Returns true when the player ran out of time.
+    pub fn spend(&mut self, color: Color, elapsed: Duration) -> bool {
+        let index = color_index(color);
+
+        if elapsed >= self.remaining[index] {
+            self.remaining[index] = Duration::ZERO;
+            return true;
+        }
+
+        self.remaining[index] -= elapsed;
+        false
+    }
+
+    /// Clock as seen by the player to move.
+    pub fn to_clock_state(self, color: Color) -> ClockState {
+        let increment = self.time_control.get_increment();
+
+        let movestogo = self
+            .time_control
+            .moves_per_period
+            .map(|moves| moves - self.moves_made[color_index(color)] % moves);
+
+        ClockState {
+            wtime: self.get_remaining(Color::White),
+            btime: self.get_remaining(Color::Black),
+            winc: increment,
+            binc: increment,
+            movestogo,
+        }
+    }
+}
+
+/// Result of a game where `flagged` ran out of time: the opponent wins,
+/// unless no sequence of legal moves could let it mate, as in FIDE 6.9.
+pub fn flag_fall_status(chess_board: &ChessBoard, flagged: Color) -> ChessStatus {
+    let opponent = flagged.opposite();
+
+    if !chess_board.has_mating_material(opponent) {
+        return ChessStatus::Draw;
+    }
+
+    match opponent {
+        Color::White => ChessStatus::WhiteWon,
+        Color::Black => ChessStatus::BlackWon,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ChessStatus::{BlackWon, Draw, WhiteWon};
+    use Color::{Black, White};
+
+    #[test]
+    fn flag_fall_needs_a_possible_mate() {
+        let cases = [
+            // Knight against rook: the rook can block its own king
+            ("r3k3/8/8/8/8/8/8/4K1N1 w - - 0 1", Black, WhiteWon),
+            ("r3k3/8/8/8/8/8/8/4K1N1 w - - 0 1", White, BlackWon),
+            ("4k3/8/8/8/8/8/8/4K1N1 w - - 0 1", Black, Draw),
+            ("3qk3/8/8/8/8/8/8/4K1N1 w - - 0 1", Black, Draw),
+            ("4k3/8/8/8/8/8/8/3NK1N1 w - - 0 1", Black, WhiteWon),
+            // Bishops on a single square color never cover the other one
+            ("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1", Black, Draw),
+            ("4k3/8/8/8/8/4B3/8/2B1K3 w - - 0 1", Black, Draw),
+            ("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1", Black, WhiteWon),
+            ("4k3/7p/8/8/8/8/8/2B1K3 w - - 0 1", Black, WhiteWon),
+            ("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1", Black, WhiteWon),
+            ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", Black, WhiteWon),
+            ("4k3/8/8/8/8/8/8/r3K3 w - - 0 1", White, BlackWon),
+            ("4k3/8/8/8/8/8/8/r3K3 w - - 0 1", Black, Draw),
+        ];
+
+        for (fen, flagged, expected) in cases {
+            let chess_board = ChessBoard::from_fen(fen).unwrap();
+            assert_eq!(flag_fall_status(&chess_board, flagged), expected, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn only_moves_earn_the_bonus_and_count() {
+        let time_control = TimeControl {
+            bonus: TimeBonus::Fischer(Duration::from_secs(1)),
+            ..TimeControl::moves_per_period(2, Duration::from_secs(60))
+        };
+        let mut clock = ChessClock::new(time_control);
+
+        assert!(!clock.spend(White, Duration::from_secs(5)));
+        assert_eq!(clock.get_remaining(White), Duration::from_secs(55));
+        assert_eq!(clock.to_clock_state(White).movestogo, Some(2));
+
+        assert!(!clock.charge(White, Duration::from_secs(5)));
+        assert_eq!(clock.get_remaining(White), Duration::from_secs(51));
+        assert_eq!(clock.to_clock_state(White).movestogo, Some(1));
+
+        // The second move completes the period
+        assert!(!clock.charge(White, Duration::from_secs(1)));
+        assert_eq!(clock.get_remaining(White), Duration::from_secs(111));
+        assert_eq!(clock.to_clock_state(White).movestogo, Some(2));
+
+        assert!(clock.spend(Black, Duration::from_secs(60)));
+        assert_eq!(clock.get_remaining(Black), Duration::ZERO);
+    }
+}
@@ -1,60 +1,827 @@
 use crate::chess_server::chess_types::{ChessBoard, ChessStatus, Color, Move};
+use crate::chess_server::clock::{flag_fall_status, ChessClock, TimeControl};
+use crate::chess_server::game_events::{GameEvent, GameObserver, GameOverReason};
+use crate::engines::score::Score;
+use crate::engines::time_manager::ClockState;
+
+use log::warn;
+
+use std::time::{Duration, Instant};
+
+/// Everything a player knows when it has to decide.
+///
+/// `history` holds the positions before the current one, oldest first, and
+/// `ply` is the number of moves played since the start of the game.
+/// `game_status` is the result of the game so far, only ongoing games ask
+/// for decisions. `draw_offered` is set when the opponent offered a draw
+/// with its last move and `opponent_score` is the score the opponent gave
+/// that move, from its point of view.
+#[derive(Clone, Copy, Debug)]
+pub struct GameContext<'a> {
+    pub chess_board: ChessBoard,
+    pub history: &'a [ChessBoard],
+    pub ply: usize,
+    pub game_status: ChessStatus,
+    pub clock: Option<ClockState>,
+    pub draw_offered: bool,
+    pub opponent_score: Option<Score>,
+}
+
+impl GameContext<'_> {
+    // Context of a position without any game around it
+    pub fn from_board(chess_board: &ChessBoard) -> GameContext<'static> {
+        GameContext {
+            chess_board: *chess_board,
+            history: &[],
+            ply: 0,
+            game_status: chess_board.get_game_status(),
+            clock: None,
+            draw_offered: false,
+            opponent_score: None,
+        }
+    }
+
+    pub fn get_turn_color(&self) -> Color {
+        self.chess_board.get_turn_color()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+    Move(Move),
+    // The move is played and the opponent may accept the draw on its turn
+    OfferDraw(Move),
+    AcceptDraw,
+    Resign,
+    // Takes back the last move of the player and the reply to it
+    Takeback,
+}
+
+impl Decision {
+    pub fn get_move(&self) -> Option<Move> {
+        match *self {
+            Decision::Move(mv) | Decision::OfferDraw(mv) => Some(mv),
+            Decision::AcceptDraw | Decision::Resign | Decision::Takeback => None,
+        }
+    }
+}
 
 pub trait Player {
-    fn select_move(&self, chess_board: &ChessBoard) -> Move;
+    fn decide(&self, context: &GameContext) -> Decision;
+
+    // Score of the last decision from the point of view of the player, for
+    // players that have one
+    fn get_last_score(&self) -> Option<Score> {
+        None
+    }
+
+    // For callers that only have a position, the player has to move
+    fn select_move(&self, chess_board: &ChessBoard) -> Move {
+        self.decide(&GameContext::from_board(chess_board))
+            .get_move()
+            .expect("Player didn't select a move")
+    }
 }
 
+fn winner_status(color: Color) -> ChessStatus {
+    match color {
+        Color::White => ChessStatus::WhiteWon,
+        Color::Black => ChessStatus::BlackWon,
+    }
+}
+
+/// A move of the game with the position it was played in.
+///
+/// `clock` is the state of the clocks right after the move.
+#[derive(Clone, Copy, Debug)]
+pub struct MoveRecord {
+    pub mv: Move,
+    pub chess_board: ChessBoard,
+    pub time_spent: Duration,
+    pub score: Option<Score>,
+    pub draw_offer: bool,
+    pub clock: Option<ChessClock>,
+}
+
+fn notify(observers: &mut [Box<dyn GameObserver>], event: &GameEvent) {
+    for observer in observers.iter_mut() {
+        observer.on_event(event);
+    }
+}
+
+/// Plays a game between two players.
+///
+/// Every move is kept in a record so that the game can be taken back with
+/// `undo`, replayed with `redo` or moved to any ply with `goto_ply`. Making
+/// a move after an undo drops the moves that could have been redone. Games
+/// ended by the players, the clock or an adjudication are over again once
+/// back at the ply they ended at.
 pub struct GameManager<'a, 'b> {
     chess_board: ChessBoard,
     player_white: &'a dyn Player,
     player_black: &'b dyn Player,
     game_status: ChessStatus,
-    round_number: u32,
+    game_over_reason: Option<GameOverReason>,
+    initial_board: ChessBoard,
+    initial_clock: Option<ChessClock>,
+    clock: Option<ChessClock>,
+    records: Vec<MoveRecord>,
+    // Ply, result and reason of an ending the board alone doesn't tell
+    ending: Option<(usize, ChessStatus, GameOverReason)>,
+    ply: usize,
+    draw_offer: Option<Color>,
+    observers: Vec<Box<dyn GameObserver>>,
 }
 
 impl<'a, 'b> GameManager<'a, 'b> {
     pub fn new(player_white: &'a dyn Player, player_black: &'b dyn Player) -> GameManager<'a, 'b> {
+        GameManager::with_position(player_white, player_black, ChessBoard::starting_position())
+    }
+
+    pub fn with_position(
+        player_white: &'a dyn Player,
+        player_black: &'b dyn Player,
+        chess_board: ChessBoard,
+    ) -> GameManager<'a, 'b> {
         GameManager {
-            chess_board: ChessBoard::starting_position(),
+            chess_board,
+            player_white,
+            player_black,
+            game_status: chess_board.get_game_status(),
+            game_over_reason: None,
+            initial_board: chess_board,
+            initial_clock: None,
+            clock: None,
+            records: Vec::new(),
+            ending: None,
+            ply: 0,
+            draw_offer: None,
+            observers: Vec::new(),
+        }
+    }
+
+    pub fn with_time_control(
+        player_white: &'a dyn Player,
+        player_black: &'b dyn Player,
+        time_control: TimeControl,
+    ) -> GameManager<'a, 'b> {
+        GameManager::with_position_and_time_control(
             player_white,
             player_black,
-            game_status: ChessStatus::Ongoing,
-            round_number: 0,
+            ChessBoard::starting_position(),
+            time_control,
+        )
+    }
+
+    pub fn with_position_and_time_control(
+        player_white: &'a dyn Player,
+        player_black: &'b dyn Player,
+        chess_board: ChessBoard,
+        time_control: TimeControl,
+    ) -> GameManager<'a, 'b> {
+        let clock = Some(ChessClock::new(time_control));
+
+        GameManager {
+            initial_clock: clock,
+            clock,
+            ..GameManager::with_position(player_white, player_black, chess_board)
         }
     }
 
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
+
     pub fn get_board(&self) -> ChessBoard {
         self.chess_board
     }
 
+    pub fn get_initial_board(&self) -> ChessBoard {
+        self.initial_board
+    }
+
     pub fn get_turn(&self) -> Color {
         self.chess_board.get_turn_color()
     }
 
+    pub fn get_clock(&self) -> Option<&ChessClock> {
+        self.clock.as_ref()
+    }
+
+    pub fn get_game_status(&self) -> ChessStatus {
+        self.game_status
+    }
+
+    pub fn get_game_over_reason(&self) -> Option<GameOverReason> {
+        self.game_over_reason
+    }
+
+    // Color of the player whose draw offer can be accepted
+    pub fn get_draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    pub fn get_ply(&self) -> usize {
+        self.ply
+    }
+
+    // Moves played up to the current ply
+    pub fn get_records(&self) -> &[MoveRecord] {
+        &self.records[..self.ply]
+    }
+
     pub fn is_game_ongoing(&self) -> bool {
         self.game_status == ChessStatus::Ongoing
     }
 
+    pub fn can_undo(&self) -> bool {
+        self.ply > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.ply < self.records.len()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        self.can_undo() && self.goto_ply(self.ply - 1)
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.can_redo() && self.goto_ply(self.ply + 1)
+    }
+
+    /// Moves to the position after `ply` moves, restoring the clocks as they
+    /// were. Returns false if the ply is beyond the recorded moves.
+    pub fn goto_ply(&mut self, ply: usize) -> bool {
+        if ply > self.records.len() {
+            return false;
+        }
+
+        (self.chess_board, self.clock) = match ply {
+            0 => (self.initial_board, self.initial_clock),
+            _ => {
+                let record = &self.records[ply - 1];
+                (record.chess_board.next_state(&record.mv), record.clock)
+            }
+        };
+
+        self.ply = ply;
+        self.draw_offer = (ply > 0 && self.records[ply - 1].draw_offer)
+            .then(|| self.records[ply - 1].chess_board.get_turn_color());
+        self.game_status = self.chess_board.get_game_status();
+        self.game_over_reason = match self.game_status {
+            ChessStatus::Ongoing => None,
+            ChessStatus::Draw => Some(GameOverReason::Stalemate),
+            ChessStatus::WhiteWon | ChessStatus::BlackWon => Some(GameOverReason::Checkmate),
+        };
+        if let Some((_, game_status, reason)) = self.ending.filter(|ending| ending.0 == ply) {
+            self.game_status = game_status;
+            self.game_over_reason = Some(reason);
+        }
+
+        notify(&mut self.observers, &GameEvent::PositionChanged { ply });
+        true
+    }
+
+    fn end_game(&mut self, game_status: ChessStatus, reason: GameOverReason) {
+        self.game_status = game_status;
+        self.game_over_reason = Some(reason);
+
+        // Nothing could have been played after the end
+        self.records.truncate(self.ply);
+        let on_board = matches!(
+            reason,
+            GameOverReason::Checkmate | GameOverReason::Stalemate
+        );
+        if !on_board {
+            self.ending = Some((self.ply, game_status, reason));
+        }
+
+        notify(
+            &mut self.observers,
+            &GameEvent::GameOver {
+                status: game_status,
+                reason,
+            },
+        );
+    }
+
+    // Ends the game with a result decided outside of it
+    pub fn adjudicate(&mut self, game_status: ChessStatus) {
+        self.end_game(game_status, GameOverReason::Adjudication);
+    }
+
     pub fn make_move(&mut self) {
-        let selected_move = match self.chess_board.get_turn_color() {
-            Color::White => self.player_white.select_move(&self.chess_board),
-            Color::Black => self.player_black.select_move(&self.chess_board),
+        if !self.is_game_ongoing() {
+            warn!("The game is over, no move is made");
+            return;
+        }
+
+        let color = self.chess_board.get_turn_color();
+        let player = match color {
+            Color::White => self.player_white,
+            Color::Black => self.player_black,
+        };
+
+        let records = self.get_records();
+        let history: Vec<ChessBoard> = records.iter().map(|record| record.chess_board).collect();
+
+        let context = GameContext {
+            chess_board: self.chess_board,
+            history: &history,
+            ply: self.ply,
+            game_status: self.game_status,
+            clock: self.clock.map(|clock| clock.to_clock_state(color)),
+            draw_offered: self.draw_offer == Some(color.opposite()),
+            opponent_score: records.last().and_then(|record| record.score),
+        };
+
+        let start_time = Instant::now();
+        let decision = player.decide(&context);
+        let time_spent = start_time.elapsed();
+
+        if let Some(clock) = self.clock.as_mut() {
+            // Only moves earn the bonus and count towards the period
+            let flagged = match decision.get_move() {
+                Some(_) => clock.charge(color, time_spent),
+                None => clock.spend(color, time_spent),
+            };
+            if flagged {
+                let game_status = flag_fall_status(&self.chess_board, color);
+                self.end_game(game_status, GameOverReason::Timeout);
+                return;
+            }
+        }
+
+        // A player that can't decide would be asked forever, so it loses
+        let legal = match decision {
+            Decision::Move(mv) | Decision::OfferDraw(mv) => {
+                self.chess_board.get_allowed_moves(color).contains(&mv)
+            }
+            Decision::AcceptDraw => context.draw_offered,
+            Decision::Resign => true,
+            Decision::Takeback => self.ply >= 2,
+        };
+        if !legal {
+            warn!("Illegal decision of {:?}: {:?}", color, decision);
+            self.end_game(
+                winner_status(color.opposite()),
+                GameOverReason::IllegalDecision,
+            );
+            return;
+        }
+
+        let (selected_move, draw_offer) = match decision {
+            Decision::Resign => {
+                self.end_game(winner_status(color.opposite()), GameOverReason::Resignation);
+                return;
+            }
+            Decision::AcceptDraw => {
+                self.end_game(ChessStatus::Draw, GameOverReason::DrawAgreement);
+                return;
+            }
+            Decision::Takeback => {
+                self.goto_ply(self.ply - 2);
+
+                // The clock comes back as it was, but not the time spent
+                // deciding on the takeback
+                let flagged = self
+                    .clock
+                    .as_mut()
+                    .is_some_and(|clock| clock.spend(color, time_spent));
+                if flagged {
+                    let game_status = flag_fall_status(&self.chess_board, color);
+                    self.end_game(game_status, GameOverReason::Timeout);
+                }
+                return;
+            }
+            Decision::OfferDraw(mv) => (mv, true),
+            Decision::Move(mv) => (mv, false),
         };
 
-        self.round_number += 1;
-        println!("Selected Move: {}", selected_move);
+        let record = MoveRecord {
+            mv: selected_move,
+            chess_board: self.chess_board,
+            time_spent,
+            score: player.get_last_score(),
+            draw_offer,
+            clock: self.clock,
+        };
 
+        self.records.truncate(self.ply);
+        self.records.push(record);
+        self.ending = None;
+        self.ply += 1;
         self.chess_board = self.chess_board.next_state(&selected_move);
+        self.draw_offer = if draw_offer { Some(color) } else { None };
+
+        notify(
+            &mut self.observers,
+            &GameEvent::MoveMade {
+                ply: self.ply - 1,
+                record: &self.records[self.ply - 1],
+            },
+        );
+
+        if draw_offer {
+            notify(&mut self.observers, &GameEvent::DrawOffered { color });
+        }
 
         // Check the game status
-        self.game_status = self.chess_board.get_game_status();
-        if self.game_status != ChessStatus::Ongoing {
-            println!("Game Over! Status: {:?}", self.game_status);
+        let game_status = self.chess_board.get_game_status();
+        match game_status {
+            ChessStatus::Ongoing => {
+                if self.chess_board.is_king_in_check(color.opposite()) {
+                    let check = GameEvent::Check {
+                        color: color.opposite(),
+                    };
+                    notify(&mut self.observers, &check);
+                }
+            }
+            ChessStatus::Draw => self.end_game(game_status, GameOverReason::Stalemate),
+            ChessStatus::WhiteWon | ChessStatus::BlackWon => {
+                self.end_game(game_status, GameOverReason::Checkmate)
+            }
         }
     }
 
     pub fn show(&self) {
-        println!("Round Number: {}", self.round_number);
+        println!("Round Number: {}", self.ply);
+        if let Some(clock) = &self.clock {
+            println!(
+                "White: {:?} Black: {:?}",
+                clock.get_remaining(Color::White),
+                clock.get_remaining(Color::Black)
+            );
+        }
         self.chess_board.print_board();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_server::io::utils::parse_uci_move;
+
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    // Plays the decisions it is given: moves in UCI, "accept", "resign",
+    // "takeback" or "offer" followed by a move
+    struct ScriptedPlayer {
+        decisions: RefCell<VecDeque<&'static str>>,
+    }
+
+    impl ScriptedPlayer {
+        fn new(decisions: &[&'static str]) -> ScriptedPlayer {
+            ScriptedPlayer {
+                decisions: RefCell::new(decisions.iter().copied().collect()),
+            }
+        }
+    }
+
+    impl Player for ScriptedPlayer {
+        fn decide(&self, context: &GameContext) -> Decision {
+            let decision = self.decisions.borrow_mut().pop_front().unwrap();
+            let parse = |mv: &str| parse_uci_move(&context.chess_board, mv).unwrap();
+
+            match decision.split_once(' ') {
+                Some(("offer", mv)) => Decision::OfferDraw(parse(mv)),
+                _ if decision == "accept" => Decision::AcceptDraw,
+                _ if decision == "resign" => Decision::Resign,
+                _ if decision == "takeback" => Decision::Takeback,
+                _ => Decision::Move(parse(decision)),
+            }
+        }
+    }
+
+    // Thinks for `delay` before each decision of `player`
+    struct SlowPlayer {
+        player: ScriptedPlayer,
+        delay: Duration,
+    }
+
+    impl Player for SlowPlayer {
+        fn decide(&self, context: &GameContext) -> Decision {
+            std::thread::sleep(self.delay);
+            self.player.decide(context)
+        }
+    }
+
+    // Always gives the same answer, whatever the position
+    struct StubbornPlayer {
+        decision: Decision,
+    }
+
+    impl Player for StubbornPlayer {
+        fn decide(&self, _context: &GameContext) -> Decision {
+            self.decision
+        }
+    }
+
+    // Plays the first allowed move, scored with its ply, and notes the
+    // context of every decision
+    struct NotingPlayer {
+        contexts: RefCell<Vec<(usize, ChessStatus, Option<Score>)>>,
+    }
+
+    impl Player for NotingPlayer {
+        fn decide(&self, context: &GameContext) -> Decision {
+            let mut contexts = self.contexts.borrow_mut();
+            contexts.push((context.ply, context.game_status, context.opponent_score));
+
+            let allowed_moves = context.chess_board.get_allowed_moves(context.get_turn_color());
+            Decision::Move(allowed_moves[0])
+        }
+
+        fn get_last_score(&self) -> Option<Score> {
+            let contexts = self.contexts.borrow();
+            contexts.last().map(|(ply, _, _)| Score::cp(*ply as i32))
+        }
+    }
+
+    #[test]
+    fn players_are_told_the_game_so_far() {
+        let white = NotingPlayer {
+            contexts: RefCell::new(Vec::new()),
+        };
+        let black = NotingPlayer {
+            contexts: RefCell::new(Vec::new()),
+        };
+        let mut game = GameManager::new(&white, &black);
+        for _ in 0..4 {
+            game.make_move();
+        }
+
+        let ongoing = ChessStatus::Ongoing;
+        let expected = [(0, ongoing, None), (2, ongoing, Some(Score::cp(1)))];
+        assert_eq!(*white.contexts.borrow(), expected);
+        let expected = [(1, ongoing, Some(Score::cp(0))), (3, ongoing, Some(Score::cp(2)))];
+        assert_eq!(*black.contexts.borrow(), expected);
+
+        let mated = ChessBoard::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        let context = GameContext::from_board(&mated);
+        assert_eq!((context.ply, context.game_status), (0, ChessStatus::WhiteWon));
+    }
+
+    #[test]
+    fn illegal_decisions_forfeit() {
+        let white = ScriptedPlayer::new(&["accept"]);
+        let black = ScriptedPlayer::new(&[]);
+        let mut game = GameManager::new(&white, &black);
+        game.make_move();
+        assert_eq!(game.get_ply(), 0);
+        assert_eq!(game.get_game_status(), ChessStatus::BlackWon);
+        assert_eq!(
+            game.get_game_over_reason(),
+            Some(GameOverReason::IllegalDecision)
+        );
+
+        // A move of the other side, or one from another position
+        let e4 = parse_uci_move(&ChessBoard::starting_position(), "e2e4").unwrap();
+        for decision in [Decision::Move(e4), Decision::OfferDraw(e4)] {
+            let white = ScriptedPlayer::new(&["e2e4"]);
+            let black = StubbornPlayer { decision };
+            let mut game = GameManager::new(&white, &black);
+            game.make_move();
+            game.make_move();
+            assert_eq!(game.get_ply(), 1);
+            assert_eq!(game.get_game_status(), ChessStatus::WhiteWon);
+            assert_eq!(
+                game.get_game_over_reason(),
+                Some(GameOverReason::IllegalDecision)
+            );
+        }
+    }
+
+    // Writes every event down in a few words
+    struct RecordingObserver {
+        events: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl GameObserver for RecordingObserver {
+        fn on_event(&mut self, event: &GameEvent) {
+            let event = match event {
+                GameEvent::MoveMade { ply, record } => format!("move {} {}", ply, record.mv),
+                GameEvent::Check { color } => format!("check {:?}", color),
+                GameEvent::DrawOffered { color } => format!("offer {:?}", color),
+                GameEvent::GameOver { status, reason } => format!("{:?} {:?}", status, reason),
+                GameEvent::PositionChanged { ply } => format!("ply {}", ply),
+            };
+            self.events.borrow_mut().push(event);
+        }
+    }
+
+    fn board_after(moves: &[&str]) -> ChessBoard {
+        moves
+            .iter()
+            .fold(ChessBoard::starting_position(), |chess_board, mv| {
+                chess_board.next_state(&parse_uci_move(&chess_board, mv).unwrap())
+            })
+    }
+
+    #[test]
+    fn undo_redo_and_goto_ply_replay_the_record() {
+        let white = ScriptedPlayer::new(&["e2e4", "offer g1f3"]);
+        let black = ScriptedPlayer::new(&["e7e5", "b8c6", "d7d5"]);
+        let time_control = TimeControl::fischer(Duration::from_secs(60), Duration::from_secs(1));
+        let mut game = GameManager::with_time_control(&white, &black, time_control);
+
+        for _ in 0..4 {
+            game.make_move();
+        }
+        let clock_at_three = game.get_records()[2].clock;
+
+        // The draw offered with g1f3 is pending again
+        assert!(game.undo());
+        assert_eq!(game.get_ply(), 3);
+        assert_eq!(game.get_board(), board_after(&["e2e4", "e7e5", "g1f3"]));
+        assert_eq!(game.get_draw_offer(), Some(Color::White));
+        assert!(game.can_redo());
+
+        assert!(game.redo());
+        assert_eq!(
+            game.get_board(),
+            board_after(&["e2e4", "e7e5", "g1f3", "b8c6"])
+        );
+        assert_eq!(game.get_draw_offer(), None);
+        assert!(!game.redo());
+
+        assert!(game.goto_ply(0));
+        assert_eq!(game.get_board(), ChessBoard::starting_position());
+        assert_eq!(game.get_draw_offer(), None);
+        assert!(!game.undo());
+        assert!(!game.goto_ply(5));
+
+        // Clocks come back as they were, and a new move drops the rest
+        assert!(game.goto_ply(3));
+        assert_eq!(game.get_draw_offer(), Some(Color::White));
+        assert_eq!(
+            game.get_clock().unwrap().get_remaining(Color::Black),
+            clock_at_three.unwrap().get_remaining(Color::Black)
+        );
+        game.make_move();
+        assert_eq!(game.get_ply(), 4);
+        assert_eq!(game.get_records()[3].mv.to_string(), "d7d5");
+        assert_eq!(game.get_draw_offer(), None);
+        assert!(!game.can_redo());
+    }
+
+    #[test]
+    fn takeback_gives_the_turn_back_to_the_player() {
+        let white = ScriptedPlayer::new(&["e2e4", "takeback", "d2d4"]);
+        let black = ScriptedPlayer::new(&["e7e5"]);
+        let mut game = GameManager::new(&white, &black);
+
+        for _ in 0..3 {
+            game.make_move();
+        }
+        assert_eq!(game.get_ply(), 0);
+        assert!(game.can_redo());
+        game.make_move();
+        assert_eq!(game.get_board(), board_after(&["d2d4"]));
+        assert!(!game.can_redo());
+
+        // Taking back restores the clocks, but deciding to still takes time
+        let white = SlowPlayer {
+            player: ScriptedPlayer::new(&["e2e4", "takeback"]),
+            delay: Duration::from_millis(20),
+        };
+        let black = ScriptedPlayer::new(&["e7e5"]);
+        let time_control = TimeControl::sudden_death(Duration::from_secs(60));
+        let mut game = GameManager::with_time_control(&white, &black, time_control);
+        for _ in 0..3 {
+            game.make_move();
+        }
+        assert_eq!(game.get_ply(), 0);
+        let remaining = game.get_clock().unwrap().get_remaining(Color::White);
+        assert!(remaining <= Duration::from_millis(59_980), "{:?}", remaining);
+        assert_eq!(
+            game.get_clock().unwrap().get_remaining(Color::Black),
+            Duration::from_secs(60)
+        );
+
+        // Nothing to take back yet
+        let white = ScriptedPlayer::new(&["e2e4"]);
+        let black = ScriptedPlayer::new(&["takeback"]);
+        let mut game = GameManager::new(&white, &black);
+        game.make_move();
+        game.make_move();
+        assert_eq!(game.get_game_status(), ChessStatus::WhiteWon);
+        assert_eq!(
+            game.get_game_over_reason(),
+            Some(GameOverReason::IllegalDecision)
+        );
+    }
+
+    #[test]
+    fn endings_off_the_board_come_back_with_redo() {
+        let white = ScriptedPlayer::new(&["e2e4"]);
+        let black = ScriptedPlayer::new(&["resign"]);
+        let mut game = GameManager::new(&white, &black);
+        game.make_move();
+        game.make_move();
+        assert_eq!(game.get_game_status(), ChessStatus::WhiteWon);
+
+        assert!(game.undo());
+        assert!(game.is_game_ongoing());
+        assert_eq!(game.get_game_over_reason(), None);
+        assert!(game.redo());
+        assert_eq!(game.get_game_status(), ChessStatus::WhiteWon);
+        assert_eq!(
+            game.get_game_over_reason(),
+            Some(GameOverReason::Resignation)
+        );
+
+        // The players aren't asked again once the game is over
+        game.make_move();
+        assert_eq!(game.get_ply(), 1);
+
+        let white = ScriptedPlayer::new(&["offer e2e4"]);
+        let black = ScriptedPlayer::new(&["accept"]);
+        let mut game = GameManager::new(&white, &black);
+        game.make_move();
+        game.make_move();
+        assert!(game.goto_ply(0));
+        assert!(game.is_game_ongoing());
+        assert!(game.goto_ply(1));
+        assert_eq!(
+            game.get_game_over_reason(),
+            Some(GameOverReason::DrawAgreement)
+        );
+
+        // Resigning after an undo drops the moves after it
+        let white = ScriptedPlayer::new(&["e2e4", "resign"]);
+        let black = ScriptedPlayer::new(&["e7e5"]);
+        let mut game = GameManager::new(&white, &black);
+        game.make_move();
+        game.make_move();
+        assert!(game.goto_ply(0));
+        game.make_move();
+        assert!(!game.can_redo());
+        assert_eq!(game.get_game_status(), ChessStatus::BlackWon);
+        assert_eq!(
+            game.get_game_over_reason(),
+            Some(GameOverReason::Resignation)
+        );
+
+        // So does adjudicating after an undo drops the moves after it
+        let white = ScriptedPlayer::new(&["e2e4"]);
+        let black = ScriptedPlayer::new(&["e7e5"]);
+        let mut game = GameManager::new(&white, &black);
+        game.make_move();
+        game.make_move();
+        assert!(game.undo());
+        game.adjudicate(ChessStatus::Draw);
+        assert!(!game.can_redo());
+        assert!(game.undo());
+        assert!(game.redo());
+        assert_eq!(
+            game.get_game_over_reason(),
+            Some(GameOverReason::Adjudication)
+        );
+
+        let white = ScriptedPlayer::new(&["e2e4"]);
+        let black = ScriptedPlayer::new(&[]);
+        let time_control = TimeControl::sudden_death(Duration::from_nanos(1));
+        let mut game = GameManager::with_time_control(&white, &black, time_control);
+        game.make_move();
+        assert_eq!(game.get_game_over_reason(), Some(GameOverReason::Timeout));
+        assert!(game.goto_ply(0));
+        assert_eq!(game.get_game_over_reason(), Some(GameOverReason::Timeout));
+    }
+
+    #[test]
+    fn observers_see_every_event() {
+        let white = ScriptedPlayer::new(&["offer e2e4", "d1h5"]);
+        let black = ScriptedPlayer::new(&["f7f6", "g7g6", "resign"]);
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut game = GameManager::new(&white, &black);
+        game.add_observer(Box::new(RecordingObserver {
+            events: events.clone(),
+        }));
+
+        for _ in 0..4 {
+            game.make_move();
+        }
+        game.undo();
+        game.make_move();
+        game.undo();
+        game.adjudicate(ChessStatus::Draw);
+
+        let expected = [
+            "move 0 e2e4",
+            "offer White",
+            "move 1 f7f6",
+            "move 2 d1h5",
+            "check Black",
+            "move 3 g7g6",
+            "ply 3",
+            "WhiteWon Resignation",
+            "ply 2",
+            "Draw Adjudication",
+        ];
+        assert_eq!(*events.borrow(), expected);
+    }
+}
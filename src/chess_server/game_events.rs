@@ -0,0 +1,73 @@
+use crate::chess_server::chess_types::{ChessStatus, Color};
+use crate::chess_server::game::MoveRecord;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameOverReason {
+    Checkmate,
+    Stalemate,
+    Resignation,
+    Timeout,
+    DrawAgreement,
+    // An illegal move, or a draw accepted without an offer, forfeits
+    IllegalDecision,
+    // Ended from outside the game, e.g. by a tournament
+    Adjudication,
+}
+
+/// Events sent by the `GameManager` to its observers.
+///
+/// `ply` is the number of moves played before the event, so a move made at
+/// ply 0 is the first move of the game.
+#[derive(Clone, Copy, Debug)]
+pub enum GameEvent<'a> {
+    MoveMade { ply: usize, record: &'a MoveRecord },
+    Check { color: Color },
+    DrawOffered { color: Color },
+    GameOver { status: ChessStatus, reason: GameOverReason },
+    // After an undo, a redo or a jump to another ply
+    PositionChanged { ply: usize },
+}
+
+pub trait GameObserver {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+/// Prints the game to stdout, as the `GameManager` used to do.
+pub struct ConsoleObserver;
+
+impl ConsoleObserver {
+    pub fn new() -> ConsoleObserver {
+        ConsoleObserver {}
+    }
+}
+
+impl GameObserver for ConsoleObserver {
+    fn on_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::MoveMade { ply, record } => {
+                // Games from a position may start with a move of black
+                let color = record.chess_board.get_turn_color();
+                let white_first = (ply % 2 == 0) == (color == Color::White);
+                let number = (ply + !white_first as usize) / 2 + 1;
+                let dots = match color {
+                    Color::White => ".",
+                    Color::Black => "...",
+                };
+
+                println!(
+                    "Selected Move: {}{} {} ({:.2}s)",
+                    number,
+                    dots,
+                    record.mv,
+                    record.time_spent.as_secs_f64()
+                )
+            }
+            GameEvent::Check { color } => println!("{:?} is in check", color),
+            GameEvent::DrawOffered { color } => println!("{:?} offers a draw", color),
+            GameEvent::GameOver { status, reason } => {
+                println!("Game Over! Status: {:?} ({:?})", status, reason)
+            }
+            GameEvent::PositionChanged { ply } => println!("Moved to ply {}", ply),
+        }
+    }
+}
@@ -1,4 +1,3 @@
-use crate::chess_server::chess_types::*;
 use crate::chess_server::game::*;
 
 use crate::chess_server::io::utils;
@@ -13,20 +12,35 @@ impl IOPlayer {
     }
 }
 
+// Besides moves, reads "resign", "draw" to accept a draw offer, a move
+// followed by "draw" to offer one and "undo" to take back the last move
 impl Player for IOPlayer {
-    fn select_move(&self, chess_board: &ChessBoard) -> Move {
-        let allowed_moves = chess_board.get_allowed_moves(chess_board.get_turn_color());
+    fn decide(&self, context: &GameContext) -> Decision {
+        let chess_board = &context.chess_board;
 
         loop {
             let mut s = String::new();
-            stdin().read_line(&mut s).expect("Crashed waiting for move");
+            let read = stdin().read_line(&mut s).expect("Crashed waiting for move");
 
-            let mv = utils::parse_move(&s);
+            // Nobody is left to play the moves
+            if read == 0 {
+                return Decision::Resign;
+            }
 
-            if allowed_moves.contains(&mv) {
-                return mv;
-            } else {
-                println!("Invalid move: {}", mv);
+            let words: Vec<&str> = s.split_whitespace().collect();
+
+            match words.as_slice() {
+                ["resign"] => return Decision::Resign,
+                ["draw"] if context.draw_offered => return Decision::AcceptDraw,
+                ["draw"] => println!("No draw offer to accept"),
+                ["undo"] if context.ply >= 2 => return Decision::Takeback,
+                ["undo"] => println!("No move to take back"),
+                [move_str] | [move_str, "draw"] => match utils::parse_uci_move(chess_board, move_str) {
+                    None => println!("Invalid move: {}", move_str),
+                    Some(mv) if words.len() == 2 => return Decision::OfferDraw(mv),
+                    Some(mv) => return Decision::Move(mv),
+                },
+                _ => println!("Invalid input: {}", s.trim()),
             }
         }
     }
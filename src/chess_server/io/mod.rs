@@ -1,2 +1,3 @@
 pub mod io_player;
+pub mod pgn;
 pub mod utils;
@@ -0,0 +1,115 @@
+use crate::chess_server::chess_types::{ChessBoard, ChessStatus, Color, Move};
+use crate::chess_server::game::GameManager;
+use crate::chess_server::io::utils::move_to_san;
+
+use std::fs::OpenOptions;
+use std::io::Write;
+
+const MAX_LINE_LENGTH: usize = 80;
+
+pub fn result_string(game_status: ChessStatus) -> &'static str {
+    match game_status {
+        ChessStatus::WhiteWon => "1-0",
+        ChessStatus::BlackWon => "0-1",
+        ChessStatus::Draw => "1/2-1/2",
+        ChessStatus::Ongoing => "*",
+    }
+}
+
+/// A game to be written in PGN.
+///
+/// The `FEN` and `SetUp` tags are added when the game doesn't start from
+/// the starting position.
+#[derive(Clone, Debug)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub initial_board: ChessBoard,
+    pub moves: Vec<Move>,
+    pub game_status: ChessStatus,
+}
+
+impl PgnGame {
+    pub fn new(initial_board: ChessBoard, moves: Vec<Move>, game_status: ChessStatus) -> PgnGame {
+        PgnGame {
+            tags: Vec::new(),
+            initial_board,
+            moves,
+            game_status,
+        }
+    }
+
+    // Moves up to the current ply of the game
+    pub fn from_game(game_manager: &GameManager) -> PgnGame {
+        PgnGame::new(
+            game_manager.get_initial_board(),
+            game_manager.get_records().iter().map(|record| record.mv).collect(),
+            game_manager.get_game_status(),
+        )
+    }
+
+    pub fn add_tag(&mut self, name: &str, value: &str) {
+        self.tags.push((name.to_owned(), value.to_owned()));
+    }
+
+    pub fn to_pgn_string(&self) -> String {
+        let result = result_string(self.game_status);
+        let mut pgn = String::new();
+
+        for (name, value) in self.tags.iter() {
+            pgn += &format!("[{} \"{}\"]\n", name, value.replace('"', "'"));
+        }
+        if self.initial_board != ChessBoard::starting_position() {
+            pgn += &format!("[FEN \"{}\"]\n[SetUp \"1\"]\n", self.initial_board.to_fen());
+        }
+        pgn += &format!("[Result \"{}\"]\n\n", result);
+
+        let mut tokens = Vec::with_capacity(self.moves.len() + 1);
+        let mut chess_board = self.initial_board;
+        let mut move_number = 1;
+
+        for (ply, mv) in self.moves.iter().enumerate() {
+            let san = move_to_san(&chess_board, mv);
+
+            match chess_board.get_turn_color() {
+                Color::White => tokens.push(format!("{}. {}", move_number, san)),
+                Color::Black if ply == 0 => tokens.push(format!("{}... {}", move_number, san)),
+                Color::Black => tokens.push(san),
+            }
+
+            if chess_board.get_turn_color() == Color::Black {
+                move_number += 1;
+            }
+            chess_board = chess_board.next_state(mv);
+        }
+        tokens.push(result.to_owned());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+                pgn += &line;
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line += &token;
+        }
+        pgn += &line;
+        pgn.push_str("\n\n");
+
+        pgn
+    }
+
+    // Games are appended so that a file can collect many of them
+    pub fn append_to_file(&self, path: &str) -> Result<(), String> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| format!("{}: {}", path, err))?;
+
+        file.write_all(self.to_pgn_string().as_bytes())
+            .map_err(|err| format!("{}: {}", path, err))
+    }
+}
@@ -1,16 +1,7 @@
 use crate::chess_server::chess_types::*;
 
-use rand::distributions::Alphanumeric;
-use rand::{thread_rng, Rng};
-
 use serde::Serialize;
 
-pub fn random_string(n: usize) -> String {
-    let string = thread_rng().sample_iter(&Alphanumeric).take(n).collect();
-
-    String::from_utf8(string).unwrap().to_lowercase()
-}
-
 #[derive(Serialize)]
 pub struct Row {
     pub board_string: String,
@@ -23,25 +14,26 @@ pub struct Row {
     pub target_eval: f64,
 }
 
+/// Order of the planes of `board_to_string`, 64 squares each from a8 to h1.
+pub const PIECE_PLANES: [ColorPiece; 12] = [
+    WHITE_PAWN,
+    WHITE_BISHOP,
+    WHITE_KNIGHT,
+    WHITE_ROOK,
+    WHITE_QUEEN,
+    WHITE_KING,
+    BLACK_PAWN,
+    BLACK_BISHOP,
+    BLACK_KNIGHT,
+    BLACK_ROOK,
+    BLACK_QUEEN,
+    BLACK_KING,
+];
+
 pub fn board_to_string(chess_board: &ChessBoard) -> String {
     let mut string = String::with_capacity(64 * 12);
 
-    let piece_order = [
-        WHITE_PAWN,
-        WHITE_BISHOP,
-        WHITE_KNIGHT,
-        WHITE_ROOK,
-        WHITE_QUEEN,
-        WHITE_ROOK,
-        BLACK_PAWN,
-        BLACK_BISHOP,
-        BLACK_KNIGHT,
-        BLACK_ROOK,
-        BLACK_QUEEN,
-        BLACK_KING,
-    ];
-
-    for piece in piece_order {
+    for piece in PIECE_PLANES {
         for square in 0..64 {
             let square = Square::from_index(square).unwrap();
             if chess_board.get_square_content(&square) == Some(piece) {
@@ -55,55 +47,145 @@ pub fn board_to_string(chess_board: &ChessBoard) -> String {
     string
 }
 
-pub fn parse_move(move_str: &str) -> Move {
-    let move_str: Vec<char> = move_str.trim().chars().collect();
-
-    assert!((move_str.len() == 4) || (move_str.len() == 5));
-
-    let parse_col = |ch: char| match ch {
-        'a' => 0,
-        'b' => 1,
-        'c' => 2,
-        'd' => 3,
-        'e' => 4,
-        'f' => 5,
-        'g' => 6,
-        'h' => 7,
-        _ => unreachable!(),
+/// Finds the legal move written in standard algebraic notation, e.g. `Nbd7`,
+/// `exd5`, `e8=Q+`. Castling can't be played on this board so it is never
+/// found.
+pub fn parse_san(chess_board: &ChessBoard, san: &str) -> Option<Move> {
+    let san = san.trim_end_matches(['+', '#', '!', '?']);
+    if san.starts_with("O-O") || san.starts_with("0-0") {
+        return None;
+    }
+
+    let (san, promotion) = match san.split_once('=') {
+        Some((san, piece)) => (san, Some(piece)),
+        None => (san, None),
     };
 
-    let parse_row = |ch: char| match ch {
-        '1' => 7,
-        '2' => 6,
-        '3' => 5,
-        '4' => 4,
-        '5' => 3,
-        '6' => 2,
-        '7' => 1,
-        '8' => 0,
-        _ => unreachable!(),
+    let promotion = match promotion {
+        None => None,
+        Some("Q") => Some(Piece::Queen),
+        Some("R") => Some(Piece::Rook),
+        Some("B") => Some(Piece::Bishop),
+        Some("N") => Some(Piece::Knight),
+        Some(_) => return None,
     };
 
-    let parse_piece = |ch: char| match ch {
-        'b' => Piece::Bishop,
-        'k' => Piece::Knight,
-        'r' => Piece::Rook,
-        'q' => Piece::Queen,
-        _ => unreachable!(),
+    let mut chars: Vec<char> = san.chars().filter(|&ch| ch != 'x').collect();
+
+    let piece = match chars.first()? {
+        'K' => Piece::King,
+        'Q' => Piece::Queen,
+        'R' => Piece::Rook,
+        'B' => Piece::Bishop,
+        'N' => Piece::Knight,
+        _ => Piece::Pawn,
     };
+    if piece != Piece::Pawn {
+        chars.remove(0);
+    }
 
-    let current_square =
-        Square::from_coordinates(parse_row(move_str[1]), parse_col(move_str[0])).unwrap();
+    if chars.len() < 2 {
+        return None;
+    }
+    let destination = chars.split_off(chars.len() - 2);
+    let next_square = Square::from_chess_notation([destination[0], destination[1]])?;
+
+    // What is left is the disambiguation, a file, a rank or both
+    let from_col = chars.iter().find_map(|ch| "abcdefgh".find(*ch));
+    let from_row = chars.iter().find_map(|ch| "87654321".find(*ch));
+
+    let color = chess_board.get_turn_color();
+    chess_board
+        .get_allowed_moves(color)
+        .into_iter()
+        .find(|mv| {
+            let (row, col) = mv.get_current_square().get_coordinates();
+            mv.get_next_square() == next_square
+                && mv.get_promotion_piece() == promotion
+                && chess_board
+                    .get_square_content(&mv.get_current_square())
+                    .is_some_and(|color_piece| color_piece.get_piece() == piece)
+                && from_col.is_none_or(|from_col| from_col == col as usize)
+                && from_row.is_none_or(|from_row| from_row == row as usize)
+        })
+}
 
-    let next_square =
-        Square::from_coordinates(parse_row(move_str[3]), parse_col(move_str[2])).unwrap();
+/// Finds the legal move written in long algebraic notation as in UCI, e.g.
+/// `e2e4` or `e7e8q`.
+pub fn parse_uci_move(chess_board: &ChessBoard, move_str: &str) -> Option<Move> {
+    let move_str = move_str.trim().to_lowercase();
 
-    {
-        if move_str.len() == 4 {
-            Move::new_normal_move(current_square, next_square)
-        } else {
-            let piece = parse_piece(move_str[4]);
-            Move::new_promotion_move(current_square, next_square, piece)
+    chess_board
+        .get_allowed_moves(chess_board.get_turn_color())
+        .into_iter()
+        .find(|mv| mv.to_string() == move_str)
+}
+
+/// Writes a legal move in standard algebraic notation, the inverse of
+/// `parse_san`.
+pub fn move_to_san(chess_board: &ChessBoard, mv: &Move) -> String {
+    let color = chess_board.get_turn_color();
+    let current_square = mv.get_current_square();
+    let next_square = mv.get_next_square();
+    let piece = chess_board
+        .get_square_content(&current_square)
+        .expect("No piece to move")
+        .get_piece();
+    let is_capture = chess_board.get_square_content(&next_square).is_some();
+
+    let mut san = String::new();
+
+    if piece == Piece::Pawn {
+        if is_capture {
+            san.push_str(&current_square.to_str()[..1]);
+        }
+    } else {
+        san.push_str(&piece.to_str().to_uppercase());
+
+        // Other pieces of the same kind that can go to the same square
+        let others: Vec<Square> = chess_board
+            .get_allowed_moves(color)
+            .into_iter()
+            .filter(|other| {
+                other.get_next_square() == next_square
+                    && other.get_current_square() != current_square
+                    && chess_board
+                        .get_square_content(&other.get_current_square())
+                        .is_some_and(|color_piece| color_piece.get_piece() == piece)
+            })
+            .map(|other| other.get_current_square())
+            .collect();
+
+        if !others.is_empty() {
+            let (row, col) = current_square.get_coordinates();
+            let square_str = current_square.to_str();
+
+            if others.iter().all(|other| other.get_coordinates().1 != col) {
+                san.push_str(&square_str[..1]);
+            } else if others.iter().all(|other| other.get_coordinates().0 != row) {
+                san.push_str(&square_str[1..]);
+            } else {
+                san.push_str(&square_str);
+            }
         }
     }
+
+    if is_capture {
+        san.push('x');
+    }
+    san.push_str(&next_square.to_str());
+
+    if let Some(promotion) = mv.get_promotion_piece() {
+        san.push('=');
+        san.push_str(&promotion.to_str().to_uppercase());
+    }
+
+    let next_board = chess_board.next_state(mv);
+    match next_board.get_game_status() {
+        ChessStatus::WhiteWon | ChessStatus::BlackWon => san.push('#'),
+        _ if next_board.is_king_in_check(color.opposite()) => san.push('+'),
+        _ => (),
+    }
+
+    san
 }
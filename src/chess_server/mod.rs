@@ -1,5 +1,7 @@
 pub mod chess_types;
+pub mod clock;
 pub mod game;
+pub mod game_events;
 pub mod io;
 
 // pub use chess_types::Color;
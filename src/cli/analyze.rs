@@ -0,0 +1,99 @@
+use crate::chess_server::chess_types::ChessBoard;
+use crate::chess_server::game::Player;
+use crate::chess_server::io::utils::move_to_san;
+use crate::engines::engine_traits::Evaluator;
+use crate::engines::evaluators::{
+    CaptureEvaluator, DynamicEvaluator, KingDangerEvaluator, KingSafetyEvaluator,
+    MaterialEvaluator, PawnStructureEvaluator, PestoEvaluator, PositionalEvaluator,
+    PressureEvaluator, StructureEvaluator,
+};
+
+use super::load_player;
+
+use clap::Args;
+
+use std::time::Instant;
+
+#[derive(Args, Debug)]
+pub struct AnalyzeArgs {
+    pub fen: String,
+
+    /// Bot name or path to a JSON engine config
+    #[arg(long, default_value = "pignite")]
+    pub bot: String,
+
+    /// Only shows the evaluations
+    #[arg(long)]
+    pub no_search: bool,
+}
+
+pub fn run(args: AnalyzeArgs) -> Result<(), String> {
+    let chess_board = ChessBoard::from_fen(&args.fen)?;
+    let color = chess_board.get_turn_color();
+
+    chess_board.print_board();
+    println!("{:?} to move, {:?}", color, chess_board.get_game_status());
+
+    let legal_moves: Vec<String> = chess_board
+        .get_allowed_moves(color)
+        .iter()
+        .map(|mv| move_to_san(&chess_board, mv))
+        .collect();
+    println!(
+        "Legal moves ({}): {}",
+        legal_moves.len(),
+        legal_moves.join(" ")
+    );
+
+    let evaluators: [(&str, Box<dyn Evaluator>); 10] = [
+        ("Material", Box::new(MaterialEvaluator::new())),
+        ("Positional", Box::new(PositionalEvaluator::new())),
+        ("Pressure", Box::new(PressureEvaluator::new())),
+        ("KingSafety", Box::new(KingSafetyEvaluator::new())),
+        ("KingDanger", Box::new(KingDangerEvaluator::new())),
+        ("Dynamic", Box::new(DynamicEvaluator::new())),
+        ("Structure", Box::new(StructureEvaluator::new())),
+        ("PawnStructure", Box::new(PawnStructureEvaluator::new())),
+        ("Pesto", Box::new(PestoEvaluator::new())),
+        (
+            "Capture(Pesto)",
+            Box::new(CaptureEvaluator::new(PestoEvaluator::new())),
+        ),
+    ];
+
+    // Evaluations are from the point of view of white
+    for (name, evaluator) in evaluators.iter() {
+        println!(
+            "{:<16} {:>8.3}",
+            name,
+            evaluator.evaluate_white(&chess_board).to_pawns()
+        );
+    }
+
+    if args.no_search || chess_board.get_allowed_moves(color).is_empty() {
+        return Ok(());
+    }
+
+    let bot = load_player(&args.bot)?;
+    let start_time = Instant::now();
+    let mv = bot.select_move(&chess_board);
+    let elapsed = start_time.elapsed();
+
+    let score = match bot.get_last_score() {
+        Some(score) => {
+            let [win, draw, loss] = score.wdl();
+            format!("{} (wdl {}/{}/{})", score, win, draw, loss)
+        }
+        None => "-".to_owned(),
+    };
+    println!(
+        "{}: {} ({}), score {}, {:.2?}",
+        args.bot,
+        move_to_san(&chess_board, &mv),
+        mv,
+        score,
+        elapsed
+    );
+
+    Ok(())
+}
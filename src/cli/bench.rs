@@ -0,0 +1,49 @@
+use crate::chess_server::chess_types::ChessBoard;
+use crate::chess_server::game::Player;
+
+use super::load_player;
+
+use clap::Args;
+
+use std::time::{Duration, Instant};
+
+// Openings, middlegames and endgames without castling rights, which this
+// board doesn't play
+const BENCH_POSITIONS: [&str; 8] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w - - 4 4",
+    "r2q1rk1/pp2bppp/2n1pn2/3p4/3P4/2NBPN2/PP3PPP/R2Q1RK1 w - - 0 10",
+    "2rq1rk1/pb1nbppp/1p2pn2/2pp4/3P4/1PN1PN2/PBQ1BPPP/3R1RK1 b - - 0 12",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+    "8/8/4k3/8/2K5/8/3P4/8 w - - 0 1",
+];
+
+#[derive(Args, Debug)]
+pub struct BenchArgs {
+    /// Bot name or path to a JSON engine config. Bots searching to a fixed
+    /// depth give comparable times.
+    #[arg(long, default_value = "weedle")]
+    pub bot: String,
+}
+
+pub fn run(args: BenchArgs) -> Result<(), String> {
+    let bot = load_player(&args.bot)?;
+    let mut total = Duration::ZERO;
+
+    for fen in BENCH_POSITIONS {
+        let chess_board = ChessBoard::from_fen(fen)?;
+
+        let start_time = Instant::now();
+        let mv = bot.select_move(&chess_board);
+        let elapsed = start_time.elapsed();
+        total += elapsed;
+
+        println!("{:<6} {:>10.2?}  {}", mv.to_string(), elapsed, fen);
+    }
+
+    println!("Total: {:.2?}", total);
+
+    Ok(())
+}
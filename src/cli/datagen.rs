@@ -0,0 +1,182 @@
+use crate::chess_server::chess_types::{ChessBoard, ChessStatus};
+use crate::chess_server::clock::TimeControl;
+use crate::chess_server::game::GameManager;
+use crate::chess_server::io::pgn::result_string;
+use crate::chess_server::io::utils::{board_to_string, Row};
+use crate::engines::engine_traits::Evaluator;
+use crate::engines::evaluators::{
+    CacheEvaluator, CaptureEvaluator, DynamicEvaluator, KingSafetyEvaluator, LinearEvaluator,
+    MaterialEvaluator, PositionalEvaluator, PressureEvaluator, TrivialEvaluator,
+};
+use crate::engines::searchers::DeepSearch;
+use crate::tournament::OpeningBook;
+
+use super::{load_player, parse_time_control};
+
+use clap::Args;
+use csv::{Writer, WriterBuilder};
+use log::info;
+
+use std::fs::File;
+use std::io::Write;
+
+#[derive(Args, Debug)]
+pub struct DatagenArgs {
+    /// CSV file the evaluations of the positions are written to
+    #[arg(long, required_unless_present = "labels")]
+    pub output: Option<String>,
+
+    /// File the positions are written to with the result of their game, as
+    /// read by the tuner
+    #[arg(long)]
+    pub labels: Option<String>,
+
+    #[arg(long, default_value_t = 1)]
+    pub games: usize,
+
+    /// Bot name or path to a JSON engine config
+    #[arg(long, default_value = "pignite")]
+    pub white: String,
+
+    /// Bot name or path to a JSON engine config
+    #[arg(long, default_value = "tepig")]
+    pub black: String,
+
+    /// Depth of the search giving the target evaluation
+    #[arg(long, default_value_t = 4)]
+    pub depth: usize,
+
+    /// Time control in seconds, e.g. 60+1, 300d5 or 40/5400+30
+    #[arg(long, value_parser = parse_time_control)]
+    pub time_control: Option<TimeControl>,
+
+    /// FEN, EPD or PGN file of openings, played in turn
+    #[arg(long)]
+    pub openings: Option<String>,
+
+    /// Games are stopped after this many plies
+    #[arg(long, default_value_t = 400)]
+    pub max_plies: usize,
+}
+
+/// Evaluations written for every position, the features and the target.
+struct RowWriter {
+    dynamic_eval: DynamicEvaluator,
+    king_safety_eval: KingSafetyEvaluator,
+    material_eval: MaterialEvaluator,
+    positional_eval: PositionalEvaluator,
+    pressure_eval: PressureEvaluator,
+    capture_eval: CaptureEvaluator<TrivialEvaluator>,
+    target_search: DeepSearch<TargetEvaluator>,
+    target_eval: TargetEvaluator,
+    writer: Writer<File>,
+}
+
+type TargetEvaluator =
+    CacheEvaluator<CaptureEvaluator<LinearEvaluator<MaterialEvaluator, PressureEvaluator>>>;
+
+impl RowWriter {
+    fn new(path: &str, depth: usize) -> Result<RowWriter, String> {
+        Ok(RowWriter {
+            dynamic_eval: DynamicEvaluator::new(),
+            king_safety_eval: KingSafetyEvaluator::new(),
+            material_eval: MaterialEvaluator::new(),
+            positional_eval: PositionalEvaluator::new(),
+            pressure_eval: PressureEvaluator::new(),
+            capture_eval: CaptureEvaluator::new(TrivialEvaluator::new()),
+            target_search: DeepSearch::new(depth),
+            target_eval: CacheEvaluator::new(CaptureEvaluator::new(LinearEvaluator::new(
+                MaterialEvaluator::new(),
+                PressureEvaluator::new(),
+                [1.0, 0.01],
+            ))),
+            writer: WriterBuilder::new()
+                .from_path(path)
+                .map_err(|err| format!("{}: {}", path, err))?,
+        })
+    }
+
+    fn write(&mut self, chess_board: &ChessBoard) -> Result<(), String> {
+        let row = Row {
+            board_string: board_to_string(chess_board),
+            dynamic_eval: self.dynamic_eval.evaluate_pawns(chess_board),
+            king_safety_eval: self.king_safety_eval.evaluate_pawns(chess_board),
+            material_eval: self.material_eval.evaluate_pawns(chess_board),
+            positional_eval: self.positional_eval.evaluate_pawns(chess_board),
+            pressure_eval: self.pressure_eval.evaluate_pawns(chess_board),
+            capture_eval: self.capture_eval.evaluate_white(chess_board).to_pawns(),
+            target_eval: self
+                .target_search
+                .search_ext(chess_board, &self.target_eval)
+                .0
+                .to_pawns(),
+        };
+
+        self.writer.serialize(row).map_err(|err| err.to_string())
+    }
+}
+
+pub fn run(args: DatagenArgs) -> Result<(), String> {
+    let player_white = load_player(&args.white)?;
+    let player_black = load_player(&args.black)?;
+    let openings = match &args.openings {
+        Some(path) => OpeningBook::from_file(path)?,
+        None => OpeningBook::starting_position(),
+    };
+
+    let mut row_writer = match &args.output {
+        Some(path) => Some(RowWriter::new(path, args.depth)?),
+        None => None,
+    };
+    let mut label_file = match &args.labels {
+        Some(path) => Some(File::create(path).map_err(|err| format!("{}: {}", path, err))?),
+        None => None,
+    };
+    let mut positions = 0;
+
+    for game in 0..args.games {
+        let chess_board = openings.get(game).chess_board;
+        let mut game_manager = match args.time_control {
+            Some(time_control) => GameManager::with_position_and_time_control(
+                &player_white,
+                &player_black,
+                chess_board,
+                time_control,
+            ),
+            None => GameManager::with_position(&player_white, &player_black, chess_board),
+        };
+
+        let mut fens = Vec::new();
+        while game_manager.is_game_ongoing() && game_manager.get_ply() < args.max_plies {
+            if let Some(row_writer) = row_writer.as_mut() {
+                row_writer.write(&game_manager.get_board())?;
+            }
+            fens.push(game_manager.get_board().to_fen());
+            positions += 1;
+
+            game_manager.make_move();
+        }
+
+        let status = game_manager.get_game_status();
+        info!("Game {} over: {:?}", game + 1, status);
+
+        if let Some(row_writer) = row_writer.as_mut() {
+            row_writer.writer.flush().map_err(|err| err.to_string())?;
+        }
+
+        // Unfinished games count as draws
+        if let Some(label_file) = label_file.as_mut() {
+            let result = match status {
+                ChessStatus::Ongoing => result_string(ChessStatus::Draw),
+                status => result_string(status),
+            };
+            for fen in fens {
+                writeln!(label_file, "{}; {}", fen, result).map_err(|err| err.to_string())?;
+            }
+        }
+    }
+
+    println!("{} positions written", positions);
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use crate::chess_server::chess_types::chess_board::SquareContainer;
+use crate::chess_server::chess_types::{ChessBoard, Color};
+use crate::engines::attack_map::AttackMap;
+use crate::engines::engine_config::{build_evaluator, EvaluatorConfig};
+
+use clap::Args;
+
+use std::fs;
+
+#[derive(Args, Debug)]
+pub struct ExplainArgs {
+    pub fen: String,
+
+    /// Evaluator config as JSON, or path to a JSON file holding one
+    #[arg(long, default_value = r#"{"capture": {"evaluator": "pesto"}}"#)]
+    pub evaluator: String,
+}
+
+pub fn run(args: ExplainArgs) -> Result<(), String> {
+    let chess_board = ChessBoard::from_fen(&args.fen)?;
+
+    let text = if args.evaluator.ends_with(".json") {
+        fs::read_to_string(&args.evaluator).map_err(|err| format!("{}: {}", args.evaluator, err))?
+    } else {
+        args.evaluator.clone()
+    };
+    let config: EvaluatorConfig = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+    let evaluator = build_evaluator(&config)?;
+
+    chess_board.print_board();
+
+    // Evaluations are from the point of view of white
+    print!("{}", evaluator.explain(&chess_board).to_table());
+
+    let attack_map = AttackMap::new(&chess_board);
+    let squares = |squares: SquareContainer| {
+        let names: Vec<String> = squares.iter().map(|square| square.to_str()).collect();
+        if names.is_empty() {
+            "-".to_string()
+        } else {
+            names.join(" ")
+        }
+    };
+
+    for color in [Color::White, Color::Black] {
+        println!(
+            "{:?}: hanging {}, attacked by cheaper pieces {}, {} squares controlled",
+            color,
+            squares(attack_map.hanging_pieces(color)),
+            squares(attack_map.attacked_by_lower_value(color)),
+            attack_map.controlled_squares(color).count_ones()
+        );
+    }
+
+    Ok(())
+}
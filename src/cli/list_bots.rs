@@ -0,0 +1,20 @@
+use crate::engines::bots::BOTS;
+
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct ListBotsArgs {}
+
+// From the strongest, as in the registry
+pub fn run(_args: ListBotsArgs) -> Result<(), String> {
+    for bot in BOTS.iter() {
+        println!(
+            "{} (~{} Elo, {:?} and slower)",
+            bot.name, bot.expected_elo, bot.fastest_time_control
+        );
+        println!("    {}", bot.description);
+        println!("    {} with {}", bot.searcher, bot.evaluator);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use crate::chess_server::clock::TimeControl;
+use crate::tournament::{
+    OpeningBook, RatingLadder, RatingSystem, SprtConfig, Tournament, TournamentConfig,
+    TournamentFormat,
+};
+
+use super::{load_participant, parse_time_control};
+
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct MatchArgs {
+    /// Bot names or paths to JSON engine configs
+    #[arg(required = true, num_args = 2..)]
+    pub bots: Vec<String>,
+
+    /// The first bot plays every other one instead of a round robin
+    #[arg(long)]
+    pub gauntlet: bool,
+
+    /// Number of openings played by every pairing
+    #[arg(long, default_value_t = 1)]
+    pub rounds: usize,
+
+    /// Plays every opening once instead of once with each color
+    #[arg(long)]
+    pub no_swap: bool,
+
+    #[arg(long, default_value_t = 1)]
+    pub threads: usize,
+
+    /// Time control in seconds, e.g. 10+0.1, 300d5 or 40/5400+30
+    #[arg(long, value_parser = parse_time_control)]
+    pub time_control: Option<TimeControl>,
+
+    /// FEN, EPD or PGN file of openings
+    #[arg(long)]
+    pub openings: Option<String>,
+
+    /// Game length after which the game is a draw, 0 for no limit
+    #[arg(long)]
+    pub max_plies: Option<usize>,
+
+    /// Tests the first bot against the second with an SPRT
+    #[arg(long)]
+    pub sprt: bool,
+
+    #[arg(long, default_value_t = 0., requires = "sprt")]
+    pub elo0: f64,
+
+    #[arg(long, default_value_t = 10., requires = "sprt")]
+    pub elo1: f64,
+
+    #[arg(long, default_value_t = 0.05, requires = "sprt")]
+    pub alpha: f64,
+
+    #[arg(long, default_value_t = 0.05, requires = "sprt")]
+    pub beta: f64,
+
+    #[arg(long, requires = "sprt")]
+    pub max_pairs: Option<usize>,
+
+    /// Adds the games to this ladder file and prints the leaderboard
+    #[arg(long, conflicts_with = "sprt")]
+    pub ladder: Option<String>,
+}
+
+pub fn run(args: MatchArgs) -> Result<(), String> {
+    let participants = args
+        .bots
+        .iter()
+        .map(|bot| load_participant(bot))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if args.sprt && participants.len() != 2 {
+        return Err("SPRT needs exactly two bots".to_owned());
+    }
+
+    let openings = match &args.openings {
+        Some(path) => OpeningBook::from_file(path)?,
+        None => OpeningBook::starting_position(),
+    };
+
+    let mut config = TournamentConfig {
+        format: match args.gauntlet {
+            true => TournamentFormat::Gauntlet,
+            false => TournamentFormat::RoundRobin,
+        },
+        rounds: args.rounds,
+        swap_colors: !args.no_swap,
+        threads: args.threads,
+        time_control: args.time_control,
+        ..TournamentConfig::default()
+    };
+    if let Some(max_plies) = args.max_plies {
+        config.adjudication.max_plies = Some(max_plies).filter(|&max_plies| max_plies > 0);
+    }
+
+    let tournament = Tournament::new(participants, openings, config);
+
+    if args.sprt {
+        let mut sprt = SprtConfig::new(args.elo0, args.elo1, args.alpha, args.beta);
+        sprt.max_pairs = args.max_pairs;
+
+        let results = tournament.run_sprt(sprt)?;
+        println!("{}", results);
+        return Ok(());
+    }
+
+    let results = tournament.run();
+    println!("{}", results);
+
+    if let Some(path) = args.ladder {
+        let mut ladder = RatingLadder::load(&path)?;
+        ladder.add_tournament(&results);
+        ladder.save(&path)?;
+
+        println!("{}", ladder.leaderboard(RatingSystem::Elo)?);
+        println!("{}", ladder.leaderboard(RatingSystem::Glicko2)?);
+    }
+
+    Ok(())
+}
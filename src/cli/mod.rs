@@ -0,0 +1,225 @@
+pub mod analyze;
+pub mod bench;
+pub mod datagen;
+pub mod explain;
+pub mod list_bots;
+pub mod match_command;
+pub mod perft;
+pub mod play;
+pub mod selfplay;
+pub mod tune;
+pub mod uci;
+
+use crate::chess_server::chess_types::{ChessBoard, Color};
+use crate::chess_server::clock::TimeControl;
+use crate::engines::bots::{bot_names, create_bot, Pokemon};
+use crate::engines::engine_config::BotConfig;
+use crate::tournament::Participant;
+
+use clap::{Parser, Subcommand};
+use log::LevelFilter;
+
+use std::time::Duration;
+
+/// Nothing is written to disk unless a path is given, e.g. with `--log-file`
+/// or `--pgn`.
+#[derive(Parser, Debug)]
+#[command(name = "chess", about = "Chess bots, and the tools to play and test them")]
+pub struct Cli {
+    /// Writes the log to this file instead of stderr
+    #[arg(long, global = true)]
+    pub log_file: Option<String>,
+
+    /// One of off, error, warn, info, debug and trace
+    #[arg(long, global = true, default_value = "warn")]
+    pub log_level: LevelFilter,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Plays against a bot, moves are read from stdin
+    Play(play::PlayArgs),
+    /// Plays games between two bots and shows them
+    Selfplay(selfplay::SelfplayArgs),
+    /// Runs a tournament or an SPRT between bots
+    Match(match_command::MatchArgs),
+    /// Shows the evaluations of a position and the move of a bot
+    Analyze(analyze::AnalyzeArgs),
+    /// Breaks the evaluation of a position down into its terms
+    Explain(explain::ExplainArgs),
+    /// Counts the leaf nodes of the move generation tree
+    Perft(perft::PerftArgs),
+    /// Times a bot on a fixed set of positions
+    Bench(bench::BenchArgs),
+    /// Speaks the UCI protocol on stdin and stdout
+    Uci(uci::UciArgs),
+    /// Plays games between two bots and writes evaluations of every position
+    Datagen(datagen::DatagenArgs),
+    /// Fits the PeSTO parameters to labeled positions
+    Tune(tune::TuneArgs),
+    /// Lists the bots of the registry with what is known about them
+    ListBots(list_bots::ListBotsArgs),
+}
+
+pub fn run(cli: Cli) -> Result<(), String> {
+    init_logging(cli.log_file.as_deref(), cli.log_level)?;
+
+    match cli.command {
+        Command::Play(args) => play::run(args),
+        Command::Selfplay(args) => selfplay::run(args),
+        Command::Match(args) => match_command::run(args),
+        Command::Analyze(args) => analyze::run(args),
+        Command::Explain(args) => explain::run(args),
+        Command::Perft(args) => perft::run(args),
+        Command::Bench(args) => bench::run(args),
+        Command::Uci(args) => uci::run(args),
+        Command::Datagen(args) => datagen::run(args),
+        Command::Tune(args) => tune::run(args),
+        Command::ListBots(args) => list_bots::run(args),
+    }
+}
+
+// The log goes to stderr by default so that it never mixes with the UCI
+// output
+fn init_logging(log_file: Option<&str>, level: LevelFilter) -> Result<(), String> {
+    match log_file {
+        Some(path) => {
+            simple_logging::log_to_file(path, level).map_err(|err| format!("{}: {}", path, err))
+        }
+        None => {
+            env_logger::Builder::new().filter_level(level).init();
+            Ok(())
+        }
+    }
+}
+
+/// A bot of the registry by name, or a JSON engine config by path.
+pub fn load_player(spec: &str) -> Result<Pokemon, String> {
+    if spec.ends_with(".json") {
+        return BotConfig::from_file(spec)?.build();
+    }
+
+    create_bot(spec).ok_or_else(|| unknown_bot(spec))
+}
+
+pub fn load_participant(spec: &str) -> Result<Participant, String> {
+    if spec.ends_with(".json") {
+        return Participant::from_config(BotConfig::from_file(spec)?);
+    }
+
+    Participant::from_name(spec).ok_or_else(|| unknown_bot(spec))
+}
+
+fn unknown_bot(name: &str) -> String {
+    format!("Unknown bot {}, expected one of {}", name, bot_names().join(", "))
+}
+
+// Far longer than any game, and short enough for the clock arithmetic not
+// to overflow
+const MAX_TIME: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Parses a time control in seconds: `60` for sudden death, `180+2` for a
+/// Fischer increment, `300d5` for a simple delay and `300b5` for a Bronstein
+/// delay. A `moves/` prefix, as in `40/5400+30`, gives the base time again
+/// every that many moves.
+pub fn parse_time_control(time_control: &str) -> Result<TimeControl, String> {
+    let invalid = || format!("Invalid time control: {}", time_control);
+    let seconds = |value: &str| {
+        value
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(|value| Duration::try_from_secs_f64(value).ok())
+            .filter(|time| *time <= MAX_TIME)
+            .ok_or_else(invalid)
+    };
+
+    let (moves, clock) = match time_control.split_once('/') {
+        Some((moves, clock)) => {
+            let moves = moves.trim().parse::<u32>().ok().filter(|moves| *moves > 0);
+            (Some(moves.ok_or_else(invalid)?), clock)
+        }
+        None => (None, time_control),
+    };
+
+    let parsed = if let Some((base, increment)) = clock.split_once('+') {
+        TimeControl::fischer(seconds(base)?, seconds(increment)?)
+    } else if let Some((base, delay)) = clock.split_once('d') {
+        TimeControl::simple_delay(seconds(base)?, seconds(delay)?)
+    } else if let Some((base, delay)) = clock.split_once('b') {
+        TimeControl::bronstein(seconds(base)?, seconds(delay)?)
+    } else {
+        TimeControl::sudden_death(seconds(clock)?)
+    };
+
+    Ok(match moves {
+        Some(moves) => TimeControl {
+            bonus: parsed.bonus,
+            ..TimeControl::moves_per_period(moves, parsed.base_time)
+        },
+        None => parsed,
+    })
+}
+
+pub fn parse_position(fen: Option<&str>) -> Result<ChessBoard, String> {
+    match fen {
+        Some(fen) => ChessBoard::from_fen(fen),
+        None => Ok(ChessBoard::starting_position()),
+    }
+}
+
+pub fn parse_color(color: &str) -> Result<Color, String> {
+    match color.to_lowercase().as_str() {
+        "white" | "w" => Ok(Color::White),
+        "black" | "b" => Ok(Color::Black),
+        _ => Err(format!("Invalid color: {}", color)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_server::clock::TimeBonus;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn parses_every_kind_of_time_control() {
+        let parsed = |time_control| parse_time_control(time_control).unwrap();
+
+        assert_eq!(parsed("60"), TimeControl::sudden_death(secs(60)));
+        assert_eq!(parsed("180+2"), TimeControl::fischer(secs(180), secs(2)));
+        assert_eq!(parsed("300d5"), TimeControl::simple_delay(secs(300), secs(5)));
+        assert_eq!(parsed("300b5"), TimeControl::bronstein(secs(300), secs(5)));
+        assert_eq!(
+            parsed("0.5+0.25"),
+            TimeControl::fischer(Duration::from_millis(500), Duration::from_millis(250))
+        );
+        assert_eq!(
+            parsed("40/5400+30"),
+            TimeControl {
+                base_time: secs(5400),
+                bonus: TimeBonus::Fischer(secs(30)),
+                moves_per_period: Some(40),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_and_overflowing_time_controls() {
+        let rejected = [
+            "", "abc", "60+", "+2", "60+x", "-5", "NaN", "inf", "0/60", "x/60", "40/", "60d5b3",
+            // Too long for a clock, or for a Duration
+            "100000000", "1e30", "60+1e30",
+        ];
+
+        for time_control in rejected {
+            assert!(parse_time_control(time_control).is_err(), "{}", time_control);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::chess_server::chess_types::ChessBoard;
+
+use super::parse_position;
+
+use clap::Args;
+
+use std::time::Instant;
+
+#[derive(Args, Debug)]
+pub struct PerftArgs {
+    pub depth: usize,
+
+    /// Starting position
+    #[arg(long)]
+    pub fen: Option<String>,
+
+    /// Shows the count after every legal move
+    #[arg(long)]
+    pub divide: bool,
+}
+
+// Castling and en passant aren't played on this board, so counts differ from
+// the usual tables once they become possible
+pub fn perft(chess_board: &ChessBoard, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let allowed_moves = chess_board.get_allowed_moves(chess_board.get_turn_color());
+    if depth == 1 {
+        return allowed_moves.len() as u64;
+    }
+
+    allowed_moves
+        .iter()
+        .map(|mv| perft(&chess_board.next_state(mv), depth - 1))
+        .sum()
+}
+
+pub fn run(args: PerftArgs) -> Result<(), String> {
+    let chess_board = parse_position(args.fen.as_deref())?;
+    let start_time = Instant::now();
+
+    let nodes = if args.divide && args.depth > 0 {
+        let mut nodes = 0;
+        for mv in chess_board.get_allowed_moves(chess_board.get_turn_color()) {
+            let count = perft(&chess_board.next_state(&mv), args.depth - 1);
+            println!("{}: {}", mv, count);
+            nodes += count;
+        }
+        nodes
+    } else {
+        perft(&chess_board, args.depth)
+    };
+
+    let elapsed = start_time.elapsed();
+    println!(
+        "Nodes: {}, time: {:.2?}, nps: {:.0}",
+        nodes,
+        elapsed,
+        nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+
+    Ok(())
+}
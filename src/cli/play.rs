@@ -0,0 +1,102 @@
+use crate::chess_server::chess_types::Color;
+use crate::chess_server::clock::TimeControl;
+use crate::chess_server::game::{GameManager, Player};
+use crate::chess_server::game_events::ConsoleObserver;
+use crate::chess_server::io::io_player::IOPlayer;
+use crate::chess_server::io::pgn::PgnGame;
+
+use super::{load_player, parse_color, parse_position, parse_time_control};
+
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct PlayArgs {
+    /// Bot name or path to a JSON engine config
+    #[arg(long, default_value = "pignite")]
+    pub bot: String,
+
+    /// Color played by the human
+    #[arg(long, default_value = "white", value_parser = parse_color)]
+    pub color: Color,
+
+    /// Time control in seconds, e.g. 300+3, 300d5 or 40/5400+30
+    #[arg(long, value_parser = parse_time_control)]
+    pub time_control: Option<TimeControl>,
+
+    /// Starting position
+    #[arg(long)]
+    pub fen: Option<String>,
+
+    /// Appends the game to this PGN file
+    #[arg(long)]
+    pub pgn: Option<String>,
+
+    /// Only prints the moves of the bot, for programs like the Python GUI
+    #[arg(long)]
+    pub quiet: bool,
+}
+
+pub fn run(args: PlayArgs) -> Result<(), String> {
+    let bot = load_player(&args.bot)?;
+    let human = IOPlayer::new();
+    let chess_board = parse_position(args.fen.as_deref())?;
+
+    let (player_white, player_black): (&dyn Player, &dyn Player) = match args.color {
+        Color::White => (&human, &bot),
+        Color::Black => (&bot, &human),
+    };
+
+    let mut game_manager = match args.time_control {
+        Some(time_control) => GameManager::with_position_and_time_control(
+            player_white,
+            player_black,
+            chess_board,
+            time_control,
+        ),
+        None => GameManager::with_position(player_white, player_black, chess_board),
+    };
+    if !args.quiet {
+        game_manager.add_observer(Box::new(ConsoleObserver::new()));
+        println!(
+            "Moves are written like e2e4 or e7e8q, \"resign\", \"draw\" and \"undo\" are accepted"
+        );
+    }
+
+    while game_manager.is_game_ongoing() {
+        let human_turn = game_manager.get_turn() == args.color;
+        if human_turn && !args.quiet {
+            game_manager.show();
+            // Also after an undo back to a move that offered one
+            if game_manager.get_draw_offer().is_some() {
+                println!("The draw offer stands, \"draw\" accepts it");
+            }
+            println!("Your move:");
+        }
+
+        game_manager.make_move();
+
+        if !human_turn && args.quiet {
+            if let Some(record) = game_manager.get_records().last() {
+                println!("{}", record.mv);
+            }
+        }
+    }
+
+    if !args.quiet {
+        game_manager.show();
+    }
+
+    if let Some(path) = args.pgn {
+        let (white, black) = match args.color {
+            Color::White => ("Human", args.bot.as_str()),
+            Color::Black => (args.bot.as_str(), "Human"),
+        };
+
+        let mut pgn_game = PgnGame::from_game(&game_manager);
+        pgn_game.add_tag("White", white);
+        pgn_game.add_tag("Black", black);
+        pgn_game.append_to_file(&path)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use crate::chess_server::chess_types::ChessStatus;
+use crate::chess_server::clock::TimeControl;
+use crate::chess_server::game::GameManager;
+use crate::chess_server::game_events::ConsoleObserver;
+use crate::chess_server::io::pgn::{result_string, PgnGame};
+use crate::tournament::{MatchScore, OpeningBook};
+
+use super::{load_player, parse_time_control};
+
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct SelfplayArgs {
+    /// Bot name or path to a JSON engine config
+    #[arg(long, default_value = "pignite")]
+    pub white: String,
+
+    /// Bot name or path to a JSON engine config
+    #[arg(long, default_value = "tepig")]
+    pub black: String,
+
+    #[arg(long, default_value_t = 1)]
+    pub games: usize,
+
+    /// Swaps the colors after every game
+    #[arg(long)]
+    pub swap: bool,
+
+    /// Time control in seconds, e.g. 60+1, 300d5 or 40/5400+30
+    #[arg(long, value_parser = parse_time_control)]
+    pub time_control: Option<TimeControl>,
+
+    /// FEN, EPD or PGN file of openings, played in turn
+    #[arg(long)]
+    pub openings: Option<String>,
+
+    /// Appends the games to this PGN file
+    #[arg(long)]
+    pub pgn: Option<String>,
+
+    /// Only prints the results
+    #[arg(long)]
+    pub quiet: bool,
+}
+
+pub fn run(args: SelfplayArgs) -> Result<(), String> {
+    let first = load_player(&args.white)?;
+    let second = load_player(&args.black)?;
+    let openings = match &args.openings {
+        Some(path) => OpeningBook::from_file(path)?,
+        None => OpeningBook::starting_position(),
+    };
+
+    // From the point of view of the white bot of the first game
+    let mut score = MatchScore::new();
+
+    for game in 0..args.games {
+        let swapped = args.swap && game % 2 == 1;
+        let (player_white, player_black) = match swapped {
+            false => (&first, &second),
+            true => (&second, &first),
+        };
+        let (white_name, black_name) = match swapped {
+            false => (&args.white, &args.black),
+            true => (&args.black, &args.white),
+        };
+
+        let opening = openings.get(game);
+        let mut game_manager = match args.time_control {
+            Some(time_control) => GameManager::with_position_and_time_control(
+                player_white,
+                player_black,
+                opening.chess_board,
+                time_control,
+            ),
+            None => GameManager::with_position(player_white, player_black, opening.chess_board),
+        };
+        if !args.quiet {
+            game_manager.add_observer(Box::new(ConsoleObserver::new()));
+        }
+
+        while game_manager.is_game_ongoing() {
+            if !args.quiet {
+                game_manager.show();
+            }
+            game_manager.make_move();
+        }
+
+        let status = game_manager.get_game_status();
+        let white_points = match status {
+            ChessStatus::WhiteWon => 1.,
+            ChessStatus::BlackWon => 0.,
+            ChessStatus::Draw | ChessStatus::Ongoing => 0.5,
+        };
+        score.add(if swapped { 1. - white_points } else { white_points });
+
+        println!(
+            "Game {}: {} - {} {} ({:?}, {} plies)",
+            game + 1,
+            white_name,
+            black_name,
+            result_string(status),
+            game_manager.get_game_over_reason(),
+            game_manager.get_ply()
+        );
+
+        if let Some(path) = &args.pgn {
+            let mut pgn_game = PgnGame::from_game(&game_manager);
+            pgn_game.add_tag("Event", "Selfplay");
+            pgn_game.add_tag("Round", &(game + 1).to_string());
+            pgn_game.add_tag("White", white_name);
+            pgn_game.add_tag("Black", black_name);
+            if !opening.name.is_empty() {
+                pgn_game.add_tag("Opening", &opening.name);
+            }
+            pgn_game.append_to_file(path)?;
+        }
+    }
+
+    println!("{} vs {}: {}", args.white, args.black, score);
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use crate::engines::evaluators::activity_evaluator::ActivityParams;
+use crate::engines::evaluators::pesto_evaluator::PestoParams;
+use crate::tuning::{load_labeled_positions, TexelConfig, TexelTuner};
+
+use clap::Args;
+
+#[derive(Args, Debug)]
+pub struct TuneArgs {
+    /// Positions with their labels, one `<fen>; <label>` per line
+    pub data: String,
+
+    /// Parameter file to write, which the pesto_params evaluator loads
+    #[arg(long)]
+    pub output: String,
+
+    /// Parameters to start from instead of the default PeSTO ones
+    #[arg(long)]
+    pub params: Option<String>,
+
+    /// Also fits the piece activity weights, evaluated on top of PeSTO, and
+    /// writes them to this file, which the activity_params evaluator loads
+    #[arg(long)]
+    pub activity_output: Option<String>,
+
+    /// Activity weights to start from instead of the default ones
+    #[arg(long, requires = "activity_output")]
+    pub activity_params: Option<String>,
+
+    #[arg(long, default_value_t = 500)]
+    pub epochs: usize,
+
+    /// Adam learning rate, in centipawns
+    #[arg(long, default_value_t = 1.)]
+    pub learning_rate: f64,
+
+    #[arg(long, default_value_t = 1)]
+    pub threads: usize,
+
+    /// Keeps the Elo scaling of the sigmoid instead of fitting it
+    #[arg(long)]
+    pub no_fit_scaling: bool,
+}
+
+pub fn run(args: TuneArgs) -> Result<(), String> {
+    let positions = load_labeled_positions(&args.data)?;
+    let params = match &args.params {
+        Some(path) => PestoParams::from_file(path)?,
+        None => PestoParams::default(),
+    };
+    println!("{} positions", positions.len());
+
+    let config = TexelConfig {
+        epochs: args.epochs,
+        learning_rate: args.learning_rate,
+        threads: args.threads,
+        fit_scaling: !args.no_fit_scaling,
+    };
+
+    let mut tuner = match &args.activity_output {
+        Some(_) => {
+            let activity_params = match &args.activity_params {
+                Some(path) => ActivityParams::from_file(path)?,
+                None => ActivityParams::default(),
+            };
+            TexelTuner::with_activity(&positions, &params, &activity_params, config)
+        }
+        None => TexelTuner::new(&positions, &params, config),
+    };
+    // Progress is logged at the info level
+    let loss = tuner.run();
+    println!("Scaling {:.6}, loss {:.6}", tuner.get_scaling(), loss);
+
+    let params = tuner.get_params();
+    params.save(&args.output)?;
+
+    println!("mg_value: {:?}", params.mg_value);
+    println!("eg_value: {:?}", params.eg_value);
+    // For the piece_values of a capture evaluator around these parameters
+    println!("Piece values in pawns: {:?}", params.piece_values());
+    println!("Parameters written to {}", args.output);
+
+    if let Some(path) = &args.activity_output {
+        tuner.get_activity_params().save(path)?;
+        println!("Activity weights written to {}", path);
+    }
+
+    Ok(())
+}
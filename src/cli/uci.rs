@@ -0,0 +1,213 @@
+use crate::chess_server::chess_types::{ChessBoard, ChessStatus, Move};
+use crate::chess_server::game::{GameContext, Player};
+use crate::chess_server::io::utils::parse_uci_move;
+use crate::engines::bots::{bot_names, Pokemon};
+use crate::engines::time_manager::ClockState;
+
+use super::load_player;
+
+use clap::Args;
+use log::{info, warn};
+
+use std::io::{stdin, stdout, BufRead, Write};
+use std::time::Duration;
+
+#[derive(Args, Debug)]
+pub struct UciArgs {
+    /// Bot name or path to a JSON engine config
+    #[arg(long, default_value = "pignite")]
+    pub bot: String,
+}
+
+/// Position set by the last `position` command, with the positions before
+/// it for the repetition checks of the bots.
+struct UciState {
+    bot_name: String,
+    bot: Pokemon,
+    chess_board: ChessBoard,
+    history: Vec<ChessBoard>,
+}
+
+impl UciState {
+    // The bot can be any name or path `--bot` takes, not only the ones
+    // offered by the option
+    fn set_bot(&mut self, bot_name: &str) -> Result<(), String> {
+        self.bot = load_player(bot_name)?;
+        self.bot_name = bot_name.to_owned();
+        Ok(())
+    }
+
+    fn set_position(&mut self, words: &[&str]) -> Result<(), String> {
+        let moves_start = words.iter().position(|&word| word == "moves");
+        let (position, moves) = match moves_start {
+            Some(index) => (&words[..index], &words[index + 1..]),
+            None => (words, &[][..]),
+        };
+
+        let mut chess_board = match position {
+            ["startpos"] => ChessBoard::starting_position(),
+            ["fen", fen @ ..] => ChessBoard::from_fen(&fen.join(" "))?,
+            _ => return Err(format!("Invalid position: {}", words.join(" "))),
+        };
+        let mut history = Vec::with_capacity(moves.len());
+
+        // Castling and en passant aren't played on this board, such moves
+        // are rejected and the previous position is kept
+        for move_str in moves {
+            let mv = parse_uci_move(&chess_board, move_str)
+                .ok_or_else(|| format!("Illegal move: {}", move_str))?;
+
+            history.push(chess_board);
+            chess_board = chess_board.next_state(&mv);
+        }
+
+        self.chess_board = chess_board;
+        self.history = history;
+        Ok(())
+    }
+
+    fn go(&self, words: &[&str]) -> Option<Move> {
+        let value = |name: &str| {
+            words
+                .iter()
+                .position(|&word| word == name)
+                .and_then(|index| words.get(index + 1))
+                .and_then(|value| value.parse::<u64>().ok())
+        };
+        let millis = |name: &str| value(name).map(Duration::from_millis);
+
+        // A fixed move time is a clock with a single move to go
+        let clock = match (millis("movetime"), millis("wtime"), millis("btime")) {
+            (Some(movetime), _, _) => Some(ClockState {
+                wtime: movetime,
+                btime: movetime,
+                winc: Duration::ZERO,
+                binc: Duration::ZERO,
+                movestogo: Some(1),
+            }),
+            (None, Some(wtime), Some(btime)) => Some(ClockState {
+                wtime,
+                btime,
+                winc: millis("winc").unwrap_or(Duration::ZERO),
+                binc: millis("binc").unwrap_or(Duration::ZERO),
+                movestogo: value("movestogo").map(|movestogo| movestogo as u32),
+            }),
+            _ => None,
+        };
+
+        let status = self.chess_board.get_game_status();
+        if status != ChessStatus::Ongoing {
+            return None;
+        }
+
+        let context = GameContext {
+            chess_board: self.chess_board,
+            history: &self.history,
+            ply: self.history.len(),
+            game_status: status,
+            clock,
+            draw_offered: false,
+            opponent_score: None,
+        };
+
+        self.bot.decide(&context).get_move()
+    }
+}
+
+// Searches are synchronous, so `stop` has nothing to stop
+pub fn run(args: UciArgs) -> Result<(), String> {
+    let mut state = UciState {
+        bot_name: args.bot.clone(),
+        bot: load_player(&args.bot)?,
+        chess_board: ChessBoard::starting_position(),
+        history: Vec::new(),
+    };
+
+    for line in stdin().lock().lines() {
+        let line = line.map_err(|err| err.to_string())?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        info!("uci < {}", line);
+
+        let reply = |message: &str| {
+            println!("{}", message);
+            info!("uci > {}", message);
+        };
+
+        match words.as_slice() {
+            ["uci"] => {
+                let mut names = bot_names();
+                if !names.contains(&state.bot_name.as_str()) {
+                    names.push(&state.bot_name);
+                }
+
+                let vars: Vec<String> = names.iter().map(|name| format!("var {}", name)).collect();
+                reply(&format!("id name {}", state.bot_name));
+                reply(&format!(
+                    "option name Bot type combo default {} {}",
+                    state.bot_name,
+                    vars.join(" ")
+                ));
+                reply("uciok");
+            }
+            ["isready"] => reply("readyok"),
+            ["ucinewgame"] => {
+                // Bots keep state between moves, a new game gets a new one
+                state.bot = load_player(&state.bot_name)?;
+            }
+            ["setoption", "name", name, "value", value @ ..]
+                if name.eq_ignore_ascii_case("Bot") =>
+            {
+                if let Err(err) = state.set_bot(&value.join(" ")) {
+                    warn!("{}", err);
+                    reply(&format!("info string {}", err));
+                }
+            }
+            ["position", position @ ..] => {
+                if let Err(err) = state.set_position(position) {
+                    warn!("{}", err);
+                    reply(&format!("info string {}", err));
+                }
+            }
+            ["go", options @ ..] => match state.go(options) {
+                Some(mv) => {
+                    if let Some(score) = state.bot.get_last_score() {
+                        reply(&format!("info score {}", score.to_uci()));
+                    }
+                    reply(&format!("bestmove {}", mv));
+                }
+                None => reply("bestmove 0000"),
+            },
+            ["d"] => state.chess_board.print_board(),
+            ["quit"] => break,
+            ["stop"] | [] => (),
+            _ => warn!("Unknown command: {}", line),
+        }
+
+        stdout().flush().map_err(|err| err.to_string())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_with_illegal_moves_are_not_set() {
+        let mut state = UciState {
+            bot_name: "magikarp".to_owned(),
+            bot: load_player("magikarp").unwrap(),
+            chess_board: ChessBoard::starting_position(),
+            history: Vec::new(),
+        };
+
+        state.set_position(&["startpos", "moves", "e2e4", "e7e5"]).unwrap();
+        let chess_board = state.chess_board;
+        assert_eq!(state.history.len(), 2);
+
+        assert!(state.set_position(&["startpos", "moves", "d2d4", "d2d4"]).is_err());
+        assert!(state.chess_board == chess_board);
+        assert_eq!(state.history.len(), 2);
+    }
+}
@@ -0,0 +1,315 @@
+use crate::chess_server::chess_types::chess_board::{SquareContainer, SEE_VALUES};
+use crate::chess_server::chess_types::{ChessBoard, Color, Piece, Square};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// From the least valuable
+const PIECES: [Piece; 6] = [
+    Piece::Pawn,
+    Piece::Knight,
+    Piece::Bishop,
+    Piece::Rook,
+    Piece::Queen,
+    Piece::King,
+];
+
+thread_local! {
+    // Attack map of the last position asked for on this thread
+    static LAST_ATTACK_MAP: RefCell<Option<(ChessBoard, Rc<AttackMap>)>> =
+        const { RefCell::new(None) };
+}
+
+/// Squares attacked by every piece of a position, with the number of
+/// attackers of every square by color and piece type.
+///
+/// Attacks are the ones of `squares_attacked_by_piece`: squares of pieces of
+/// the same color count as defended, and pins are ignored.
+#[derive(Clone, Debug)]
+pub struct AttackMap {
+    chess_board: ChessBoard,
+    // Squares attacked by the piece standing on every square, as masks
+    attacks: [u64; 64],
+    // Number of attackers of every square, by color and piece type
+    counts: [[[u8; 6]; 64]; 2],
+    // Squares attacked by every color
+    attacked: [u64; 2],
+    // Squares of the pieces of every color
+    occupied: [u64; 2],
+}
+
+impl AttackMap {
+    pub fn new(chess_board: &ChessBoard) -> AttackMap {
+        let mut attack_map = AttackMap {
+            chess_board: *chess_board,
+            attacks: [0; 64],
+            counts: [[[0; 6]; 64]; 2],
+            attacked: [0; 2],
+            occupied: [0; 2],
+        };
+
+        let mut squares = SquareContainer::new();
+        for (square, content) in chess_board.iter_coordinates() {
+            let Some(content) = content else {
+                continue;
+            };
+            let color = content.get_color() as usize;
+            let piece = content.get_piece() as usize;
+
+            squares.clear();
+            chess_board.inplace_squares_attacked_by_piece(&square, &mut squares);
+
+            let mut attacks = 0;
+            for attacked_square in squares.iter() {
+                let index = attacked_square.get_index() as usize;
+                attacks |= 1 << index;
+                attack_map.counts[color][index][piece] += 1;
+            }
+
+            attack_map.attacks[square.get_index() as usize] = attacks;
+            attack_map.attacked[color] |= attacks;
+            attack_map.occupied[color] |= 1 << square.get_index();
+        }
+
+        attack_map
+    }
+
+    /// Attack map of the position, shared by everything evaluating it on
+    /// the same thread. Only the last position is kept, which is enough for
+    /// evaluators combined in a single evaluation.
+    pub fn shared(chess_board: &ChessBoard) -> Rc<AttackMap> {
+        LAST_ATTACK_MAP.with(|last_attack_map| {
+            let mut last_attack_map = last_attack_map.borrow_mut();
+
+            match last_attack_map.as_ref() {
+                Some((previous, attack_map)) if previous == chess_board => attack_map.clone(),
+                _ => {
+                    let attack_map = Rc::new(AttackMap::new(chess_board));
+                    *last_attack_map = Some((*chess_board, attack_map.clone()));
+                    attack_map
+                }
+            }
+        })
+    }
+
+    /// Squares attacked by the piece on the square, as a mask.
+    pub fn get_attacks_from(&self, square: &Square) -> u64 {
+        self.attacks[square.get_index() as usize]
+    }
+
+    pub fn get_attackers(&self, square: &Square, color: Color) -> u8 {
+        self.counts[color as usize][square.get_index() as usize]
+            .iter()
+            .sum()
+    }
+
+    pub fn get_attackers_by_piece(&self, square: &Square, color: Color, piece: Piece) -> u8 {
+        self.counts[color as usize][square.get_index() as usize][piece as usize]
+    }
+
+    pub fn is_attacked(&self, square: &Square, color: Color) -> bool {
+        self.attacked[color as usize] & 1 << square.get_index() != 0
+    }
+
+    pub fn least_valuable_attacker(&self, square: &Square, color: Color) -> Option<Piece> {
+        let counts = &self.counts[color as usize][square.get_index() as usize];
+
+        PIECES.into_iter().find(|piece| counts[*piece as usize] > 0)
+    }
+
+    /// Squares of the pieces of the color, as a mask.
+    pub fn get_pieces(&self, color: Color) -> u64 {
+        self.occupied[color as usize]
+    }
+
+    /// Squares attacked by the color, as a mask.
+    pub fn attacked_squares(&self, color: Color) -> u64 {
+        self.attacked[color as usize]
+    }
+
+    /// Squares the color attacks more often than the other color, as a mask.
+    pub fn controlled_squares(&self, color: Color) -> u64 {
+        let mut controlled = 0;
+
+        for index in 0..64 {
+            let square = Square::from_index(index as i8).unwrap();
+            if self.get_attackers(&square, color) > self.get_attackers(&square, color.opposite()) {
+                controlled |= 1 << index;
+            }
+        }
+
+        controlled
+    }
+
+    /// Pieces of the color attacked by the other color and not defended.
+    pub fn hanging_pieces(&self, color: Color) -> SquareContainer {
+        let hanging = self.occupied[color as usize]
+            & self.attacked[color.opposite() as usize]
+            & !self.attacked[color as usize];
+
+        self.pieces_in(hanging)
+            .filter(|square| !self.is_king(square))
+            .collect()
+    }
+
+    /// Pieces of the color attacked by a piece of the other color worth
+    /// less, which win material whether the piece is defended or not.
+    pub fn attacked_by_lower_value(&self, color: Color) -> SquareContainer {
+        self.pieces_in(self.occupied[color as usize] & self.attacked[color.opposite() as usize])
+            .filter(|square| !self.is_king(square))
+            .filter(|square| {
+                let piece = self
+                    .chess_board
+                    .get_square_content(square)
+                    .unwrap()
+                    .get_piece();
+
+                // The king is worth nothing in the exchange but can't take
+                // a defended piece
+                self.least_valuable_attacker(square, color.opposite())
+                    .is_some_and(|attacker| {
+                        attacker != Piece::King
+                            && SEE_VALUES[attacker as usize] < SEE_VALUES[piece as usize]
+                    })
+            })
+            .collect()
+    }
+
+    fn is_king(&self, square: &Square) -> bool {
+        self.chess_board
+            .get_square_content(square)
+            .is_some_and(|content| content.get_piece() == Piece::King)
+    }
+
+    fn pieces_in(&self, mask: u64) -> impl Iterator<Item = Square> {
+        (0..64)
+            .filter(move |index| mask & 1 << index != 0)
+            .map(|index| Square::from_index(index as i8).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_match_squares_attacked_by_piece() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R b KQ - 0 8",
+        ];
+
+        for fen in fens {
+            let chess_board = ChessBoard::from_fen(fen).unwrap();
+            let attack_map = AttackMap::new(&chess_board);
+            let mut counts = [[[0; 6]; 64]; 2];
+
+            for (square, content) in chess_board.iter_coordinates() {
+                let Some(content) = content else {
+                    continue;
+                };
+                let attacks = chess_board.squares_attacked_by_piece(&square);
+                let mask = attacks
+                    .iter()
+                    .fold(0, |mask, attacked| mask | 1 << attacked.get_index());
+                assert_eq!(
+                    attack_map.get_attacks_from(&square),
+                    mask,
+                    "{} {:?}",
+                    fen,
+                    square
+                );
+
+                for attacked in attacks.iter() {
+                    counts[content.get_color() as usize][attacked.get_index() as usize]
+                        [content.get_piece() as usize] += 1;
+                }
+            }
+
+            for index in 0..64 {
+                let square = Square::from_index(index).unwrap();
+                for color in [Color::White, Color::Black] {
+                    let expected = counts[color as usize][index as usize];
+                    for piece in PIECES {
+                        assert_eq!(
+                            attack_map.get_attackers_by_piece(&square, color, piece),
+                            expected[piece as usize],
+                            "{} {:?} {:?}",
+                            fen,
+                            square,
+                            piece
+                        );
+                    }
+                    assert_eq!(
+                        attack_map.get_attackers(&square, color),
+                        expected.iter().sum::<u8>()
+                    );
+                    assert_eq!(
+                        attack_map.is_attacked(&square, color),
+                        expected.iter().any(|count| *count > 0)
+                    );
+                }
+            }
+        }
+    }
+
+    fn names(squares: &SquareContainer) -> Vec<String> {
+        squares.iter().map(|square| square.to_str()).collect()
+    }
+
+    #[test]
+    fn finds_hanging_pieces_and_cheaper_attackers() {
+        use Color::{Black, White};
+
+        let cases: [(&str, Color, &[&str], &[&str]); 5] = [
+            // The queen is taken by the pawn and the knight, nothing defends it
+            (
+                "r3k3/8/8/3q4/4P3/2N5/8/4K3 w - - 0 1",
+                Black,
+                &["d5"],
+                &["d5"],
+            ),
+            ("r3k3/8/8/3q4/4P3/2N5/8/4K3 w - - 0 1", White, &[], &[]),
+            // Defended, but a pawn takes the knight
+            ("4k3/8/2p5/3n4/4P3/8/8/4K3 w - - 0 1", Black, &[], &["d5"]),
+            // Only the king takes the queen, and kings never hang
+            ("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1", Black, &["d2"], &[]),
+            ("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1", White, &[], &[]),
+        ];
+
+        for (fen, color, hanging, lower) in cases {
+            let attack_map = AttackMap::new(&ChessBoard::from_fen(fen).unwrap());
+
+            assert_eq!(names(&attack_map.hanging_pieces(color)), hanging, "{}", fen);
+            assert_eq!(
+                names(&attack_map.attacked_by_lower_value(color)),
+                lower,
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn controls_squares_attacked_more_often() {
+        let attack_map =
+            AttackMap::new(&ChessBoard::from_fen("r3k3/8/8/3q4/4P3/2N5/8/4K3 w - - 0 1").unwrap());
+        let square = |name: &str| {
+            let mut chars = name.chars();
+            let square =
+                Square::from_chess_notation([chars.next().unwrap(), chars.next().unwrap()]);
+            1 << square.unwrap().get_index()
+        };
+
+        let white = attack_map.controlled_squares(Color::White);
+        let black = attack_map.controlled_squares(Color::Black);
+
+        // Two attackers against none, one against one, and the rook's file
+        assert_ne!(white & square("d5"), 0);
+        assert_eq!((white | black) & square("e4"), 0);
+        assert_ne!(black & square("a1"), 0);
+        assert_eq!(white & black, 0);
+    }
+}
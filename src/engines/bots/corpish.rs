@@ -1,37 +1,37 @@
-use ordered_float::OrderedFloat;
-
 use crate::engines::engine_traits::*;
 use crate::engines::evaluators::*;
 use crate::engines::policies::*;
+use crate::engines::score::Score;
 use crate::engines::searchers::*;
 
 use super::Pokemon;
 
 pub fn corpish() -> Pokemon {
     // Stochastic Rollout Evaluator
-    let base_eval = ClampEvaluator::new(
-        CaptureEvaluator::new(PestoEvaluator::new()),
-        OrderedFloat(3.00),
-    );
+    let base_eval =
+        ClampEvaluator::new(CaptureEvaluator::new(PestoEvaluator::new()), Score::cp(300));
 
-    let rollout_eval = StochasticRollout::new(
-        SoftmaxPolicy::new(base_eval.clone(), 0.25),
+    // Temperatures and the value of a win follow the thresholds of the clamps
+    let rollout_eval = StochasticRollout::with_win(
+        SoftmaxPolicy::new(base_eval.clone(), 0.75),
         base_eval.clone(),
         6,
         5,
+        Score::cp(300),
     );
 
     let policy_evaluation = CacheEvaluator::new(ClampEvaluator::new(
         CaptureEvaluator::new(PestoEvaluator::new()),
-        OrderedFloat(6.),
+        Score::cp(600),
     ));
     let engine = SearcherEngine::new(
         rollout_eval,
-        MonteCarloTreeSearch::new(
-            SoftmaxPolicy::new(policy_evaluation, 0.1),
+        MonteCarloTreeSearch::with_win(
+            SoftmaxPolicy::new(policy_evaluation, 0.6),
             20,
             100, // 1000
             2.,
+            Score::cp(300),
         ),
     );
 
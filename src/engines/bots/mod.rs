@@ -24,3 +24,6 @@ pub use tepig::tepig;
 
 pub mod pignite;
 pub use pignite::pignite;
+
+pub mod registry;
+pub use registry::{bot_names, create_bot, find_bot, BotInfo, TimeControlClass, BOTS};
@@ -7,6 +7,7 @@ use crate::engines::timed_searchers::clunky_searcher::ClunkySearcher;
 use crate::engines::timed_searchers::clunky_searcher_v2::ClunkySearcherV2;
 use crate::engines::timed_searchers::clunky_searcher_v3::ClunkySearcherV3;
 use crate::engines::timed_searchers::clunky_searcher_v4::ClunkySearcherV4;
+use crate::engines::timed_searchers::clunky_searcher_v5::ClunkySearcherV5;
 
 use super::Pokemon;
 
@@ -17,7 +18,7 @@ pub fn pignite() -> Pokemon {
     let engine = SearcherEngine::new(
         evaluator,
         TimedSearcherWrapper::new(
-            Box::new(ClunkySearcherV4::new()),
+            Box::new(ClunkySearcherV5::new()),
             Duration::from_secs(1)
         )
     );
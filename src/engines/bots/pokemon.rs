@@ -1,5 +1,5 @@
-use crate::chess_server::chess_types::*;
-use crate::chess_server::game::Player;
+use crate::chess_server::game::{Decision, GameContext, Player};
+use crate::engines::score::Score;
 
 pub struct Pokemon {
     engine: Box<dyn Player>,
@@ -12,7 +12,11 @@ impl Pokemon {
 }
 
 impl Player for Pokemon {
-    fn select_move(&self, chess_board: &ChessBoard) -> Move {
-        self.engine.select_move(chess_board)
+    fn decide(&self, context: &GameContext) -> Decision {
+        self.engine.decide(context)
+    }
+
+    fn get_last_score(&self) -> Option<Score> {
+        self.engine.get_last_score()
     }
 }
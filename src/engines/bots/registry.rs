@@ -0,0 +1,187 @@
+use std::time::Duration;
+
+use crate::chess_server::clock::TimeControl;
+
+use super::*;
+
+/// Speed of a game, from its estimated duration as on lichess: base time
+/// plus 40 increments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TimeControlClass {
+    Bullet,
+    Blitz,
+    Rapid,
+    Classical,
+}
+
+impl TimeControlClass {
+    pub fn from_time_control(time_control: &TimeControl) -> TimeControlClass {
+        let estimated = time_control.base_time + 40 * time_control.get_increment();
+
+        if estimated < Duration::from_secs(3 * 60) {
+            TimeControlClass::Bullet
+        } else if estimated < Duration::from_secs(8 * 60) {
+            TimeControlClass::Blitz
+        } else if estimated < Duration::from_secs(25 * 60) {
+            TimeControlClass::Rapid
+        } else {
+            TimeControlClass::Classical
+        }
+    }
+}
+
+/// A bot and what is known about it.
+///
+/// `expected_elo` is a rough guess to order the bots until they have been
+/// rated on the ladder. Bots that search to a fixed depth or for a fixed
+/// number of rollouts ignore the clock, so their `fastest_time_control` is
+/// the fastest one where they don't flag.
+pub struct BotInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub searcher: &'static str,
+    pub evaluator: &'static str,
+    pub expected_elo: u32,
+    pub fastest_time_control: TimeControlClass,
+    pub constructor: fn() -> Pokemon,
+}
+
+impl BotInfo {
+    pub fn create(&self) -> Pokemon {
+        (self.constructor)()
+    }
+
+    pub fn suits(&self, time_control: &TimeControl) -> bool {
+        TimeControlClass::from_time_control(time_control) >= self.fastest_time_control
+    }
+}
+
+pub static BOTS: [BotInfo; 8] = [
+    BotInfo {
+        name: "pignite",
+        description: "Iterative deepening with pruning, extensions and quiescence",
+        searcher: "ClunkySearcherV5",
+        evaluator: "CaptureEvaluator(PestoEvaluator)",
+        expected_elo: 1600,
+        fastest_time_control: TimeControlClass::Bullet,
+        constructor: pignite,
+    },
+    BotInfo {
+        name: "tepig",
+        description: "Plain iterative deepening",
+        searcher: "IterativeDeepening",
+        evaluator: "CaptureEvaluator(PestoEvaluator)",
+        expected_elo: 1500,
+        fastest_time_control: TimeControlClass::Bullet,
+        constructor: tepig,
+    },
+    BotInfo {
+        name: "darkrai",
+        description: "Fixed depth search",
+        searcher: "DeepSearch(6)",
+        evaluator: "PestoEvaluator",
+        expected_elo: 1400,
+        fastest_time_control: TimeControlClass::Rapid,
+        constructor: darkrai,
+    },
+    BotInfo {
+        name: "weedle",
+        description: "Fixed depth alpha-beta",
+        searcher: "AlphaBeta(4)",
+        evaluator: "PestoEvaluator",
+        expected_elo: 1250,
+        fastest_time_control: TimeControlClass::Bullet,
+        constructor: weedle,
+    },
+    BotInfo {
+        name: "ninetales",
+        description: "Monte Carlo tree search, deep search in the endgame",
+        searcher: "MonteCarloTreeSearch / DeepSearch(9)",
+        evaluator: "StochasticRollout(PestoEvaluator)",
+        expected_elo: 1100,
+        fastest_time_control: TimeControlClass::Rapid,
+        constructor: ninetales,
+    },
+    BotInfo {
+        name: "pikachu",
+        description: "Monte Carlo tree search, deep search in the endgame",
+        searcher: "MonteCarloTreeSearch / DeepSearch(9)",
+        evaluator: "StochasticRollout(Material, Pressure, KingSafety)",
+        expected_elo: 1000,
+        fastest_time_control: TimeControlClass::Rapid,
+        constructor: pikachu,
+    },
+    BotInfo {
+        name: "corpish",
+        description: "Monte Carlo tree search",
+        searcher: "MonteCarloTreeSearch",
+        evaluator: "StochasticRollout(PestoEvaluator)",
+        expected_elo: 1000,
+        fastest_time_control: TimeControlClass::Blitz,
+        constructor: corpish,
+    },
+    BotInfo {
+        name: "magikarp",
+        description: "Shallow minimax",
+        searcher: "MinMax(2)",
+        evaluator: "PestoEvaluator",
+        expected_elo: 800,
+        fastest_time_control: TimeControlClass::Bullet,
+        constructor: magikarp,
+    },
+];
+
+// Names are case insensitive
+pub fn find_bot(name: &str) -> Option<&'static BotInfo> {
+    BOTS.iter().find(|bot| bot.name.eq_ignore_ascii_case(name))
+}
+
+pub fn create_bot(name: &str) -> Option<Pokemon> {
+    find_bot(name).map(BotInfo::create)
+}
+
+pub fn bot_names() -> Vec<&'static str> {
+    BOTS.iter().map(|bot| bot.name).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn every_bot_can_be_created() {
+        for bot in BOTS.iter() {
+            bot.create();
+            assert!(create_bot(bot.name).is_some(), "{}", bot.name);
+        }
+        assert!(create_bot("missingno").is_none());
+    }
+
+    #[test]
+    fn bots_are_found_whatever_the_case() {
+        assert_eq!(find_bot("Pignite").map(|bot| bot.name), Some("pignite"));
+        assert_eq!(find_bot("MAGIKARP").map(|bot| bot.name), Some("magikarp"));
+        assert!(find_bot("pignite ").is_none());
+    }
+
+    #[test]
+    fn bot_names_are_unique() {
+        let names: HashSet<String> = BOTS.iter().map(|bot| bot.name.to_lowercase()).collect();
+        assert_eq!(names.len(), BOTS.len());
+        assert_eq!(bot_names().len(), BOTS.len());
+    }
+
+    #[test]
+    fn slow_bots_only_suit_long_games() {
+        let bullet = TimeControl::fischer(Duration::from_secs(60), Duration::from_secs(1));
+        let rapid = TimeControl::sudden_death(Duration::from_secs(15 * 60));
+        let darkrai = find_bot("darkrai").unwrap();
+
+        assert_eq!(TimeControlClass::from_time_control(&bullet), TimeControlClass::Bullet);
+        assert_eq!(TimeControlClass::from_time_control(&rapid), TimeControlClass::Rapid);
+        assert!(!darkrai.suits(&bullet));
+        assert!(darkrai.suits(&rapid));
+    }
+}
@@ -0,0 +1,599 @@
+use crate::chess_server::chess_types::ChessBoard;
+use crate::chess_server::game::Player;
+use crate::engines::bots::{find_bot, Pokemon};
+use crate::engines::engine_traits::*;
+use crate::engines::evaluators::*;
+use crate::engines::if_else_engine::IfElseEngine;
+use crate::engines::policies::SoftmaxPolicy;
+use crate::engines::quiescence::{QuiescenceConfig, QuiescenceSearch};
+use crate::engines::random_engine::RandomEngine;
+use crate::engines::score::Score;
+use crate::engines::searchers::*;
+use crate::engines::timed_searchers::alpha_beta::AlphaBeta;
+use crate::engines::timed_searchers::clunky_searcher::ClunkySearcher;
+use crate::engines::timed_searchers::clunky_searcher_v2::ClunkySearcherV2;
+use crate::engines::timed_searchers::clunky_searcher_v3::ClunkySearcherV3;
+use crate::engines::timed_searchers::clunky_searcher_v4::ClunkySearcherV4;
+use crate::engines::timed_searchers::clunky_searcher_v5::{ClunkySearcherV5, SearchConfig};
+use crate::engines::timed_searchers::iterative_deepening::IterativeDeepening as TimedIterativeDeepening;
+use crate::engines::timed_searchers::minmax::MinMax;
+
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+use std::time::Duration;
+
+pub type DynEvaluator = Box<dyn Evaluator>;
+
+// Evaluators used by rollouts, which evaluate on several threads
+pub type SharedEvaluator = Box<dyn Evaluator + Send + Sync>;
+
+/// Evaluator tree, e.g. `{"capture": {"evaluator": "pesto"}}`.
+///
+/// `linear` sums its terms with their weights. `cache` keeps a table that
+/// can't be shared between threads, so it is only allowed at the top of a
+/// tree.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluatorConfig {
+    Trivial,
+    Material,
+    Pressure,
+    Positional,
+    KingSafety,
+    KingDanger,
+    Dynamic,
+    Structure,
+    PawnStructure,
+    Pesto,
+    // PeSTO with the parameters of a file written by the tuner
+    PestoParams {
+        path: String,
+    },
+    Activity,
+    // Piece activity with the weights of a file written by the tuner
+    ActivityParams {
+        path: String,
+    },
+    Nnue {
+        path: String,
+    },
+    // Linear model or MLP fitted in the notebooks
+    Learned {
+        path: String,
+    },
+    // Piece values are pawn, bishop, knight, rook and queen
+    Capture {
+        evaluator: Box<EvaluatorConfig>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        piece_values: Option<[f64; 5]>,
+    },
+    Cache {
+        evaluator: Box<EvaluatorConfig>,
+    },
+    Negate {
+        evaluator: Box<EvaluatorConfig>,
+    },
+    Clamp {
+        evaluator: Box<EvaluatorConfig>,
+        threshold: f64,
+    },
+    Threshold {
+        evaluator: Box<EvaluatorConfig>,
+        threshold: f64,
+    },
+    Linear {
+        terms: Vec<WeightedEvaluatorConfig>,
+    },
+    Rollout {
+        policy: PolicyConfig,
+        evaluator: Box<EvaluatorConfig>,
+        max_depth: usize,
+        // Value of a won game in pawns, the threshold of a clamped evaluator
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        win: Option<f64>,
+    },
+    StochasticRollout {
+        policy: PolicyConfig,
+        evaluator: Box<EvaluatorConfig>,
+        max_depth: usize,
+        iterations: usize,
+        // Value of a won game in pawns, the threshold of a clamped evaluator
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        win: Option<f64>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WeightedEvaluatorConfig {
+    pub evaluator: EvaluatorConfig,
+    pub weight: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyConfig {
+    Softmax {
+        evaluator: Box<EvaluatorConfig>,
+        temperature: f64,
+    },
+}
+
+/// Searchers that are given a time for every move, or the clock.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimedSearcherConfig {
+    MinMax { depth: usize },
+    AlphaBeta { depth: usize },
+    IterativeDeepening,
+    Clunky,
+    ClunkyV2,
+    ClunkyV3,
+    ClunkyV4,
+    ClunkyV5,
+}
+
+/// Searchers with a fixed depth search to the end whatever the clock says.
+/// With `quiescence`, their leaves are searched until the position is quiet.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearcherConfig {
+    MinMax {
+        depth: usize,
+    },
+    AlphaBeta {
+        depth: usize,
+        #[serde(default)]
+        quiescence: bool,
+    },
+    Deep {
+        depth: usize,
+        #[serde(default)]
+        quiescence: bool,
+    },
+    IterativeDeepening {
+        depth: usize,
+        #[serde(default)]
+        quiescence: bool,
+    },
+    RepetitionAware {
+        depth: usize,
+    },
+    Mcts {
+        policy: PolicyConfig,
+        max_depth: usize,
+        iterations: usize,
+        c_puct: f64,
+        // Value of a won game in pawns, the threshold of a clamped evaluator
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        win: Option<f64>,
+    },
+    Timed {
+        searcher: TimedSearcherConfig,
+        time_ms: u64,
+        // Features of clunky_v5, every one left out is on
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        search_config: Option<SearchConfig>,
+    },
+}
+
+/// Chooses between the two players of an `if_else` player.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionConfig {
+    // At least that many pieces on the board, kings included
+    MinPieces(usize),
+}
+
+impl ConditionConfig {
+    pub fn evaluate(&self, chess_board: &ChessBoard) -> bool {
+        match *self {
+            ConditionConfig::MinPieces(count) => {
+                chess_board
+                    .iter_coordinates()
+                    .filter(|(_, content)| content.is_some())
+                    .count()
+                    >= count
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayerConfig {
+    Engine {
+        evaluator: EvaluatorConfig,
+        searcher: SearcherConfig,
+    },
+    IfElse {
+        condition: ConditionConfig,
+        then: Box<PlayerConfig>,
+        otherwise: Box<PlayerConfig>,
+    },
+    // A bot of the registry
+    Bot(String),
+    Random,
+}
+
+/// A bot described by a JSON file instead of a constructor, e.g.
+///
+/// ```json
+/// {
+///   "name": "pesto_v5",
+///   "player": {"engine": {
+///     "evaluator": {"capture": {"evaluator": "pesto"}},
+///     "searcher": {"timed": {"searcher": "clunky_v5", "time_ms": 1000}}
+///   }}
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BotConfig {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub player: PlayerConfig,
+}
+
+impl BotConfig {
+    pub fn from_json_str(json: &str) -> Result<BotConfig, String> {
+        serde_json::from_str(json).map_err(|err| err.to_string())
+    }
+
+    pub fn from_file(path: &str) -> Result<BotConfig, String> {
+        let json = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        BotConfig::from_json_str(&json).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    pub fn to_json_string(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn build(&self) -> Result<Pokemon, String> {
+        build_player(&self.player)
+    }
+}
+
+pub fn build_player(config: &PlayerConfig) -> Result<Pokemon, String> {
+    let player: Box<dyn Player> = match config {
+        PlayerConfig::Engine {
+            evaluator,
+            searcher,
+        } => build_engine(build_evaluator(evaluator)?, searcher)?,
+        PlayerConfig::IfElse {
+            condition,
+            then,
+            otherwise,
+        } => {
+            let condition = condition.clone();
+            Box::new(IfElseEngine::new(
+                build_player(then)?,
+                build_player(otherwise)?,
+                move |chess_board: &ChessBoard| condition.evaluate(chess_board),
+            ))
+        }
+        PlayerConfig::Bot(name) => match find_bot(name) {
+            Some(bot) => return Ok(bot.create()),
+            None => return Err(format!("Unknown bot: {}", name)),
+        },
+        PlayerConfig::Random => Box::new(RandomEngine::new()),
+    };
+
+    Ok(Pokemon::new(player))
+}
+
+fn with_quiescence(quiescence: bool) -> Option<QuiescenceSearch> {
+    quiescence.then(|| QuiescenceSearch::new(QuiescenceConfig::default()))
+}
+
+fn build_engine(
+    evaluator: DynEvaluator,
+    config: &SearcherConfig,
+) -> Result<Box<dyn Player>, String> {
+    let player: Box<dyn Player> = match config {
+        SearcherConfig::MinMax { depth } => {
+            Box::new(SearcherEngine::new(evaluator, MinMaxSearcher::new(*depth)))
+        }
+        SearcherConfig::AlphaBeta { depth, quiescence } => {
+            let searcher = match with_quiescence(*quiescence) {
+                Some(quiescence) => AlphaBetaSearcher::with_quiescence(*depth, quiescence),
+                None => AlphaBetaSearcher::new(*depth),
+            };
+            Box::new(SearcherEngine::new(evaluator, searcher))
+        }
+        SearcherConfig::Deep { depth, quiescence } => {
+            let searcher = match with_quiescence(*quiescence) {
+                Some(quiescence) => DeepSearch::with_quiescence(*depth, quiescence),
+                None => DeepSearch::new(*depth),
+            };
+            Box::new(SearcherEngine::new(evaluator, searcher))
+        }
+        SearcherConfig::IterativeDeepening { depth, quiescence } => {
+            let searcher = match with_quiescence(*quiescence) {
+                Some(quiescence) => IterativeDeepening::with_quiescence(*depth, quiescence),
+                None => IterativeDeepening::new(*depth),
+            };
+            Box::new(SearcherEngine::new(evaluator, searcher))
+        }
+        SearcherConfig::RepetitionAware { depth } => {
+            Box::new(SearcherEngine::new(evaluator, RepetitionAwareSearcher::new(*depth)))
+        }
+        SearcherConfig::Mcts {
+            policy,
+            max_depth,
+            iterations,
+            c_puct,
+            win,
+        } => {
+            let PolicyConfig::Softmax {
+                evaluator: policy_evaluator,
+                temperature,
+            } = policy;
+            let policy = SoftmaxPolicy::new(build_evaluator(policy_evaluator)?, *temperature);
+
+            if *max_depth == 0 {
+                return Err(String::from("mcts needs a max_depth of at least 1"));
+            }
+            let searcher = match win {
+                Some(win) => MonteCarloTreeSearch::with_win(
+                    policy,
+                    *max_depth,
+                    *iterations,
+                    *c_puct,
+                    Score::from_pawns(*win),
+                ),
+                None => MonteCarloTreeSearch::new(policy, *max_depth, *iterations, *c_puct),
+            };
+            Box::new(SearcherEngine::new(evaluator, searcher))
+        }
+        SearcherConfig::Timed {
+            searcher,
+            time_ms,
+            search_config,
+        } => {
+            if search_config.is_some() && *searcher != TimedSearcherConfig::ClunkyV5 {
+                return Err(String::from("search_config is only used by clunky_v5"));
+            }
+
+            let timed_searcher: Box<dyn TimedSearcher<DynEvaluator>> = match searcher {
+                TimedSearcherConfig::MinMax { depth } => Box::new(MinMax::new(*depth)),
+                TimedSearcherConfig::AlphaBeta { depth } => Box::new(AlphaBeta::new(*depth)),
+                TimedSearcherConfig::IterativeDeepening => Box::new(TimedIterativeDeepening::new()),
+                TimedSearcherConfig::Clunky => Box::new(ClunkySearcher::new()),
+                TimedSearcherConfig::ClunkyV2 => Box::new(ClunkySearcherV2::new()),
+                TimedSearcherConfig::ClunkyV3 => Box::new(ClunkySearcherV3::new()),
+                TimedSearcherConfig::ClunkyV4 => Box::new(ClunkySearcherV4::new()),
+                TimedSearcherConfig::ClunkyV5 => Box::new(ClunkySearcherV5::with_config(
+                    search_config.unwrap_or_default(),
+                )),
+            };
+
+            Box::new(SearcherEngine::new(
+                evaluator,
+                TimedSearcherWrapper::new(timed_searcher, Duration::from_millis(*time_ms)),
+            ))
+        }
+    };
+
+    Ok(player)
+}
+
+pub fn build_evaluator(config: &EvaluatorConfig) -> Result<DynEvaluator, String> {
+    match config {
+        EvaluatorConfig::Cache { evaluator } => {
+            Ok(Box::new(CacheEvaluator::new(build_evaluator(evaluator)?)))
+        }
+        _ => Ok(build_shared_evaluator(config)?),
+    }
+}
+
+fn build_shared_policy(config: &PolicyConfig) -> Result<SoftmaxPolicy<SharedEvaluator>, String> {
+    let PolicyConfig::Softmax {
+        evaluator,
+        temperature,
+    } = config;
+
+    Ok(SoftmaxPolicy::new(build_shared_evaluator(evaluator)?, *temperature))
+}
+
+pub fn build_shared_evaluator(config: &EvaluatorConfig) -> Result<SharedEvaluator, String> {
+    let evaluator: SharedEvaluator = match config {
+        EvaluatorConfig::Trivial => Box::new(TrivialEvaluator::new()),
+        EvaluatorConfig::Material => Box::new(MaterialEvaluator::new()),
+        EvaluatorConfig::Pressure => Box::new(PressureEvaluator::new()),
+        EvaluatorConfig::Positional => Box::new(PositionalEvaluator::new()),
+        EvaluatorConfig::KingSafety => Box::new(KingSafetyEvaluator::new()),
+        EvaluatorConfig::KingDanger => Box::new(KingDangerEvaluator::new()),
+        EvaluatorConfig::Dynamic => Box::new(DynamicEvaluator::new()),
+        EvaluatorConfig::Structure => Box::new(StructureEvaluator::new()),
+        EvaluatorConfig::PawnStructure => Box::new(PawnStructureEvaluator::new()),
+        EvaluatorConfig::Pesto => Box::new(PestoEvaluator::new()),
+        EvaluatorConfig::PestoParams { path } => Box::new(PestoEvaluator::from_file(path)?),
+        EvaluatorConfig::Activity => Box::new(ActivityEvaluator::new()),
+        EvaluatorConfig::ActivityParams { path } => Box::new(ActivityEvaluator::from_file(path)?),
+        EvaluatorConfig::Nnue { path } => Box::new(NnueEvaluator::from_file(path)?),
+        EvaluatorConfig::Learned { path } => Box::new(LearnedEvaluator::from_file(path)?),
+        EvaluatorConfig::Capture {
+            evaluator,
+            piece_values,
+        } => {
+            let evaluator = build_shared_evaluator(evaluator)?;
+            match piece_values {
+                Some(piece_values) => {
+                    Box::new(CaptureEvaluator::with_piece_values(evaluator, *piece_values))
+                }
+                None => Box::new(CaptureEvaluator::new(evaluator)),
+            }
+        }
+        EvaluatorConfig::Cache { .. } => {
+            return Err(String::from(
+                "cache is only allowed at the top of an evaluator tree",
+            ))
+        }
+        EvaluatorConfig::Negate { evaluator } => {
+            Box::new(NegateEvaluator::new(build_shared_evaluator(evaluator)?))
+        }
+        EvaluatorConfig::Clamp {
+            evaluator,
+            threshold,
+        } => Box::new(ClampEvaluator::new(
+            build_shared_evaluator(evaluator)?,
+            Score::from_pawns(*threshold),
+        )),
+        EvaluatorConfig::Threshold {
+            evaluator,
+            threshold,
+        } => Box::new(ThresholdEvaluator::new(
+            build_shared_evaluator(evaluator)?,
+            Score::from_pawns(*threshold),
+        )),
+        EvaluatorConfig::Linear { terms } => {
+            let (first, rest) = terms
+                .split_first()
+                .ok_or("linear needs at least one term")?;
+
+            // The terms are chained two by two, the running sum keeping a
+            // weight of 1
+            let mut sum: SharedEvaluator = Box::new(LinearEvaluator::new(
+                build_shared_evaluator(&first.evaluator)?,
+                TrivialEvaluator::new(),
+                [first.weight, 0.],
+            ));
+            for term in rest {
+                sum = Box::new(LinearEvaluator::new(
+                    sum,
+                    build_shared_evaluator(&term.evaluator)?,
+                    [1., term.weight],
+                ));
+            }
+            sum
+        }
+        EvaluatorConfig::Rollout {
+            policy,
+            evaluator,
+            max_depth,
+            win,
+        } => {
+            if *max_depth == 0 {
+                return Err(String::from("rollout needs a max_depth of at least 1"));
+            }
+            let policy = build_shared_policy(policy)?;
+            let evaluator = build_shared_evaluator(evaluator)?;
+            match win {
+                Some(win) => Box::new(RolloutEvaluator::with_win(
+                    policy,
+                    evaluator,
+                    *max_depth,
+                    Score::from_pawns(*win),
+                )),
+                None => Box::new(RolloutEvaluator::new(policy, evaluator, *max_depth)),
+            }
+        }
+        EvaluatorConfig::StochasticRollout {
+            policy,
+            evaluator,
+            max_depth,
+            iterations,
+            win,
+        } => {
+            if *max_depth == 0 {
+                return Err(String::from("stochastic_rollout needs a max_depth of at least 1"));
+            }
+            let policy = build_shared_policy(policy)?;
+            let evaluator = build_shared_evaluator(evaluator)?;
+            match win {
+                Some(win) => Box::new(StochasticRollout::with_win(
+                    policy,
+                    evaluator,
+                    *max_depth,
+                    *iterations,
+                    Score::from_pawns(*win),
+                )),
+                None => {
+                    Box::new(StochasticRollout::new(policy, evaluator, *max_depth, *iterations))
+                }
+            }
+        }
+    };
+
+    Ok(evaluator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::timed_searchers::clunky_searcher_v5::{
+        ExtensionConfig, PruningConfig, WindowConfig,
+    };
+
+    fn timed_search_config(json: &str) -> Option<SearchConfig> {
+        match serde_json::from_str(json).unwrap() {
+            SearcherConfig::Timed { search_config, .. } => search_config,
+            _ => panic!("Not a timed searcher: {}", json),
+        }
+    }
+
+    #[test]
+    fn clunky_v5_takes_a_search_config() {
+        let json = r#"{"timed": {"searcher": "clunky_v5", "time_ms": 100}}"#;
+        assert_eq!(timed_search_config(json), None);
+
+        let json = r#"{"timed": {"searcher": "clunky_v5", "time_ms": 100, "search_config": {
+            "pruning": {"null_move": false, "razoring": false},
+            "windows": {"aspiration_windows": false, "aspiration_delta": 0.25},
+            "extensions": {"singular": false, "max_extensions": 4}
+        }}}"#;
+        let expected = SearchConfig {
+            pruning: PruningConfig {
+                null_move: false,
+                razoring: false,
+                ..PruningConfig::all()
+            },
+            windows: WindowConfig {
+                aspiration_windows: false,
+                aspiration_delta: Score::cp(25),
+                ..WindowConfig::default()
+            },
+            extensions: ExtensionConfig {
+                singular: false,
+                max_extensions: 4,
+                ..ExtensionConfig::all()
+            },
+            ..SearchConfig::default()
+        };
+        assert_eq!(timed_search_config(json), Some(expected));
+
+        let player = |searcher: &str| {
+            let json = format!(
+                r#"{{"timed": {{"searcher": {}, "time_ms": 100, "search_config": {{}}}}}}"#,
+                searcher
+            );
+            build_player(&PlayerConfig::Engine {
+                evaluator: EvaluatorConfig::Pesto,
+                searcher: serde_json::from_str(&json).unwrap(),
+            })
+        };
+        assert!(player(r#""clunky_v5""#).is_ok());
+        assert_eq!(
+            player(r#""clunky_v4""#).err(),
+            Some(String::from("search_config is only used by clunky_v5"))
+        );
+    }
+
+    #[test]
+    fn shipped_configs_survive_a_round_trip() {
+        let paths = [
+            "configs/learned_linear.json",
+            "configs/pesto_v5.json",
+            "configs/pikachu.json",
+        ];
+
+        for path in paths {
+            let config = BotConfig::from_file(path).unwrap();
+            let json = config.to_json_string();
+
+            assert_eq!(BotConfig::from_json_str(&json), Ok(config.clone()), "{}", path);
+            assert!(config.build().is_ok(), "{}", path);
+        }
+    }
+}
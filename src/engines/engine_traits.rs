@@ -1,45 +1,178 @@
 use crate::chess_server::chess_types::chess_board::{MoveContainer, MOVE_CONTAINER_SIZE};
 use crate::chess_server::chess_types::{ChessBoard, Move};
-use crate::chess_server::game::Player;
+use crate::chess_server::game::{Decision, GameContext, Player};
+use crate::engines::eval_trace::EvalTrace;
+use crate::engines::score::Score;
+use crate::engines::time_manager::{ClockState, SearchTimer, TimeManager};
+
+use log::info;
 
-use ordered_float::OrderedFloat;
 use smallvec::SmallVec;
 
+use std::cell::Cell;
 use std::time::Duration;
 
-pub const EVAL_WHITE_WON: OrderedFloat<f64> = OrderedFloat(1000.);
-pub const EVAL_BLACK_WON: OrderedFloat<f64> = OrderedFloat(-1000.);
-pub const EVAL_DRAW: OrderedFloat<f64> = OrderedFloat(0.);
+// Results from the point of view of white, for the searchers that keep
+// scores that way
+pub const EVAL_WHITE_WON: Score = Score::mate_in(0);
+pub const EVAL_BLACK_WON: Score = Score::mated_in(0);
+pub const EVAL_DRAW: Score = Score::DRAW;
+
+// Engines take a draw offer when both players see the game within this of 0
+const DRAW_ACCEPT_SCORE: Score = Score::cp(10);
 
 pub trait Evaluator {
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64>;
+    /// Evaluation from the point of view of the side to move.
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score;
+
+    fn evaluate_white(&self, chess_board: &ChessBoard) -> Score {
+        self.evaluate(chess_board)
+            .relative(chess_board.get_turn_color())
+    }
 
     // As method to allow for dynamic dispatch
     fn get_name(&self) -> String;
+
+    /// Evaluation broken down into the terms it is made of, following the
+    /// way the evaluator is composed. Evaluators without terms are a leaf.
+    fn explain(&self, chess_board: &ChessBoard) -> EvalTrace {
+        EvalTrace::new(&self.get_name(), self.evaluate_white(chess_board).to_pawns())
+    }
+
+    /// Called by searchers when they make a move, with the position after it,
+    /// for evaluators that keep state along the line being searched.
+    fn push_position(&self, _chess_board: &ChessBoard) {}
+
+    /// Called by searchers when they unmake the move of the last position
+    /// pushed.
+    fn pop_position(&self) {}
+}
+
+// Lets evaluator trees built at runtime be used wherever an evaluator is
+impl<E: Evaluator + ?Sized> Evaluator for Box<E> {
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        (**self).evaluate(chess_board)
+    }
+
+    fn get_name(&self) -> String {
+        (**self).get_name()
+    }
+
+    fn explain(&self, chess_board: &ChessBoard) -> EvalTrace {
+        (**self).explain(chess_board)
+    }
+
+    fn push_position(&self, chess_board: &ChessBoard) {
+        (**self).push_position(chess_board)
+    }
+
+    fn pop_position(&self) {
+        (**self).pop_position()
+    }
 }
 
 pub trait Searcher<E: Evaluator> {
     fn search(&self, chess_board: &ChessBoard, evaluator: &E) -> Move;
+
+    // Called before every search by players that know about the game, for
+    // searchers that care about the clock or the previous positions
+    fn set_game_context(&self, _context: &GameContext) {}
+
+    // Score of the last search from the point of view of the side to move
+    fn get_last_score(&self) -> Option<Score> {
+        None
+    }
 }
 
 pub trait TimedSearcher<E: Evaluator> {
     fn search(&self, chess_board: &ChessBoard, evaluator: &E, avail_time: Duration) -> Option<Move>;
+
+    // Searchers that don't know about soft and hard limits only get the soft
+    // one. Only ClunkySearcherV5 overrides this and stretches its time, the
+    // older clunky searchers think for the soft limit of the time manager.
+    fn search_with_timer(
+        &self,
+        chess_board: &ChessBoard,
+        evaluator: &E,
+        timer: SearchTimer,
+    ) -> Option<Move> {
+        self.search(chess_board, evaluator, timer.soft_limit())
+    }
+
+    fn get_last_score(&self) -> Option<Score> {
+        None
+    }
 }
 
+/// Turns a [`TimedSearcher`] into a [`Searcher`].
+///
+/// Every move gets `avail_time`, unless the game context comes with a clock,
+/// in which case the time manager decides how long to think.
 pub struct TimedSearcherWrapper<E: Evaluator> {
     timed_searcher: Box<dyn TimedSearcher<E>>,
-    avail_time: Duration
+    avail_time: Duration,
+    time_manager: TimeManager,
+    clock: Cell<Option<ClockState>>,
+    // Whether the last move was a single reply, played without a search
+    skipped_search: Cell<bool>,
 }
 
 impl<E: Evaluator> TimedSearcherWrapper<E> {
     pub fn new(timed_searcher: Box<dyn TimedSearcher<E>>, avail_time: Duration) -> Self {
-        TimedSearcherWrapper { timed_searcher , avail_time }
+        TimedSearcherWrapper::with_time_manager(timed_searcher, avail_time, TimeManager::new())
+    }
+
+    pub fn with_time_manager(
+        timed_searcher: Box<dyn TimedSearcher<E>>,
+        avail_time: Duration,
+        time_manager: TimeManager,
+    ) -> Self {
+        TimedSearcherWrapper {
+            timed_searcher,
+            avail_time,
+            time_manager,
+            clock: Cell::new(None),
+            skipped_search: Cell::new(false),
+        }
     }
 }
 
 impl<E: Evaluator> Searcher<E> for TimedSearcherWrapper<E> {
+    fn set_game_context(&self, context: &GameContext) {
+        self.clock.set(context.clock);
+    }
+
+    fn get_last_score(&self) -> Option<Score> {
+        if self.skipped_search.get() {
+            return None;
+        }
+        self.timed_searcher.get_last_score()
+    }
+
     fn search(&self, chess_board: &ChessBoard, evaluator: &E) -> Move {
-        self.timed_searcher.search(chess_board, evaluator, self.avail_time).unwrap()
+        let color = chess_board.get_turn_color();
+        let allowed_moves = chess_board.get_allowed_moves(color);
+
+        // No need to think when there is a single reply
+        self.skipped_search.set(allowed_moves.len() == 1);
+        if allowed_moves.len() == 1 {
+            return allowed_moves[0];
+        }
+
+        match self.clock.get() {
+            Some(clock) => {
+                let limits = self.time_manager.limits(&clock, color);
+                info!("Time limits: soft {:?}, hard {:?}", limits.soft, limits.hard);
+
+                self.timed_searcher
+                    .search_with_timer(chess_board, evaluator, SearchTimer::new(limits))
+                    .unwrap()
+            }
+            None => self
+                .timed_searcher
+                .search(chess_board, evaluator, self.avail_time)
+                .unwrap(),
+        }
     }
 }
 
@@ -59,8 +192,22 @@ impl<E: Evaluator, S: Searcher<E>> SearcherEngine<E, S> {
 }
 
 impl<E: Evaluator, S: Searcher<E>> Player for SearcherEngine<E, S> {
-    fn select_move(&self, chess_board: &ChessBoard) -> Move {
-        self.searcher.search(chess_board, &self.evaluator)
+    fn decide(&self, context: &GameContext) -> Decision {
+        self.searcher.set_game_context(context);
+        let mv = self.searcher.search(&context.chess_board, &self.evaluator);
+
+        let level = |score: Option<Score>| {
+            score.is_some_and(|score| -DRAW_ACCEPT_SCORE <= score && score <= DRAW_ACCEPT_SCORE)
+        };
+        if context.draw_offered && level(self.get_last_score()) && level(context.opponent_score) {
+            return Decision::AcceptDraw;
+        }
+
+        Decision::Move(mv)
+    }
+
+    fn get_last_score(&self) -> Option<Score> {
+        self.searcher.get_last_score()
     }
 }
 
@@ -72,3 +219,54 @@ pub trait Policy {
         moves: &MoveContainer,
     ) -> SmallVec<[f64; MOVE_CONTAINER_SIZE]>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::evaluators::MaterialEvaluator;
+    use crate::engines::timed_searchers::clunky_searcher_v5::ClunkySearcherV5;
+
+    #[test]
+    fn single_replies_have_no_score() {
+        let searcher = TimedSearcherWrapper::new(
+            Box::new(ClunkySearcherV5::new()),
+            Duration::from_millis(50),
+        );
+        let evaluator = MaterialEvaluator::new();
+
+        let chess_board = ChessBoard::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        searcher.search(&chess_board, &evaluator);
+        assert!(searcher.get_last_score().is_some());
+
+        // Only Kh7 gets out of check
+        let chess_board = ChessBoard::from_fen("R6k/8/8/8/8/8/8/6RK b - - 0 1").unwrap();
+        let mv = searcher.search(&chess_board, &evaluator);
+        assert_eq!(mv.to_string(), "h8h7");
+        assert_eq!(searcher.get_last_score(), None);
+    }
+
+    #[test]
+    fn draws_are_accepted_when_both_players_agree() {
+        let engine = SearcherEngine::new(
+            MaterialEvaluator::new(),
+            TimedSearcherWrapper::new(Box::new(ClunkySearcherV5::new()), Duration::from_millis(20)),
+        );
+        let decide = |fen: &str, opponent_score: Option<Score>| {
+            let chess_board = ChessBoard::from_fen(fen).unwrap();
+            let context = GameContext {
+                draw_offered: true,
+                opponent_score,
+                ..GameContext::from_board(&chess_board)
+            };
+            engine.decide(&context)
+        };
+
+        let level = "4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1";
+        assert_eq!(decide(level, Some(Score::cp(5))), Decision::AcceptDraw);
+        assert_ne!(decide(level, Some(Score::cp(-200))), Decision::AcceptDraw);
+        assert_ne!(decide(level, None), Decision::AcceptDraw);
+
+        let won = "4k3/pppppppp/8/8/8/8/PPPPPPPP/R3K3 w - - 0 1";
+        assert_ne!(decide(won, Some(Score::cp(0))), Decision::AcceptDraw);
+    }
+}
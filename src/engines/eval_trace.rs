@@ -0,0 +1,169 @@
+use std::fmt::Write;
+
+/// Named contribution to an evaluation, with the terms it is made of.
+///
+/// A term with a weight adds `weight * value` to its parent. Terms without
+/// one feed a parent that isn't a sum, e.g. a clamp.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalTrace {
+    pub name: String,
+    pub value: f64,
+    pub weight: Option<f64>,
+    pub children: Vec<EvalTrace>,
+    /// Whether the value is nothing but the sum of the weighted children, which
+    /// lets an enclosing sum take the children over.
+    pub is_sum: bool,
+}
+
+impl EvalTrace {
+    pub fn new(name: &str, value: f64) -> EvalTrace {
+        EvalTrace::with_children(name, value, Vec::new())
+    }
+
+    pub fn with_children(name: &str, value: f64, children: Vec<EvalTrace>) -> EvalTrace {
+        EvalTrace {
+            name: name.to_owned(),
+            value,
+            weight: None,
+            children,
+            is_sum: false,
+        }
+    }
+
+    pub fn sum(name: &str, value: f64, children: Vec<EvalTrace>) -> EvalTrace {
+        EvalTrace {
+            is_sum: true,
+            ..EvalTrace::with_children(name, value, children)
+        }
+    }
+
+    pub fn weighted(mut self, weight: f64) -> EvalTrace {
+        self.weight = Some(weight);
+        self
+    }
+
+    pub fn get_contribution(&self) -> Option<f64> {
+        self.weight.map(|weight| weight * self.value)
+    }
+
+    /// One line per term, indented by depth.
+    pub fn to_table(&self) -> String {
+        let mut rows = Vec::new();
+        self.collect_rows(0, &mut rows);
+
+        let name_width = rows
+            .iter()
+            .map(|(depth, trace)| 2 * depth + trace.name.len())
+            .chain([4])
+            .max()
+            .unwrap();
+
+        let mut table = String::new();
+        writeln!(
+            table,
+            "{:<name_width$}  {:>8}  {:>8}  {:>12}",
+            "Term", "Weight", "Value", "Contribution"
+        )
+        .unwrap();
+
+        let optional = |value: Option<f64>| match value {
+            Some(value) => format!("{:.3}", value),
+            None => String::new(),
+        };
+
+        for (depth, trace) in rows {
+            writeln!(
+                table,
+                "{:<name_width$}  {:>8}  {:>8.3}  {:>12}",
+                format!("{}{}", "  ".repeat(depth), trace.name),
+                optional(trace.weight),
+                trace.value,
+                optional(trace.get_contribution())
+            )
+            .unwrap();
+        }
+
+        table
+    }
+
+    fn collect_rows<'a>(&'a self, depth: usize, rows: &mut Vec<(usize, &'a EvalTrace)>) {
+        rows.push((depth, self));
+        for child in self.children.iter() {
+            child.collect_rows(depth + 1, rows);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_server::chess_types::ChessBoard;
+    use crate::engines::engine_traits::Evaluator;
+    use crate::engines::evaluators::{LinearEvaluator, MaterialEvaluator, PestoEvaluator};
+
+    const FEN: &str = "r1bqk2r/pp3ppp/2n1pn2/3p4/1bPP4/2N2N2/PP2BPPP/R2QKB1R w KQkq - 0 8";
+
+    fn weighted_sum(trace: &EvalTrace) -> f64 {
+        trace.children.iter().map(|child| child.get_contribution().unwrap()).sum()
+    }
+
+    #[test]
+    fn pesto_terms_add_up_to_the_evaluation() {
+        let chess_board = ChessBoard::from_fen(FEN).unwrap();
+        let trace = PestoEvaluator::new().explain(&chess_board);
+
+        // The evaluation is rounded to centipawns
+        assert!((weighted_sum(&trace) - trace.value).abs() <= 0.01, "{:?}", trace);
+        for phase in trace.children.iter() {
+            assert!((weighted_sum(phase) - phase.value).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn nested_linear_terms_are_flattened_and_add_up() {
+        let chess_board = ChessBoard::from_fen(FEN).unwrap();
+        let inner =
+            LinearEvaluator::new(MaterialEvaluator::new(), PestoEvaluator::new(), [2., 0.5]);
+        let evaluator = LinearEvaluator::new(inner, PestoEvaluator::new(), [0.5, 1.]);
+        let trace = evaluator.explain(&chess_board);
+
+        let weights: Vec<_> = trace.children.iter().map(|child| child.weight.unwrap()).collect();
+        assert_eq!(weights, [1., 0.25, 1.]);
+        assert!(trace.is_sum);
+        assert!((weighted_sum(&trace) - trace.value).abs() <= 0.02, "{:?}", trace);
+
+        // Without weight the term is left out
+        let evaluator =
+            LinearEvaluator::new(MaterialEvaluator::new(), PestoEvaluator::new(), [0., 1.]);
+        assert_eq!(evaluator.explain(&chess_board).children.len(), 1);
+    }
+
+    #[test]
+    fn tables_show_one_indented_row_per_term() {
+        let pawns = EvalTrace::new("Pawns", 1.).weighted(1.);
+        let trace = EvalTrace::with_children(
+            "Total",
+            1.5,
+            vec![
+                EvalTrace::with_children("Material", 1., vec![pawns]).weighted(2.),
+                EvalTrace::new("Mobility", -0.5).weighted(1.),
+            ],
+        );
+        let table = trace.to_table();
+        let rows: Vec<Vec<_>> =
+            table.lines().map(|line| line.split_whitespace().collect()).collect();
+
+        assert_eq!(
+            rows,
+            [
+                vec!["Term", "Weight", "Value", "Contribution"],
+                vec!["Total", "1.500"],
+                vec!["Material", "2.000", "1.000", "2.000"],
+                vec!["Pawns", "1.000", "1.000", "1.000"],
+                vec!["Mobility", "1.000", "-0.500", "-0.500"],
+            ]
+        );
+        assert!(table.lines().nth(3).unwrap().starts_with("    Pawns"));
+        assert!(table.lines().nth(4).unwrap().starts_with("  Mobility"));
+    }
+}
@@ -0,0 +1,502 @@
+use crate::chess_server::chess_types::{ChessBoard, Color, Piece};
+use crate::engines::engine_traits::*;
+use crate::engines::eval_trace::EvalTrace;
+use crate::engines::evaluators::pawn_structure_evaluator::{relative_rank, rows_ahead};
+use crate::engines::evaluators::pesto_evaluator::game_phase;
+use crate::engines::score::Score;
+
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+use std::ops::Range;
+
+/// Weights of the piece activity terms in centipawns, middlegame then
+/// endgame. Mobility tables are indexed by the number of safe squares, the
+/// last entry counting for anything above.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActivityParams {
+    pub knight_mobility: [[i32; 2]; 9],
+    pub bishop_mobility: [[i32; 2]; 14],
+    pub rook_mobility: [[i32; 2]; 15],
+    pub queen_mobility: [[i32; 2]; 28],
+    pub knight_outpost: [i32; 2],
+    pub bishop_outpost: [i32; 2],
+    pub rook_open_file: [i32; 2],
+    pub rook_half_open_file: [i32; 2],
+    pub queen_open_file: [i32; 2],
+    pub queen_half_open_file: [i32; 2],
+    pub rook_on_seventh: [i32; 2],
+    pub bishop_pair: [i32; 2],
+    // For every own pawn on the squares of the color of the bishop
+    pub bad_bishop: [i32; 2],
+}
+
+// Half of the classical Stockfish values, PeSTO tables already rewarding
+// pieces on active squares
+impl Default for ActivityParams {
+    fn default() -> Self {
+        ActivityParams {
+            knight_mobility: [
+                [-31, -39],
+                [-26, -28],
+                [-6, -15],
+                [-1, -8],
+                [1, 3],
+                [6, 6],
+                [10, 8],
+                [14, 10],
+                [18, 13],
+            ],
+            bishop_mobility: [
+                [-23, -29],
+                [-10, -12],
+                [7, -4],
+                [14, 6],
+                [19, 10],
+                [26, 20],
+                [26, 28],
+                [30, 29],
+                [31, 32],
+                [34, 36],
+                [39, 39],
+                [41, 43],
+                [45, 44],
+                [48, 49],
+            ],
+            rook_mobility: [
+                [-30, -41],
+                [-12, -7],
+                [0, 8],
+                [1, 21],
+                [2, 36],
+                [7, 50],
+                [10, 51],
+                [15, 61],
+                [20, 66],
+                [20, 69],
+                [20, 76],
+                [22, 80],
+                [28, 82],
+                [29, 85],
+                [33, 87],
+            ],
+            queen_mobility: [
+                [-14, -24],
+                [-8, -14],
+                [-4, -4],
+                [-4, 8],
+                [9, 19],
+                [12, 27],
+                [11, 29],
+                [18, 36],
+                [20, 38],
+                [27, 47],
+                [32, 47],
+                [34, 50],
+                [34, 62],
+                [35, 64],
+                [35, 66],
+                [35, 66],
+                [35, 68],
+                [36, 70],
+                [37, 73],
+                [38, 74],
+                [45, 76],
+                [52, 84],
+                [52, 85],
+                [53, 85],
+                [56, 89],
+                [57, 92],
+                [57, 93],
+                [59, 110],
+            ],
+            knight_outpost: [28, 18],
+            bishop_outpost: [15, 10],
+            rook_open_file: [24, 14],
+            rook_half_open_file: [10, 4],
+            queen_open_file: [3, 4],
+            queen_half_open_file: [2, 2],
+            rook_on_seventh: [10, 20],
+            bishop_pair: [25, 50],
+            bad_bishop: [-2, -4],
+        }
+    }
+}
+
+// Every weight of the parameters, in the order of the fields
+const KNIGHT_MOBILITY: usize = 0;
+const BISHOP_MOBILITY: usize = KNIGHT_MOBILITY + 9;
+const ROOK_MOBILITY: usize = BISHOP_MOBILITY + 14;
+const QUEEN_MOBILITY: usize = ROOK_MOBILITY + 15;
+const KNIGHT_OUTPOST: usize = QUEEN_MOBILITY + 28;
+const BISHOP_OUTPOST: usize = KNIGHT_OUTPOST + 1;
+const ROOK_OPEN_FILE: usize = BISHOP_OUTPOST + 1;
+const ROOK_HALF_OPEN_FILE: usize = ROOK_OPEN_FILE + 1;
+const QUEEN_OPEN_FILE: usize = ROOK_HALF_OPEN_FILE + 1;
+const QUEEN_HALF_OPEN_FILE: usize = QUEEN_OPEN_FILE + 1;
+const ROOK_ON_SEVENTH: usize = QUEEN_HALF_OPEN_FILE + 1;
+const BISHOP_PAIR: usize = ROOK_ON_SEVENTH + 1;
+const BAD_BISHOP: usize = BISHOP_PAIR + 1;
+pub const ACTIVITY_TERMS: usize = BAD_BISHOP + 1;
+
+const TERM_GROUPS: [(&str, Range<usize>); 13] = [
+    ("Knight mobility", KNIGHT_MOBILITY..BISHOP_MOBILITY),
+    ("Bishop mobility", BISHOP_MOBILITY..ROOK_MOBILITY),
+    ("Rook mobility", ROOK_MOBILITY..QUEEN_MOBILITY),
+    ("Queen mobility", QUEEN_MOBILITY..KNIGHT_OUTPOST),
+    ("Knight outposts", KNIGHT_OUTPOST..BISHOP_OUTPOST),
+    ("Bishop outposts", BISHOP_OUTPOST..ROOK_OPEN_FILE),
+    ("Rooks on open files", ROOK_OPEN_FILE..ROOK_HALF_OPEN_FILE),
+    (
+        "Rooks on half-open files",
+        ROOK_HALF_OPEN_FILE..QUEEN_OPEN_FILE,
+    ),
+    (
+        "Queens on open files",
+        QUEEN_OPEN_FILE..QUEEN_HALF_OPEN_FILE,
+    ),
+    (
+        "Queens on half-open files",
+        QUEEN_HALF_OPEN_FILE..ROOK_ON_SEVENTH,
+    ),
+    ("Rooks on the seventh", ROOK_ON_SEVENTH..BISHOP_PAIR),
+    ("Bishop pair", BISHOP_PAIR..BAD_BISHOP),
+    ("Bad bishops", BAD_BISHOP..ACTIVITY_TERMS),
+];
+
+impl ActivityParams {
+    pub fn from_file(path: &str) -> Result<ActivityParams, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        serde_json::from_str(&text).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = serde_json::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(path, text).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    /// Weights in the order of `activity_counts`.
+    pub fn to_terms(&self) -> [[i32; 2]; ACTIVITY_TERMS] {
+        let mut terms = [[0; 2]; ACTIVITY_TERMS];
+
+        terms[KNIGHT_MOBILITY..BISHOP_MOBILITY].copy_from_slice(&self.knight_mobility);
+        terms[BISHOP_MOBILITY..ROOK_MOBILITY].copy_from_slice(&self.bishop_mobility);
+        terms[ROOK_MOBILITY..QUEEN_MOBILITY].copy_from_slice(&self.rook_mobility);
+        terms[QUEEN_MOBILITY..KNIGHT_OUTPOST].copy_from_slice(&self.queen_mobility);
+        terms[KNIGHT_OUTPOST] = self.knight_outpost;
+        terms[BISHOP_OUTPOST] = self.bishop_outpost;
+        terms[ROOK_OPEN_FILE] = self.rook_open_file;
+        terms[ROOK_HALF_OPEN_FILE] = self.rook_half_open_file;
+        terms[QUEEN_OPEN_FILE] = self.queen_open_file;
+        terms[QUEEN_HALF_OPEN_FILE] = self.queen_half_open_file;
+        terms[ROOK_ON_SEVENTH] = self.rook_on_seventh;
+        terms[BISHOP_PAIR] = self.bishop_pair;
+        terms[BAD_BISHOP] = self.bad_bishop;
+
+        terms
+    }
+
+    pub fn from_terms(terms: &[[i32; 2]; ACTIVITY_TERMS]) -> ActivityParams {
+        ActivityParams {
+            knight_mobility: terms[KNIGHT_MOBILITY..BISHOP_MOBILITY].try_into().unwrap(),
+            bishop_mobility: terms[BISHOP_MOBILITY..ROOK_MOBILITY].try_into().unwrap(),
+            rook_mobility: terms[ROOK_MOBILITY..QUEEN_MOBILITY].try_into().unwrap(),
+            queen_mobility: terms[QUEEN_MOBILITY..KNIGHT_OUTPOST].try_into().unwrap(),
+            knight_outpost: terms[KNIGHT_OUTPOST],
+            bishop_outpost: terms[BISHOP_OUTPOST],
+            rook_open_file: terms[ROOK_OPEN_FILE],
+            rook_half_open_file: terms[ROOK_HALF_OPEN_FILE],
+            queen_open_file: terms[QUEEN_OPEN_FILE],
+            queen_half_open_file: terms[QUEEN_HALF_OPEN_FILE],
+            rook_on_seventh: terms[ROOK_ON_SEVENTH],
+            bishop_pair: terms[BISHOP_PAIR],
+            bad_bishop: terms[BAD_BISHOP],
+        }
+    }
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+/// How many times every weight applies to the position, white minus black.
+/// The evaluation is linear in the weights once the game phase is known,
+/// which is what the tuner relies on.
+pub fn activity_counts(chess_board: &ChessBoard) -> [i32; ACTIVITY_TERMS] {
+    let mut counts = [0; ACTIVITY_TERMS];
+
+    // Rows of the pawns of every color and file as masks, squares attacked by
+    // the pawns, and pawns on light and dark squares
+    let mut pawns = [[0u8; 8]; 2];
+    let mut pawn_attacks = [0u64; 2];
+    let mut pawns_by_square_color = [[0; 2]; 2];
+    let mut bishops = [0; 2];
+
+    for (square, content) in chess_board.iter_coordinates() {
+        let Some(content) = content else {
+            continue;
+        };
+        if content.get_piece() != Piece::Pawn {
+            continue;
+        }
+
+        let us = color_index(content.get_color());
+        let (row, col) = square.get_coordinates();
+        pawns[us][col as usize] |= 1 << row;
+        pawns_by_square_color[us][((row + col) % 2) as usize] += 1;
+
+        for target in chess_board.squares_attacked_by_piece(&square) {
+            pawn_attacks[us] |= 1 << target.get_index();
+        }
+    }
+
+    for (square, content) in chess_board.iter_coordinates() {
+        let Some(content) = content else {
+            continue;
+        };
+
+        let color = content.get_color();
+        let us = color_index(color);
+        let them = color_index(color.opposite());
+        let sign = match color {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        let (row, col) = square.get_coordinates();
+        let rank = relative_rank(color, row);
+        let square_bit = 1 << square.get_index();
+
+        let mobility_table = match content.get_piece() {
+            Piece::Knight => KNIGHT_MOBILITY..BISHOP_MOBILITY,
+            Piece::Bishop => BISHOP_MOBILITY..ROOK_MOBILITY,
+            Piece::Rook => ROOK_MOBILITY..QUEEN_MOBILITY,
+            Piece::Queen => QUEEN_MOBILITY..KNIGHT_OUTPOST,
+            Piece::Pawn | Piece::King => continue,
+        };
+
+        // Squares not taken by own pieces nor attacked by enemy pawns
+        let mobility = chess_board
+            .squares_attacked_by_piece(&square)
+            .iter()
+            .filter(|target| {
+                !chess_board.contains_piece_of_color(target, color)
+                    && pawn_attacks[them] & (1 << target.get_index()) == 0
+            })
+            .count();
+        counts[mobility_table.start + mobility.min(mobility_table.len() - 1)] += sign;
+
+        let enemy_adjacent_pawns = [col.wrapping_sub(1), col + 1]
+            .iter()
+            .filter(|&&adjacent_col| adjacent_col < 8)
+            .fold(0, |mask, &adjacent_col| {
+                mask | pawns[them][adjacent_col as usize]
+            });
+        let outpost = (3..=5).contains(&rank)
+            && pawn_attacks[us] & square_bit != 0
+            && enemy_adjacent_pawns & rows_ahead(color, row) == 0;
+
+        let file_pawns = [pawns[0][col as usize], pawns[1][col as usize]];
+        let open_file = file_pawns[0] | file_pawns[1] == 0;
+        let half_open_file = !open_file && file_pawns[us] == 0;
+
+        match content.get_piece() {
+            Piece::Knight => {
+                if outpost {
+                    counts[KNIGHT_OUTPOST] += sign;
+                }
+            }
+            Piece::Bishop => {
+                if outpost {
+                    counts[BISHOP_OUTPOST] += sign;
+                }
+                bishops[us] += 1;
+                counts[BAD_BISHOP] += sign * pawns_by_square_color[us][((row + col) % 2) as usize];
+            }
+            Piece::Rook => {
+                if open_file {
+                    counts[ROOK_OPEN_FILE] += sign;
+                } else if half_open_file {
+                    counts[ROOK_HALF_OPEN_FILE] += sign;
+                }
+
+                // Only counts with enemy pawns to take on the seventh or the
+                // enemy king stuck on the eighth
+                if rank == 6 {
+                    let enemy_pawns_on_row = pawns[them].iter().any(|file| file & (1 << row) != 0);
+                    let enemy_king_row =
+                        chess_board.find_king(color.opposite()).get_coordinates().0;
+                    if enemy_pawns_on_row || relative_rank(color, enemy_king_row) == 7 {
+                        counts[ROOK_ON_SEVENTH] += sign;
+                    }
+                }
+            }
+            Piece::Queen => {
+                if open_file {
+                    counts[QUEEN_OPEN_FILE] += sign;
+                } else if half_open_file {
+                    counts[QUEEN_HALF_OPEN_FILE] += sign;
+                }
+            }
+            Piece::Pawn | Piece::King => {}
+        }
+    }
+
+    if bishops[0] >= 2 {
+        counts[BISHOP_PAIR] += 1;
+    }
+    if bishops[1] >= 2 {
+        counts[BISHOP_PAIR] -= 1;
+    }
+
+    counts
+}
+
+/// Mobility, outposts, rooks and queens on open files, rooks on the seventh,
+/// bishop pair and bad bishops, tapered by the game phase as in PeSTO. The
+/// weights can be fitted by the tuner.
+#[derive(Clone)]
+pub struct ActivityEvaluator {
+    terms: [[i32; 2]; ACTIVITY_TERMS],
+}
+
+impl ActivityEvaluator {
+    pub fn new() -> ActivityEvaluator {
+        ActivityEvaluator::with_params(&ActivityParams::default())
+    }
+
+    pub fn with_params(params: &ActivityParams) -> ActivityEvaluator {
+        ActivityEvaluator {
+            terms: params.to_terms(),
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<ActivityEvaluator, String> {
+        Ok(ActivityEvaluator::with_params(&ActivityParams::from_file(
+            path,
+        )?))
+    }
+
+    // Tapered score in pawns of some of the terms
+    fn score(&self, counts: &[i32; ACTIVITY_TERMS], terms: Range<usize>, phase: i32) -> f64 {
+        let mut mg = 0;
+        let mut eg = 0;
+        for term in terms {
+            mg += counts[term] * self.terms[term][0];
+            eg += counts[term] * self.terms[term][1];
+        }
+
+        (mg * phase + eg * (24 - phase)) as f64 / 2_400.
+    }
+}
+
+unsafe impl Send for ActivityEvaluator {}
+unsafe impl Sync for ActivityEvaluator {}
+
+impl Evaluator for ActivityEvaluator {
+    fn get_name(&self) -> String {
+        "ActivityEvaluator".to_string()
+    }
+
+    #[inline(always)]
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        let counts = activity_counts(chess_board);
+
+        Score::from_pawns(self.score(&counts, 0..ACTIVITY_TERMS, game_phase(chess_board)))
+            .relative(chess_board.get_turn_color())
+    }
+
+    fn explain(&self, chess_board: &ChessBoard) -> EvalTrace {
+        let counts = activity_counts(chess_board);
+        let phase = game_phase(chess_board);
+
+        let children = TERM_GROUPS
+            .iter()
+            .map(|(name, terms)| {
+                EvalTrace::new(name, self.score(&counts, terms.clone(), phase)).weighted(1.)
+            })
+            .collect();
+
+        EvalTrace::with_children(
+            &format!("ActivityEvaluator (phase {}/24)", phase),
+            self.score(&counts, 0..ACTIVITY_TERMS, phase),
+            children,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(fen: &str) -> [i32; ACTIVITY_TERMS] {
+        activity_counts(&ChessBoard::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn knights_supported_by_pawns_out_of_reach_of_enemy_pawns_are_outposts() {
+        assert_eq!(counts("4k3/8/8/3N4/4P3/8/8/4K3 w - - 0 1")[KNIGHT_OUTPOST], 1);
+        assert_eq!(counts("4k3/8/2p5/3N4/4P3/8/8/4K3 w - - 0 1")[KNIGHT_OUTPOST], 0);
+        assert_eq!(counts("4k3/8/8/3N4/8/8/8/4K3 w - - 0 1")[KNIGHT_OUTPOST], 0);
+    }
+
+    #[test]
+    fn rooks_on_open_and_half_open_files() {
+        let open = counts("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        assert_eq!((open[ROOK_OPEN_FILE], open[ROOK_HALF_OPEN_FILE]), (1, 0));
+
+        let half_open = counts("4k3/8/p7/8/8/8/8/R3K3 w - - 0 1");
+        assert_eq!((half_open[ROOK_OPEN_FILE], half_open[ROOK_HALF_OPEN_FILE]), (0, 1));
+
+        let closed = counts("4k3/8/p7/8/8/8/P7/R3K3 w - - 0 1");
+        assert_eq!((closed[ROOK_OPEN_FILE], closed[ROOK_HALF_OPEN_FILE]), (0, 0));
+    }
+
+    #[test]
+    fn rooks_on_the_seventh_need_pawns_to_take_or_a_king_on_the_eighth() {
+        assert_eq!(counts("4k3/R7/8/8/8/8/8/4K3 w - - 0 1")[ROOK_ON_SEVENTH], 1);
+        assert_eq!(counts("8/R7/3k4/8/8/8/8/4K3 w - - 0 1")[ROOK_ON_SEVENTH], 0);
+        assert_eq!(counts("8/R6p/3k4/8/8/8/8/4K3 w - - 0 1")[ROOK_ON_SEVENTH], 1);
+    }
+
+    #[test]
+    fn bishop_pair() {
+        assert_eq!(counts("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1")[BISHOP_PAIR], 1);
+        assert_eq!(counts("2b1kb2/8/8/8/8/8/8/2B1K3 w - - 0 1")[BISHOP_PAIR], -1);
+    }
+
+    #[test]
+    fn mobility_leaves_out_squares_attacked_by_enemy_pawns() {
+        // The knight goes to a3, c3 and d2
+        assert_eq!(counts("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1")[KNIGHT_MOBILITY + 3], 1);
+
+        // The pawn on b4 takes a3 and c3 away
+        let counts = counts("4k3/8/8/8/1p6/8/8/1N2K3 w - - 0 1");
+        assert_eq!(counts[KNIGHT_MOBILITY + 1], 1);
+        assert_eq!(counts[KNIGHT_MOBILITY + 3], 0);
+    }
+
+    #[test]
+    fn mirrored_positions_have_opposite_counts() {
+        let fen = "r2q1rk1/pp2bppp/2n1pn2/3p4/2PP4/1QN1BN2/PP3PPP/R4RK1 w - - 0 10";
+        let counts = counts(fen);
+        let mirrored = activity_counts(&ChessBoard::from_fen(fen).unwrap().mirrored());
+
+        assert!(counts.iter().any(|&count| count != 0));
+        assert_eq!(mirrored, counts.map(|count| -count));
+    }
+
+    #[test]
+    fn params_round_trip_through_terms() {
+        let params = ActivityParams::default();
+        assert_eq!(ActivityParams::from_terms(&params.to_terms()), params);
+
+        let mut terms = [[0; 2]; ACTIVITY_TERMS];
+        for (index, term) in terms.iter_mut().enumerate() {
+            *term = [index as i32, -(index as i32)];
+        }
+        assert_eq!(ActivityParams::from_terms(&terms).to_terms(), terms);
+    }
+}
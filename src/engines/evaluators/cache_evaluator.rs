@@ -1,15 +1,15 @@
 use crate::engines::engine_traits::*;
+use crate::engines::eval_trace::EvalTrace;
 use std::cell::RefCell;
 
 use crate::chess_server::chess_types::ChessBoard;
+use crate::engines::score::Score;
 use crate::engines::zobrist_hash::ZobristHashMap;
 
-use ordered_float::OrderedFloat;
-
 #[derive(Clone)]
 pub struct CacheEvaluator<E: Evaluator> {
     evaluator: E,
-    cache: RefCell<ZobristHashMap<OrderedFloat<f64>>>,
+    cache: RefCell<ZobristHashMap<Score>>,
 }
 
 unsafe impl<E: Evaluator> Send for CacheEvaluator<E> where E: Send {}
@@ -29,7 +29,7 @@ impl<E: Evaluator> Evaluator for CacheEvaluator<E> {
     }
 
     #[inline(always)]
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
         if let Some(&eval) = self.cache.borrow().get_key_value(chess_board) {
             return eval;
         }
@@ -38,4 +38,17 @@ impl<E: Evaluator> Evaluator for CacheEvaluator<E> {
         self.cache.borrow_mut().insert(chess_board, eval);
         eval
     }
+
+    fn explain(&self, chess_board: &ChessBoard) -> EvalTrace {
+        let trace = self.evaluator.explain(chess_board);
+        EvalTrace::with_children("CacheEvaluator", trace.value, vec![trace.weighted(1.)])
+    }
+
+    fn push_position(&self, chess_board: &ChessBoard) {
+        self.evaluator.push_position(chess_board)
+    }
+
+    fn pop_position(&self) {
+        self.evaluator.pop_position()
+    }
 }
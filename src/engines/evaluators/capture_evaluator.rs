@@ -1,9 +1,9 @@
-use std::cmp::{max, min};
-
-use crate::chess_server::chess_types::{ChessBoard, Color, Piece};
+use crate::chess_server::chess_types::chess_board::SquareContainer;
+use crate::chess_server::chess_types::{ChessBoard, Move, Piece, Square};
+use crate::engines::attack_map::AttackMap;
 use crate::engines::engine_traits::*;
-
-use ordered_float::OrderedFloat;
+use crate::engines::eval_trace::EvalTrace;
+use crate::engines::score::Score;
 
 const VALUE_PAWN: f64 = 1.;
 const VALUE_BISHOP: f64 = 3.1;
@@ -11,9 +11,15 @@ const VALUE_KNIGHT: f64 = 3.;
 const VALUE_ROOK: f64 = 5.;
 const VALUE_QUEEN: f64 = 9.;
 
+// Exchanges are resolved in centipawns
+const SEE_SCALE: f64 = 100.;
+
+/// Adds the gain of the best capture of the side to move to the evaluation,
+/// as given by the static exchange evaluation of the captures.
 #[derive(Clone)]
 pub struct CaptureEvaluator<E: Evaluator> {
     evaluator: E,
+    piece_values: [f64; 5],
 }
 
 unsafe impl<E: Evaluator> Send for CaptureEvaluator<E> where E: Send {}
@@ -22,95 +28,162 @@ unsafe impl<E: Evaluator> Sync for CaptureEvaluator<E> where E: Sync {}
 
 impl<E: Evaluator> CaptureEvaluator<E> {
     pub fn new(evaluator: E) -> CaptureEvaluator<E> {
-        CaptureEvaluator { evaluator }
+        CaptureEvaluator::with_piece_values(
+            evaluator,
+            [VALUE_PAWN, VALUE_BISHOP, VALUE_KNIGHT, VALUE_ROOK, VALUE_QUEEN],
+        )
     }
-}
 
-impl<E: Evaluator> Evaluator for CaptureEvaluator<E> {
-    fn get_name(&self) -> String {
-        format!("CaptureEvaluator({})", self.evaluator.get_name())
+    // Values of pawn, bishop, knight, rook and queen, in pawns
+    pub fn with_piece_values(evaluator: E, piece_values: [f64; 5]) -> CaptureEvaluator<E> {
+        CaptureEvaluator {
+            evaluator,
+            piece_values,
+        }
     }
 
-    #[inline(always)]
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
-        let eval = self.evaluator.evaluate(chess_board);
-        let mut adjusted_eval = eval;
-
-        let mut pressure = [0; 64];
-        let mut content_value = [0.; 64];
-
-        // Looks for hanging pieces on one side only
-        for (coordinate, content) in chess_board.iter_coordinates() {
-            if let Some(content) = content {
-                let get_piece_value = |piece: Piece| match piece {
-                    Piece::Pawn => VALUE_PAWN,
-                    Piece::Bishop => VALUE_BISHOP,
-                    Piece::Knight => VALUE_KNIGHT,
-                    Piece::Rook => VALUE_ROOK,
-                    Piece::Queen => VALUE_QUEEN,
-                    _ => 0.,
+    // Best capture of the side to move with its static exchange evaluation
+    fn best_capture(&self, chess_board: &ChessBoard) -> Option<(Move, Score)> {
+        let [pawn, bishop, knight, rook, queen] = self.piece_values;
+        let values =
+            [pawn, bishop, knight, rook, queen, 0.].map(|value| (value * SEE_SCALE).round() as i32);
+
+        let color = chess_board.get_turn_color();
+        let attack_map = AttackMap::shared(chess_board);
+        let mut best_capture = None;
+        let mut best_gain = 0;
+
+        // Pieces left hanging or attacked by cheaper ones are tried first.
+        // No capture wins more than the piece it takes, unless it promotes,
+        // so once they are looked at most of the other captures are ruled out
+        let mask = |squares: SquareContainer| {
+            squares
+                .iter()
+                .fold(0_u64, |mask, square| mask | 1 << square.get_index())
+        };
+        let likely_gains = mask(attack_map.hanging_pieces(color.opposite()))
+            | mask(attack_map.attacked_by_lower_value(color.opposite()));
+        let targets = attack_map.get_pieces(color.opposite()) & attack_map.attacked_squares(color);
+
+        for targets in [likely_gains, targets & !likely_gains] {
+            for (square, content) in chess_board.iter_coordinates() {
+                let Some(content) = content else {
+                    continue;
                 };
+                if content.get_color() != color {
+                    continue;
+                }
 
-                let piece_value = get_piece_value(content.get_piece());
-
-                match content.get_color() {
-                    Color::White => content_value[coordinate.get_index() as usize] = piece_value,
-                    Color::Black => content_value[coordinate.get_index() as usize] = -piece_value,
-                };
+                let captures = attack_map.get_attacks_from(&square) & targets;
+                for index in 0..64 {
+                    if captures & 1 << index == 0 {
+                        continue;
+                    }
+                    let target = Square::from_index(index).unwrap();
+                    let victim = chess_board.get_square_content(&target).unwrap();
+
+                    // The king can't take a defended piece
+                    if content.get_piece() == Piece::King
+                        && attack_map.is_attacked(&target, color.opposite())
+                    {
+                        continue;
+                    }
 
-                for coord in chess_board.squares_attacked_by_piece(&coordinate) {
-                    // Does not verify pins for example
-                    match content.get_color() {
-                        Color::White => pressure[coord.get_index() as usize] += 1,
-                        Color::Black => pressure[coord.get_index() as usize] -= 1,
+                    let mv = match content.get_piece() {
+                        Piece::Pawn if matches!(target.get_coordinates().0, 0 | 7) => {
+                            Move::new_promotion_move(square, target, Piece::Queen)
+                        }
+                        _ => Move::new_normal_move(square, target),
                     };
 
-                    // Check if we can capture a piece of larger value
-                    if content.get_color() == chess_board.get_turn_color() {
-                        if let Some(color_piece) = chess_board.get_square_content(&coord) {
-                            if color_piece.get_color() != content.get_color() {
-                                // We can capture the color piece!
-                                let this_piece_value = get_piece_value(color_piece.get_piece());
-
-                                match content.get_color() {
-                                    Color::White => {
-                                        adjusted_eval =
-                                            max(eval, eval + this_piece_value - piece_value)
-                                    }
-                                    Color::Black => {
-                                        adjusted_eval =
-                                            min(eval, eval - this_piece_value + piece_value)
-                                    }
-                                };
-                            }
-                        }
+                    if mv.get_promotion_piece().is_none()
+                        && values[victim.get_piece() as usize] <= best_gain
+                    {
+                        continue;
+                    }
+
+                    let gain = chess_board.see_with_values(&mv, &values);
+                    if gain > best_gain {
+                        best_gain = gain;
+                        best_capture = Some(mv);
                     }
                 }
             }
         }
 
-        // We have to check if the player who played has hanging pieces.
-        // If so, we assume the other player will take the hanging piece and we
-        // evaluate from there
+        best_capture.map(|mv| (mv, Score::cp(best_gain)))
+    }
+}
 
-        // Naive check for now...
-        match chess_board.get_turn_color() {
-            Color::White => {
-                for index in 0..64 {
-                    if pressure[index] > 0 && content_value[index] < 0. {
-                        adjusted_eval = max(adjusted_eval, eval - content_value[index]);
-                    }
-                }
-            }
-            Color::Black => {
-                for index in 0..64 {
-                    if pressure[index] < 0 && content_value[index] > 0. {
-                        adjusted_eval = min(adjusted_eval, eval - content_value[index]);
-                    }
-                }
-            }
+impl<E: Evaluator> Evaluator for CaptureEvaluator<E> {
+    fn get_name(&self) -> String {
+        format!("CaptureEvaluator({})", self.evaluator.get_name())
+    }
+
+    #[inline(always)]
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        let eval = self.evaluator.evaluate(chess_board);
+
+        match self.best_capture(chess_board) {
+            Some((_, gain)) => eval.saturating_eval_add(gain),
+            None => eval,
+        }
+    }
+
+    fn explain(&self, chess_board: &ChessBoard) -> EvalTrace {
+        let trace = self.evaluator.explain(chess_board);
+        let (name, gain) = match self.best_capture(chess_board) {
+            Some((mv, gain)) => (format!("Captures ({})", mv), gain),
+            None => ("Captures".to_string(), Score::cp(0)),
         };
 
-        adjusted_eval
+        // Traces are from the point of view of white
+        let gain = gain.relative(chess_board.get_turn_color()).to_pawns();
+        let adjustment = EvalTrace::new(&name, gain).weighted(1.);
+
+        EvalTrace::with_children(
+            "CaptureEvaluator",
+            self.evaluate_white(chess_board).to_pawns(),
+            vec![trace.weighted(1.), adjustment],
+        )
+    }
+
+    fn push_position(&self, chess_board: &ChessBoard) {
+        self.evaluator.push_position(chess_board)
+    }
+
+    fn pop_position(&self) {
+        self.evaluator.pop_position()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::evaluators::{MaterialEvaluator, ThresholdEvaluator};
+
+    #[test]
+    fn captures_in_won_positions_are_not_mates() {
+        let evaluator = CaptureEvaluator::new(ThresholdEvaluator::new(
+            MaterialEvaluator::new(),
+            Score::cp(300),
+        ));
+        let fens = [
+            "4k3/8/8/8/8/8/3p4/QQQQK3 w - - 0 1",
+            "qqqqk3/3P4/8/8/8/8/8/4K3 b - - 0 1",
+            // The side to move is lost but still wins a pawn
+            "qqqqk3/8/8/8/8/8/3p4/3QK3 w - - 0 1",
+        ];
+
+        for fen in fens {
+            let chess_board = ChessBoard::from_fen(fen).unwrap();
+            let eval = evaluator.evaluate(&chess_board);
+
+            assert!(!eval.is_mate(), "{}", fen);
+            assert_eq!(
+                evaluator.explain(&chess_board).value,
+                evaluator.evaluate_white(&chess_board).to_pawns()
+            );
+        }
     }
 }
@@ -1,13 +1,15 @@
 use crate::engines::engine_traits::*;
+use crate::engines::eval_trace::EvalTrace;
 
 use crate::chess_server::chess_types::ChessBoard;
+use crate::engines::score::Score;
 
-use ordered_float::OrderedFloat;
-
+/// Squeezes evaluations between minus and plus the threshold: small ones are
+/// about unchanged, large ones get close to the threshold.
 #[derive(Clone)]
 pub struct ClampEvaluator<E: Evaluator> {
     evaluator: E,
-    threshold: OrderedFloat<f64>,
+    threshold: Score,
 }
 
 unsafe impl<E: Evaluator> Send for ClampEvaluator<E> where E: Send {}
@@ -15,12 +17,17 @@ unsafe impl<E: Evaluator> Send for ClampEvaluator<E> where E: Send {}
 unsafe impl<E: Evaluator> Sync for ClampEvaluator<E> where E: Sync {}
 
 impl<E: Evaluator> ClampEvaluator<E> {
-    pub fn new(evaluator: E, threshold: OrderedFloat<f64>) -> ClampEvaluator<E> {
+    pub fn new(evaluator: E, threshold: Score) -> ClampEvaluator<E> {
         ClampEvaluator {
             evaluator,
             threshold,
         }
     }
+
+    fn clamp(&self, pawns: f64) -> Score {
+        let threshold = self.threshold.to_pawns();
+        Score::from_pawns(threshold * (pawns / threshold).tanh())
+    }
 }
 
 impl<E: Evaluator> Evaluator for ClampEvaluator<E> {
@@ -29,9 +36,54 @@ impl<E: Evaluator> Evaluator for ClampEvaluator<E> {
     }
 
     #[inline(always)]
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
-        let eval = self.evaluator.evaluate(chess_board);
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        self.clamp(self.evaluator.evaluate(chess_board).to_pawns())
+    }
+
+    fn explain(&self, chess_board: &ChessBoard) -> EvalTrace {
+        let trace = self.evaluator.explain(chess_board);
+        EvalTrace::with_children(
+            &format!("ClampEvaluator (within {})", self.threshold),
+            self.clamp(trace.value).to_pawns(),
+            vec![trace],
+        )
+    }
+
+    fn push_position(&self, chess_board: &ChessBoard) {
+        self.evaluator.push_position(chess_board)
+    }
 
-        OrderedFloat((eval / self.threshold).tanh())
+    fn pop_position(&self) {
+        self.evaluator.pop_position()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::evaluators::MaterialEvaluator;
+
+    #[test]
+    fn clamps_in_centipawns_as_explained() {
+        let evaluator = ClampEvaluator::new(MaterialEvaluator::new(), Score::cp(300));
+        let cases = [
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", 0),
+            // A pawn is about kept, a queen is squeezed under the threshold
+            ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", 96),
+            ("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1", -96),
+            ("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", 299),
+            ("3qk3/8/8/8/8/8/8/QQQQK3 b - - 0 1", -300),
+        ];
+
+        for (fen, centipawns) in cases {
+            let chess_board = ChessBoard::from_fen(fen).unwrap();
+            let eval = evaluator.evaluate(&chess_board);
+
+            assert_eq!(eval, Score::cp(centipawns), "{}", fen);
+            assert_eq!(
+                evaluator.explain(&chess_board).value,
+                evaluator.evaluate_white(&chess_board).to_pawns()
+            );
+        }
     }
 }
@@ -1,7 +1,7 @@
 use crate::chess_server::chess_types::{ChessBoard, Color};
+use crate::engines::attack_map::AttackMap;
 use crate::engines::engine_traits::*;
-
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
 #[derive(Clone)]
 pub struct DynamicEvaluator;
@@ -10,6 +10,29 @@ impl DynamicEvaluator {
     pub fn new() -> DynamicEvaluator {
         DynamicEvaluator {}
     }
+
+    // In pawns for white, before it gets clamped into a Score
+    pub fn evaluate_pawns(&self, chess_board: &ChessBoard) -> f64 {
+        // 1 point of advantage for player who has the move
+        let mut eval = if chess_board.get_turn_color() == Color::White {
+            1.0
+        } else {
+            -1.0
+        };
+
+        let attack_map = AttackMap::shared(chess_board);
+
+        for (coord, _) in chess_board.iter_coordinates() {
+            let attacks = attack_map.get_attackers(&coord, Color::White) as f64
+                - attack_map.get_attackers(&coord, Color::Black) as f64;
+
+            let (row, col) = coord.get_coordinates();
+            let rad = (row as f64 - 3.5).abs() + (col as f64 - 3.5).abs();
+            eval += ((1.0 - rad) / 8.0).exp() * attacks;
+        }
+
+        eval
+    }
 }
 
 unsafe impl Send for DynamicEvaluator {}
@@ -21,29 +44,7 @@ impl Evaluator for DynamicEvaluator {
     }
 
     #[inline(always)]
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
-        // 1 point of advantage for player who has the move
-        let mut eval = if chess_board.get_turn_color() == Color::White {
-            1.0
-        } else {
-            -1.0
-        };
-
-        for (coordinate, content) in chess_board.iter_coordinates() {
-            if let Some(content) = content {
-                let sign = match content.get_color() {
-                    Color::White => 1.,
-                    Color::Black => -1.,
-                };
-
-                for coord in chess_board.squares_attacked_by_piece(&coordinate) {
-                    let (row, col) = coord.get_coordinates();
-                    let rad = (row as f64 - 3.5).abs() + (col as f64 - 3.5).abs();
-                    eval += ((1.0 - rad) / 8.0).exp() * sign;
-                }
-            }
-        }
-
-        OrderedFloat(eval)
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        Score::from_pawns(self.evaluate_pawns(chess_board)).relative(chess_board.get_turn_color())
     }
 }
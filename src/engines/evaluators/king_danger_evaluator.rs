@@ -0,0 +1,310 @@
+use crate::chess_server::chess_types::chess_board::SquareContainer;
+use crate::chess_server::chess_types::{ChessBoard, Color, Piece};
+use crate::engines::attack_map::AttackMap;
+use crate::engines::engine_traits::*;
+use crate::engines::eval_trace::EvalTrace;
+use crate::engines::evaluators::pawn_structure_evaluator::forward;
+use crate::engines::evaluators::pesto_evaluator::game_phase;
+use crate::engines::score::Score;
+
+// Attack units for every square of the king zone attacked by a knight,
+// bishop, rook or queen
+const ATTACK_WEIGHTS: [i32; 4] = [2, 2, 3, 5];
+
+// Attack units for a piece that can give check on a square the defender
+// doesn't cover
+const SAFE_CHECK_UNITS: [i32; 4] = [3, 2, 4, 6];
+
+// Attack units for files next to the king without own pawns in front of it
+const HALF_OPEN_FILE_UNITS: i32 = 2;
+const OPEN_FILE_UNITS: i32 = 3;
+
+const MAX_UNITS: i32 = 99;
+
+// Centipawns lost for a number of attack units, growing slowly for a single
+// attacker and quickly once several pieces join in
+const DANGER: [i32; 100] = [
+    0, 0, 1, 2, 3, 5, 7, 9, 12, 15, 18, 22, 26, 30, 35, 39, 44, 50, 56, 62, 68, 75, 82, 85, 89, 97,
+    105, 113, 122, 131, 140, 150, 169, 180, 191, 202, 213, 225, 237, 248, 260, 272, 283, 295, 307,
+    319, 330, 342, 354, 366, 377, 389, 401, 412, 424, 436, 448, 459, 471, 483, 494, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+];
+
+// Centipawns by number of rows between the king and the closest own pawn in
+// front of it, on the file of the king and the files next to it
+const PAWN_SHIELD: [i32; 3] = [0, 12, 6];
+
+// Centipawns lost by number of rows between the king and the closest enemy
+// pawn in front of it, on the same files
+const PAWN_STORM: [i32; 5] = [0, 0, 16, 10, 4];
+
+fn attacker_index(piece: Piece) -> Option<usize> {
+    match piece {
+        Piece::Knight => Some(0),
+        Piece::Bishop => Some(1),
+        Piece::Rook => Some(2),
+        Piece::Queen => Some(3),
+        Piece::Pawn | Piece::King => None,
+    }
+}
+
+fn mask(squares: &SquareContainer) -> u64 {
+    squares
+        .iter()
+        .fold(0, |mask, square| mask | 1 << square.get_index())
+}
+
+/// Safety of one king, in centipawns from the point of view of its side.
+#[derive(Clone, Copy, Debug, Default)]
+struct KingSafety {
+    attackers: i32,
+    attack_units: i32,
+    danger: i32,
+    pawn_shield: i32,
+    pawn_storm: i32,
+}
+
+impl KingSafety {
+    fn score(&self) -> i32 {
+        self.pawn_shield - self.pawn_storm - self.danger
+    }
+}
+
+/// King safety from attack units.
+///
+/// Pieces attacking the squares around the king and in front of it, safe
+/// checks and files without pawns in front of the king add attack units,
+/// which a table turns into a penalty growing faster than the attack. Pawn
+/// shield and pawn storms are scored on top. Only counts in the middlegame.
+#[derive(Clone)]
+pub struct KingDangerEvaluator;
+
+impl KingDangerEvaluator {
+    pub fn new() -> KingDangerEvaluator {
+        KingDangerEvaluator {}
+    }
+
+    fn king_safety(&self, chess_board: &ChessBoard, color: Color) -> KingSafety {
+        let mut safety = KingSafety::default();
+
+        let king_square = chess_board.find_king(color);
+        let forward = forward(color);
+
+        // Squares around the king, and two rows in front of it
+        let mut zone = SquareContainer::new();
+        chess_board.inplace_squares_attacked_by_king(&king_square, &mut zone);
+        zone.extend((-1..=1).filter_map(|d_col| king_square.add(2 * forward, d_col)));
+        let zone = mask(&zone) | 1 << king_square.get_index();
+
+        // Squares a piece standing on would give check from
+        let mut knight_checks = SquareContainer::new();
+        let mut bishop_checks = SquareContainer::new();
+        let mut rook_checks = SquareContainer::new();
+        chess_board.inplace_squares_attacked_by_knight(&king_square, &mut knight_checks);
+        chess_board.inplace_squares_attacked_by_bishop(&king_square, &mut bishop_checks);
+        chess_board.inplace_squares_attacked_by_rook(&king_square, &mut rook_checks);
+        let (knight_checks, bishop_checks, rook_checks) = (
+            mask(&knight_checks),
+            mask(&bishop_checks),
+            mask(&rook_checks),
+        );
+        let checks = [
+            knight_checks,
+            bishop_checks,
+            rook_checks,
+            bishop_checks | rook_checks,
+        ];
+
+        let attack_map = AttackMap::shared(chess_board);
+        let defended = attack_map.attacked_squares(color);
+        let occupied_by_attacker = attack_map.get_pieces(color.opposite());
+
+        let mut safe_check = false;
+        for (square, content) in chess_board.iter_coordinates() {
+            let Some(content) = content else {
+                continue;
+            };
+            if content.get_color() == color {
+                continue;
+            }
+            let Some(index) = attacker_index(content.get_piece()) else {
+                continue;
+            };
+
+            let attacked = attack_map.get_attacks_from(&square);
+
+            let zone_attacks = (attacked & zone).count_ones() as i32;
+            if zone_attacks > 0 {
+                safety.attackers += 1;
+                safety.attack_units += ATTACK_WEIGHTS[index] * zone_attacks;
+            }
+
+            if attacked & checks[index] & !defended & !occupied_by_attacker != 0 {
+                safety.attack_units += SAFE_CHECK_UNITS[index];
+                safe_check = true;
+            }
+        }
+
+        // Closest pawn in front of the king on every file around it. Enemy
+        // pawns behind it are blocked and don't storm the king
+        for d_col in -1..=1 {
+            let Some(file_square) = king_square.add(0, d_col) else {
+                continue;
+            };
+
+            let mut own_pawn = None;
+            let mut enemy_pawn = None;
+            let mut distance = 1;
+            while let Some(square) = file_square.add(distance * forward, 0) {
+                if let Some(content) = chess_board.get_square_content(&square) {
+                    if content.get_piece() == Piece::Pawn {
+                        if content.get_color() == color {
+                            own_pawn = Some(distance as usize);
+                        } else {
+                            enemy_pawn = Some(distance as usize);
+                        }
+                        break;
+                    }
+                }
+                distance += 1;
+            }
+
+            match own_pawn {
+                Some(distance) => {
+                    safety.pawn_shield += PAWN_SHIELD.get(distance).copied().unwrap_or(0)
+                }
+                None if enemy_pawn.is_none() => safety.attack_units += OPEN_FILE_UNITS,
+                None => safety.attack_units += HALF_OPEN_FILE_UNITS,
+            }
+
+            if let Some(distance) = enemy_pawn {
+                safety.pawn_storm += PAWN_STORM.get(distance).copied().unwrap_or(0);
+            }
+        }
+
+        // A lone attacker can't do much without a check
+        if safety.attackers >= 2 || safe_check {
+            safety.danger = DANGER[safety.attack_units.min(MAX_UNITS) as usize];
+        }
+
+        safety
+    }
+}
+
+unsafe impl Send for KingDangerEvaluator {}
+unsafe impl Sync for KingDangerEvaluator {}
+
+impl Evaluator for KingDangerEvaluator {
+    fn get_name(&self) -> String {
+        "KingDangerEvaluator".to_string()
+    }
+
+    #[inline(always)]
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        let phase = game_phase(chess_board);
+        if phase == 0 {
+            return Score::cp(0);
+        }
+
+        let white = self.king_safety(chess_board, Color::White);
+        let black = self.king_safety(chess_board, Color::Black);
+
+        Score::cp((white.score() - black.score()) * phase / 24)
+            .relative(chess_board.get_turn_color())
+    }
+
+    fn explain(&self, chess_board: &ChessBoard) -> EvalTrace {
+        let phase = game_phase(chess_board);
+
+        let king_trace = |color: Color, sign: f64| {
+            let safety = self.king_safety(chess_board, color);
+
+            let children = vec![
+                EvalTrace::new(
+                    &format!("Attack units ({} attackers)", safety.attackers),
+                    safety.attack_units as f64,
+                ),
+                EvalTrace::new("Danger", -safety.danger as f64 / 100.).weighted(1.),
+                EvalTrace::new("Pawn shield", safety.pawn_shield as f64 / 100.).weighted(1.),
+                EvalTrace::new("Pawn storm", -safety.pawn_storm as f64 / 100.).weighted(1.),
+            ];
+
+            EvalTrace::with_children(
+                &format!("{:?} king", color),
+                safety.score() as f64 / 100.,
+                children,
+            )
+            .weighted(sign * phase as f64 / 24.)
+        };
+
+        EvalTrace::with_children(
+            &format!("KingDangerEvaluator (phase {}/24)", phase),
+            self.evaluate_white(chess_board).to_pawns(),
+            vec![king_trace(Color::White, 1.), king_trace(Color::Black, -1.)],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white_king_safety(fen: &str) -> KingSafety {
+        KingDangerEvaluator::new().king_safety(&ChessBoard::from_fen(fen).unwrap(), Color::White)
+    }
+
+    #[test]
+    fn exposed_kings_under_attack_are_worse_than_castled_ones() {
+        let castled = white_king_safety("r2q2k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1");
+        let exposed = white_king_safety("6k1/5ppp/8/8/2q5/4K3/r7/8 w - - 0 1");
+
+        assert_eq!(castled.attackers, 0);
+        assert_eq!(castled.pawn_shield, 3 * PAWN_SHIELD[1]);
+        assert_eq!(exposed.attackers, 2);
+        assert!(exposed.danger > 0);
+        assert!(exposed.score() < castled.score(), "{:?} {:?}", exposed, castled);
+    }
+
+    #[test]
+    fn a_lone_attacker_needs_a_safe_check() {
+        // The knight attacks f2 and g3 but can't check
+        let safety = white_king_safety("6k1/8/8/8/4n3/8/5PPP/6K1 w - - 0 1");
+        assert_eq!(safety.attackers, 1);
+        assert!(safety.attack_units > 0);
+        assert_eq!(safety.danger, 0);
+
+        // From d4 it can check on e2
+        let safety = white_king_safety("6k1/8/8/8/3n4/8/5PPP/6K1 w - - 0 1");
+        assert_eq!(safety.attackers, 1);
+        assert!(safety.danger > 0);
+    }
+
+    #[test]
+    fn blocked_pawns_do_not_storm_the_king() {
+        let blocked = white_king_safety("6k1/8/8/8/6p1/6P1/8/6K1 w - - 0 1");
+        assert_eq!((blocked.pawn_shield, blocked.pawn_storm), (PAWN_SHIELD[2], 0));
+
+        let storm = white_king_safety("6k1/8/8/8/8/6p1/8/6K1 w - - 0 1");
+        assert_eq!((storm.pawn_shield, storm.pawn_storm), (0, PAWN_STORM[2]));
+    }
+
+    #[test]
+    fn king_safety_only_counts_in_the_middlegame() {
+        let chess_board = ChessBoard::from_fen("6k1/8/8/8/8/6p1/8/6K1 w - - 0 1").unwrap();
+        assert_eq!(game_phase(&chess_board), 0);
+        assert_eq!(KingDangerEvaluator::new().evaluate(&chess_board), Score::cp(0));
+    }
+
+    #[test]
+    fn mirrored_positions_have_opposite_evaluations() {
+        let evaluator = KingDangerEvaluator::new();
+        let fen = "r4rk1/pp3p1p/2n1pqp1/3p4/2PP2Q1/2N1BN2/PP3PPP/R5K1 w - - 0 14";
+        let chess_board = ChessBoard::from_fen(fen).unwrap();
+        let mirrored = chess_board.mirrored();
+
+        assert_ne!(evaluator.evaluate_white(&chess_board), Score::cp(0));
+        assert_eq!(evaluator.evaluate_white(&mirrored), -evaluator.evaluate_white(&chess_board));
+        assert_eq!(evaluator.evaluate(&mirrored), evaluator.evaluate(&chess_board));
+    }
+}
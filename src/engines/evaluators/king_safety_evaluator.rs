@@ -1,7 +1,7 @@
 use crate::chess_server::chess_types::{ChessBoard, Color, Square};
+use crate::engines::attack_map::AttackMap;
 use crate::engines::engine_traits::*;
-
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
 const TURN_ADVANTAGE: f64 = 0.3;
 
@@ -12,18 +12,9 @@ impl KingSafetyEvaluator {
     pub fn new() -> KingSafetyEvaluator {
         KingSafetyEvaluator {}
     }
-}
-
-unsafe impl Send for KingSafetyEvaluator {}
-unsafe impl Sync for KingSafetyEvaluator {}
-
-impl Evaluator for KingSafetyEvaluator {
-    fn get_name(&self) -> String {
-        "KingSafetyEvaluator".to_string()
-    }
 
-    #[inline(always)]
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
+    // In pawns for white, before it gets clamped into a Score
+    pub fn evaluate_pawns(&self, chess_board: &ChessBoard) -> f64 {
         // 1 point of advantage for player who has the move
         let mut eval = if chess_board.get_turn_color() == Color::White {
             TURN_ADVANTAGE
@@ -41,33 +32,36 @@ impl Evaluator for KingSafetyEvaluator {
             ((row0 as i8 - row1 as i8).abs() + (col0 as i8 - col1 as i8).abs()) as f64
         };
 
-        for (coordinate, content) in chess_board.iter_coordinates() {
-            if let Some(content) = content {
-                match content.get_color() {
-                    Color::White => {
-                        let sign = 1.;
-                        for coord in chess_board.squares_attacked_by_piece(&coordinate) {
-                            let (row, col) = coord.get_coordinates();
-                            let rad = (row as f64 - 3.5).abs() + (col as f64 - 3.5).abs();
-                            let distance_to_king = dist(&coord, &coord_black_king);
+        let attack_map = AttackMap::shared(chess_board);
+
+        for (coord, _) in chess_board.iter_coordinates() {
+            let (row, col) = coord.get_coordinates();
+            let rad = (row as f64 - 3.5).abs() + (col as f64 - 3.5).abs();
 
-                            eval += ((1.0 - rad - 3. * distance_to_king) / 8.0).exp() * sign;
-                        }
-                    }
-                    Color::Black => {
-                        let sign = -1.;
-                        for coord in chess_board.squares_attacked_by_piece(&coordinate) {
-                            let (row, col) = coord.get_coordinates();
-                            let rad = (row as f64 - 3.5).abs() + (col as f64 - 3.5).abs();
-                            let distance_to_king = dist(&coord, &coord_white_king);
+            // Attacks of white count near the black king and the other way around
+            let white_attacks = attack_map.get_attackers(&coord, Color::White) as f64;
+            let black_attacks = attack_map.get_attackers(&coord, Color::Black) as f64;
 
-                            eval += ((1.0 - rad - 3. * distance_to_king) / 8.0).exp() * sign;
-                        }
-                    }
-                }
-            }
+            eval +=
+                ((1.0 - rad - 3. * dist(&coord, &coord_black_king)) / 8.0).exp() * white_attacks;
+            eval -=
+                ((1.0 - rad - 3. * dist(&coord, &coord_white_king)) / 8.0).exp() * black_attacks;
         }
 
-        OrderedFloat(eval)
+        eval
+    }
+}
+
+unsafe impl Send for KingSafetyEvaluator {}
+unsafe impl Sync for KingSafetyEvaluator {}
+
+impl Evaluator for KingSafetyEvaluator {
+    fn get_name(&self) -> String {
+        "KingSafetyEvaluator".to_string()
+    }
+
+    #[inline(always)]
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        Score::from_pawns(self.evaluate_pawns(chess_board)).relative(chess_board.get_turn_color())
     }
 }
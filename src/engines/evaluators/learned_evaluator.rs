@@ -0,0 +1,473 @@
+use crate::chess_server::chess_types::{ChessBoard, Color, Piece};
+use crate::chess_server::io::utils::PIECE_PLANES;
+use crate::engines::engine_traits::*;
+use crate::engines::eval_trace::EvalTrace;
+use crate::engines::evaluators::{
+    CaptureEvaluator, DynamicEvaluator, KingSafetyEvaluator, MaterialEvaluator,
+    PositionalEvaluator, PressureEvaluator, TrivialEvaluator,
+};
+use crate::engines::score::Score;
+
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+
+/// Inputs of a model, named after the columns of the CSV written by
+/// `chess datagen` and the piece counts computed in the notebooks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Feature {
+    #[serde(rename = "dynamic_eval")]
+    DynamicEval,
+    #[serde(rename = "king_safety_eval")]
+    KingSafetyEval,
+    #[serde(rename = "material_eval")]
+    MaterialEval,
+    #[serde(rename = "positional_eval")]
+    PositionalEval,
+    #[serde(rename = "pressure_eval")]
+    PressureEval,
+    #[serde(rename = "capture_eval")]
+    CaptureEval,
+    #[serde(rename = "WHITE_PAWN")]
+    WhitePawn,
+    #[serde(rename = "WHITE_BISHOP")]
+    WhiteBishop,
+    #[serde(rename = "WHITE_KNIGHT")]
+    WhiteKnight,
+    #[serde(rename = "WHITE_ROOK")]
+    WhiteRook,
+    #[serde(rename = "WHITE_QUEEN")]
+    WhiteQueen,
+    #[serde(rename = "BLACK_PAWN")]
+    BlackPawn,
+    #[serde(rename = "BLACK_BISHOP")]
+    BlackBishop,
+    #[serde(rename = "BLACK_KNIGHT")]
+    BlackKnight,
+    #[serde(rename = "BLACK_ROOK")]
+    BlackRook,
+    #[serde(rename = "BLACK_QUEEN")]
+    BlackQueen,
+}
+
+impl Feature {
+    // Name in the model files
+    pub fn get_name(&self) -> String {
+        serde_json::to_value(self).unwrap().as_str().unwrap().to_owned()
+    }
+
+    pub fn evaluate(&self, chess_board: &ChessBoard) -> f64 {
+        let count = |color: Color, piece: Piece| {
+            chess_board
+                .iter_coordinates()
+                .filter(|(_, content)| {
+                    content.is_some_and(|content| {
+                        content.get_color() == color && content.get_piece() == piece
+                    })
+                })
+                .count() as f64
+        };
+
+        match self {
+            // The models were fitted on the evaluations before they were
+            // clamped, from the point of view of white and in pawns
+            Feature::DynamicEval => DynamicEvaluator::new().evaluate_pawns(chess_board),
+            Feature::KingSafetyEval => KingSafetyEvaluator::new().evaluate_pawns(chess_board),
+            Feature::MaterialEval => MaterialEvaluator::new().evaluate_pawns(chess_board),
+            Feature::PositionalEval => PositionalEvaluator::new().evaluate_pawns(chess_board),
+            Feature::PressureEval => PressureEvaluator::new().evaluate_pawns(chess_board),
+            // As in the rows written by datagen, the gain is at most a queen
+            Feature::CaptureEval => CaptureEvaluator::new(TrivialEvaluator::new())
+                .evaluate_white(chess_board)
+                .to_pawns(),
+            Feature::WhitePawn => count(Color::White, Piece::Pawn),
+            Feature::WhiteBishop => count(Color::White, Piece::Bishop),
+            Feature::WhiteKnight => count(Color::White, Piece::Knight),
+            Feature::WhiteRook => count(Color::White, Piece::Rook),
+            Feature::WhiteQueen => count(Color::White, Piece::Queen),
+            Feature::BlackPawn => count(Color::Black, Piece::Pawn),
+            Feature::BlackBishop => count(Color::Black, Piece::Bishop),
+            Feature::BlackKnight => count(Color::Black, Piece::Knight),
+            Feature::BlackRook => count(Color::Black, Piece::Rook),
+            Feature::BlackQueen => count(Color::Black, Piece::Queen),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Activation {
+    #[default]
+    Identity,
+    Relu,
+    Tanh,
+    Logistic,
+}
+
+impl Activation {
+    fn apply(&self, x: f64) -> f64 {
+        match self {
+            Activation::Identity => x,
+            Activation::Relu => x.max(0.),
+            Activation::Tanh => x.tanh(),
+            Activation::Logistic => 1. / (1. + (-x).exp()),
+        }
+    }
+}
+
+/// `weights` has a row for every output, i.e. `coefs_[i].T` of an sklearn
+/// `MLPRegressor`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DenseLayer {
+    pub weights: Vec<Vec<f64>>,
+    pub bias: Vec<f64>,
+    #[serde(default)]
+    pub activation: Activation,
+}
+
+impl DenseLayer {
+    fn forward(&self, input: &[f64]) -> Vec<f64> {
+        self.weights
+            .iter()
+            .zip(self.bias.iter())
+            .map(|(row, bias)| {
+                let sum: f64 = row.iter().zip(input).map(|(weight, x)| weight * x).sum();
+                self.activation.apply(sum + bias)
+            })
+            .collect()
+    }
+}
+
+/// Standardisation of the inputs, as done by an sklearn `StandardScaler`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scaler {
+    pub mean: Vec<f64>,
+    pub scale: Vec<f64>,
+}
+
+/// Model fitted in the notebooks, predicting the evaluation in pawns from
+/// the point of view of white.
+///
+/// Linear models use the named features, e.g. `{"type": "linear",
+/// "features": ["material_eval", "pressure_eval"], "coefficients": [1.0,
+/// 0.01]}`. The inputs of an MLP are the 768 piece planes of
+/// `board_to_string` if `piece_planes` is set, followed by the features.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LearnedModel {
+    Linear {
+        features: Vec<Feature>,
+        coefficients: Vec<f64>,
+        #[serde(default)]
+        intercept: f64,
+    },
+    Mlp {
+        #[serde(default)]
+        piece_planes: bool,
+        #[serde(default)]
+        features: Vec<Feature>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scaler: Option<Scaler>,
+        layers: Vec<DenseLayer>,
+    },
+}
+
+impl LearnedModel {
+    pub fn from_file(path: &str) -> Result<LearnedModel, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        let model: LearnedModel =
+            serde_json::from_str(&text).map_err(|err| format!("{}: {}", path, err))?;
+
+        model.check().map_err(|err| format!("{}: {}", path, err))?;
+        Ok(model)
+    }
+
+    pub fn input_size(&self) -> usize {
+        match self {
+            LearnedModel::Linear { features, .. } => features.len(),
+            LearnedModel::Mlp {
+                piece_planes,
+                features,
+                ..
+            } => features.len() + if *piece_planes { 64 * PIECE_PLANES.len() } else { 0 },
+        }
+    }
+
+    /// Checks that the sizes of the coefficients and layers fit together.
+    pub fn check(&self) -> Result<(), String> {
+        let input_size = self.input_size();
+
+        match self {
+            LearnedModel::Linear { coefficients, .. } => {
+                if coefficients.len() != input_size {
+                    return Err(format!(
+                        "{} coefficients for {} features",
+                        coefficients.len(),
+                        input_size
+                    ));
+                }
+            }
+            LearnedModel::Mlp { scaler, layers, .. } => {
+                if let Some(scaler) = scaler {
+                    if scaler.mean.len() != input_size || scaler.scale.len() != input_size {
+                        return Err(format!("The scaler needs {} values", input_size));
+                    }
+                }
+
+                let mut size = input_size;
+                for (index, layer) in layers.iter().enumerate() {
+                    if layer.bias.len() != layer.weights.len() {
+                        return Err(format!("Layer {} needs as many biases as rows", index));
+                    }
+                    if layer.weights.iter().any(|row| row.len() != size) {
+                        return Err(format!("Every row of layer {} needs {} weights", index, size));
+                    }
+                    size = layer.bias.len();
+                }
+
+                if size != 1 {
+                    return Err(format!("The last layer has {} outputs instead of 1", size));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn predict(&self, chess_board: &ChessBoard) -> f64 {
+        match self {
+            LearnedModel::Linear {
+                features,
+                coefficients,
+                intercept,
+            } => {
+                let sum: f64 = features
+                    .iter()
+                    .zip(coefficients)
+                    .map(|(feature, coefficient)| coefficient * feature.evaluate(chess_board))
+                    .sum();
+
+                intercept + sum
+            }
+            LearnedModel::Mlp {
+                piece_planes,
+                features,
+                scaler,
+                layers,
+            } => {
+                let mut input = Vec::with_capacity(self.input_size());
+                if *piece_planes {
+                    input.extend(piece_plane_values(chess_board));
+                }
+                input.extend(features.iter().map(|feature| feature.evaluate(chess_board)));
+
+                if let Some(scaler) = scaler {
+                    for ((x, mean), scale) in input.iter_mut().zip(&scaler.mean).zip(&scaler.scale)
+                    {
+                        *x = (*x - mean) / scale;
+                    }
+                }
+
+                layers
+                    .iter()
+                    .fold(input, |input, layer| layer.forward(&input))[0]
+            }
+        }
+    }
+}
+
+// Same values as the characters of board_to_string
+fn piece_plane_values(chess_board: &ChessBoard) -> Vec<f64> {
+    let mut values = vec![0.; 64 * PIECE_PLANES.len()];
+
+    for (square, content) in chess_board.iter_coordinates() {
+        let Some(content) = content else {
+            continue;
+        };
+
+        for (plane, piece) in PIECE_PLANES.iter().enumerate() {
+            if *piece == content {
+                values[64 * plane + square.get_index() as usize] = 1.;
+            }
+        }
+    }
+
+    values
+}
+
+/// Evaluates positions with a model fitted in the notebooks, see
+/// `LearnedModel`.
+#[derive(Clone)]
+pub struct LearnedEvaluator {
+    model: LearnedModel,
+}
+
+impl LearnedEvaluator {
+    pub fn new(model: LearnedModel) -> Result<LearnedEvaluator, String> {
+        model.check()?;
+        Ok(LearnedEvaluator { model })
+    }
+
+    pub fn from_file(path: &str) -> Result<LearnedEvaluator, String> {
+        LearnedEvaluator::new(LearnedModel::from_file(path)?)
+    }
+}
+
+impl Evaluator for LearnedEvaluator {
+    fn get_name(&self) -> String {
+        "LearnedEvaluator".to_string()
+    }
+
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        Score::from_pawns(self.model.predict(chess_board)).relative(chess_board.get_turn_color())
+    }
+
+    // The terms of an MLP can't be told apart
+    fn explain(&self, chess_board: &ChessBoard) -> EvalTrace {
+        let eval = self.model.predict(chess_board);
+
+        let LearnedModel::Linear {
+            features,
+            coefficients,
+            intercept,
+        } = &self.model
+        else {
+            return EvalTrace::new("LearnedEvaluator (mlp)", eval);
+        };
+
+        let mut terms: Vec<EvalTrace> = features
+            .iter()
+            .zip(coefficients)
+            .map(|(feature, coefficient)| {
+                EvalTrace::new(&feature.get_name(), feature.evaluate(chess_board))
+                    .weighted(*coefficient)
+            })
+            .collect();
+        terms.push(EvalTrace::new("Intercept", *intercept).weighted(1.));
+
+        EvalTrace::with_children("LearnedEvaluator (linear)", eval, terms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_server::io::utils::board_to_string;
+
+    // Three white pawns against two black pawns and a queen
+    const FEN: &str = "4k3/pp6/8/8/8/1q6/PPP5/4K3 w - - 0 1";
+
+    fn layer(weights: &[&[f64]], bias: &[f64], activation: Activation) -> DenseLayer {
+        DenseLayer {
+            weights: weights.iter().map(|row| row.to_vec()).collect(),
+            bias: bias.to_vec(),
+            activation,
+        }
+    }
+
+    fn mlp() -> LearnedModel {
+        LearnedModel::Mlp {
+            piece_planes: false,
+            features: vec![Feature::WhitePawn, Feature::BlackPawn],
+            scaler: Some(Scaler {
+                mean: vec![1., 1.],
+                scale: vec![2., 1.],
+            }),
+            layers: vec![
+                layer(&[&[1., -1.], &[2., 1.]], &[0.5, -4.], Activation::Relu),
+                layer(&[&[2., 3.]], &[1.], Activation::Identity),
+            ],
+        }
+    }
+
+    #[test]
+    fn linear_models_weigh_their_features() {
+        let model = LearnedModel::Linear {
+            features: vec![Feature::WhitePawn, Feature::BlackQueen],
+            coefficients: vec![0.5, -2.],
+            intercept: 0.25,
+        };
+        let chess_board = ChessBoard::from_fen(FEN).unwrap();
+
+        assert_eq!(model.predict(&chess_board), 1.5 - 2. + 0.25);
+    }
+
+    #[test]
+    fn mlps_scale_their_inputs_and_go_through_every_layer() {
+        let chess_board = ChessBoard::from_fen(FEN).unwrap();
+
+        // Scaled inputs (1, 1), hidden layer relu(0.5, -1) = (0.5, 0)
+        assert_eq!(mlp().predict(&chess_board), 2. * 0.5 + 1.);
+
+        let evaluator = LearnedEvaluator::new(mlp()).unwrap();
+        assert_eq!(evaluator.evaluate_white(&chess_board), Score::cp(200));
+        let chess_board = ChessBoard::from_fen("4k3/pp6/8/8/8/1q6/PPP5/4K3 b - - 0 1").unwrap();
+        assert_eq!(evaluator.evaluate(&chess_board), Score::cp(-200));
+    }
+
+    #[test]
+    fn mismatched_sizes_are_rejected() {
+        let linear = LearnedModel::Linear {
+            features: vec![Feature::WhitePawn, Feature::BlackQueen],
+            coefficients: vec![0.5],
+            intercept: 0.,
+        };
+        assert!(linear.check().is_err());
+        assert!(LearnedEvaluator::new(linear).is_err());
+        assert!(mlp().check().is_ok());
+
+        let broken_mlp = |breaks: fn(&mut Option<Scaler>, &mut Vec<DenseLayer>)| {
+            let mut model = mlp();
+            if let LearnedModel::Mlp { scaler, layers, .. } = &mut model {
+                breaks(scaler, layers);
+            }
+            model.check()
+        };
+        assert!(broken_mlp(|scaler, _| scaler.as_mut().unwrap().mean.push(0.)).is_err());
+        assert!(broken_mlp(|scaler, _| scaler.as_mut().unwrap().scale.truncate(1)).is_err());
+        assert!(broken_mlp(|_, layers| layers[0].weights[1].push(0.)).is_err());
+        assert!(broken_mlp(|_, layers| layers[0].bias.push(0.)).is_err());
+        assert!(broken_mlp(|_, layers| {
+            layers[1] = layer(&[&[1., 1.], &[1., 1.]], &[0., 0.], Activation::Identity)
+        })
+        .is_err());
+        assert!(broken_mlp(|_, layers| layers.clear()).is_err());
+    }
+
+    #[test]
+    fn piece_planes_match_the_datagen_boards() {
+        let chess_board = ChessBoard::starting_position();
+        let board_string: String = piece_plane_values(&chess_board)
+            .iter()
+            .map(|value| if *value == 1. { '1' } else { '0' })
+            .collect();
+
+        assert_eq!(board_string, board_to_string(&chess_board));
+    }
+
+    #[test]
+    fn loads_the_linear_model_of_the_notebooks() {
+        let evaluator = LearnedEvaluator::from_file("models/linear.json").unwrap();
+        assert_eq!(evaluator.model.input_size(), 16);
+
+        let eval = evaluator.model.predict(&ChessBoard::starting_position());
+        assert!(eval.is_finite() && eval.abs() < 1., "{}", eval);
+
+        // A queen up is a lot better
+        let fen = "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let chess_board = ChessBoard::from_fen(fen).unwrap();
+        assert!(evaluator.model.predict(&chess_board) > eval + 5.);
+    }
+
+    #[test]
+    fn features_are_not_clamped() {
+        let chess_board = ChessBoard::from_fen("4k3/PPPPPPPP/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let pressure = Feature::PressureEval.evaluate(&chess_board);
+
+        assert!(pressure > Score::MAX_EVAL.to_pawns());
+        assert_eq!(
+            pressure,
+            PressureEvaluator::new().evaluate_pawns(&chess_board)
+        );
+    }
+}
@@ -1,7 +1,8 @@
 use crate::engines::engine_traits::*;
+use crate::engines::eval_trace::EvalTrace;
 
 use crate::chess_server::chess_types::ChessBoard;
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
 #[derive(Clone)]
 pub struct LinearEvaluator<E1: Evaluator, E2: Evaluator> {
@@ -45,10 +46,47 @@ impl<E1: Evaluator, E2: Evaluator> Evaluator for LinearEvaluator<E1, E2> {
     }
 
     #[inline(always)]
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
-        let eval_1 = self.evaluator_1.evaluate(chess_board);
-        let eval_2 = self.evaluator_2.evaluate(chess_board);
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        let eval_1 = self.evaluator_1.evaluate(chess_board).to_pawns();
+        let eval_2 = self.evaluator_2.evaluate(chess_board).to_pawns();
 
-        OrderedFloat(self.coef[0]) * eval_1 + OrderedFloat(self.coef[1]) * eval_2
+        Score::from_pawns(self.coef[0] * eval_1 + self.coef[1] * eval_2)
+    }
+
+    // Sums of more than two terms are chains of LinearEvaluators, so nested
+    // sums are flattened and terms without weight left out
+    fn explain(&self, chess_board: &ChessBoard) -> EvalTrace {
+        let mut terms = Vec::new();
+
+        for (trace, coef) in [
+            (self.evaluator_1.explain(chess_board), self.coef[0]),
+            (self.evaluator_2.explain(chess_board), self.coef[1]),
+        ] {
+            if coef == 0. {
+                continue;
+            }
+
+            if trace.is_sum {
+                for term in trace.children {
+                    let weight = term.weight.unwrap_or(1.) * coef;
+                    terms.push(term.weighted(weight));
+                }
+            } else {
+                terms.push(trace.weighted(coef));
+            }
+        }
+
+        let eval = self.evaluate_white(chess_board).to_pawns();
+        EvalTrace::sum("LinearEvaluator", eval, terms)
+    }
+
+    fn push_position(&self, chess_board: &ChessBoard) {
+        self.evaluator_1.push_position(chess_board);
+        self.evaluator_2.push_position(chess_board);
+    }
+
+    fn pop_position(&self) {
+        self.evaluator_1.pop_position();
+        self.evaluator_2.pop_position();
     }
 }
@@ -1,7 +1,6 @@
 use crate::chess_server::chess_types::{ChessBoard, Color, Piece};
 use crate::engines::engine_traits::*;
-
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
 const VALUE_PAWN: f64 = 1.;
 const VALUE_BISHOP: f64 = 3.;
@@ -42,15 +41,9 @@ impl MaterialEvaluator {
             value_queen: coef[4],
         }
     }
-}
 
-impl Evaluator for MaterialEvaluator {
-    fn get_name(&self) -> String {
-        "MaterialEvaluator".to_string()
-    }
-
-    #[inline(always)]
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
+    // In pawns for white, before it gets clamped into a Score
+    pub fn evaluate_pawns(&self, chess_board: &ChessBoard) -> f64 {
         let mut eval = 0.;
 
         for (_, content) in chess_board.iter_coordinates() {
@@ -73,6 +66,17 @@ impl Evaluator for MaterialEvaluator {
             }
         }
 
-        OrderedFloat(eval)
+        eval
+    }
+}
+
+impl Evaluator for MaterialEvaluator {
+    fn get_name(&self) -> String {
+        "MaterialEvaluator".to_string()
+    }
+
+    #[inline(always)]
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        Score::from_pawns(self.evaluate_pawns(chess_board)).relative(chess_board.get_turn_color())
     }
 }
@@ -1,11 +1,16 @@
+pub mod activity_evaluator;
 pub mod cache_evaluator;
 pub mod capture_evaluator;
 pub mod clamp_evaluator;
 pub mod dynamic_evaluator;
+pub mod king_danger_evaluator;
 pub mod king_safety_evaluator;
+pub mod learned_evaluator;
 pub mod linear_evaluator;
 pub mod material_evaluator;
 pub mod negate_evaluator;
+pub mod pawn_structure_evaluator;
+pub mod nnue_evaluator;
 pub mod positional_evaluator;
 pub mod pressure_evaluator;
 pub mod rollout_evaluator;
@@ -15,14 +20,19 @@ pub mod threshold_evaluator;
 pub mod trivial_evaluator;
 pub mod pesto_evaluator;
 
+pub use activity_evaluator::ActivityEvaluator;
 pub use cache_evaluator::CacheEvaluator;
 pub use capture_evaluator::CaptureEvaluator;
 pub use clamp_evaluator::ClampEvaluator;
 pub use dynamic_evaluator::DynamicEvaluator;
+pub use king_danger_evaluator::KingDangerEvaluator;
 pub use king_safety_evaluator::KingSafetyEvaluator;
+pub use learned_evaluator::LearnedEvaluator;
 pub use linear_evaluator::LinearEvaluator;
 pub use material_evaluator::MaterialEvaluator;
 pub use negate_evaluator::NegateEvaluator;
+pub use pawn_structure_evaluator::PawnStructureEvaluator;
+pub use nnue_evaluator::NnueEvaluator;
 pub use positional_evaluator::PositionalEvaluator;
 pub use pressure_evaluator::PressureEvaluator;
 pub use rollout_evaluator::RolloutEvaluator;
@@ -1,8 +1,8 @@
 use crate::engines::engine_traits::*;
+use crate::engines::eval_trace::EvalTrace;
 
 use crate::chess_server::chess_types::ChessBoard;
-
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
 #[derive(Clone)]
 pub struct NegateEvaluator<E: Evaluator> {
@@ -25,7 +25,20 @@ impl<E: Evaluator> Evaluator for NegateEvaluator<E> {
     }
 
     #[inline(always)]
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
         -self.evaluator.evaluate(chess_board)
     }
+
+    fn explain(&self, chess_board: &ChessBoard) -> EvalTrace {
+        let trace = self.evaluator.explain(chess_board);
+        EvalTrace::with_children("NegateEvaluator", -trace.value, vec![trace.weighted(-1.)])
+    }
+
+    fn push_position(&self, chess_board: &ChessBoard) {
+        self.evaluator.push_position(chess_board)
+    }
+
+    fn pop_position(&self) {
+        self.evaluator.pop_position()
+    }
 }
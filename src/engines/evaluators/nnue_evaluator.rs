@@ -0,0 +1,185 @@
+use crate::chess_server::chess_types::ChessBoard;
+use crate::engines::engine_traits::*;
+use crate::engines::nnue::{Accumulator, AccumulatorStack, LayerBuffers, NnueNetwork};
+use crate::engines::score::Score;
+
+use std::cell::RefCell;
+use std::sync::Arc;
+
+thread_local! {
+    // Accumulators of the line searched on this thread, with the id of the network
+    static STACK: RefCell<Option<(usize, AccumulatorStack)>> = const { RefCell::new(None) };
+    static LAYER_BUFFERS: RefCell<LayerBuffers> = RefCell::new(LayerBuffers::default());
+}
+
+/// Evaluates positions with an NNUE network.
+///
+/// Searchers push and pop positions as they make and unmake moves, which
+/// keeps a stack of accumulators, one per ply, each updated from the one of
+/// its parent. Positions evaluated without being pushed are updated from the
+/// last accumulator instead, so searchers that don't push still get
+/// incremental updates. Threads keep their own stack, so the evaluator can
+/// be shared between them.
+#[derive(Clone)]
+pub struct NnueEvaluator {
+    network: Arc<NnueNetwork>,
+}
+
+impl NnueEvaluator {
+    pub fn new(network: NnueNetwork) -> NnueEvaluator {
+        NnueEvaluator {
+            network: Arc::new(network),
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<NnueEvaluator, String> {
+        Ok(NnueEvaluator::new(NnueNetwork::from_file(path)?))
+    }
+
+    fn with_stack<T>(
+        &self,
+        chess_board: &ChessBoard,
+        f: impl FnOnce(&NnueNetwork, &mut AccumulatorStack) -> T,
+    ) -> T {
+        let network = &*self.network;
+
+        STACK.with(|stack| {
+            let mut stack = stack.borrow_mut();
+
+            match stack.as_mut() {
+                Some((id, stack)) if *id == network.get_id() => f(network, stack),
+                _ => {
+                    let (_, stack) = stack.insert((
+                        network.get_id(),
+                        AccumulatorStack::new(network, chess_board),
+                    ));
+                    f(network, stack)
+                }
+            }
+        })
+    }
+
+    // The network is trained on centipawns from the point of view of the
+    // side to move
+    fn accumulator_eval(&self, accumulator: &Accumulator, chess_board: &ChessBoard) -> Score {
+        let eval = LAYER_BUFFERS.with(|buffers| {
+            self.network.propagate(
+                accumulator,
+                chess_board.get_turn_color(),
+                &mut buffers.borrow_mut(),
+            )
+        });
+
+        Score::cp(eval).clamp(-Score::MAX_EVAL, Score::MAX_EVAL)
+    }
+}
+
+impl Evaluator for NnueEvaluator {
+    fn get_name(&self) -> String {
+        "NnueEvaluator".to_string()
+    }
+
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        self.with_stack(chess_board, |network, stack| {
+            let accumulator = stack.accumulator(network, chess_board);
+            self.accumulator_eval(accumulator, chess_board)
+        })
+    }
+
+    fn push_position(&self, chess_board: &ChessBoard) {
+        self.with_stack(chess_board, |network, stack| {
+            stack.push(network, chess_board)
+        })
+    }
+
+    fn pop_position(&self) {
+        STACK.with(|stack| {
+            if let Some((id, stack)) = stack.borrow_mut().as_mut() {
+                if *id == self.network.get_id() {
+                    stack.pop();
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::quiescence::{QuiescenceConfig, QuiescenceSearch};
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    // Computes every accumulator from scratch, ignoring pushes and pops
+    struct RefreshEvaluator(NnueEvaluator);
+
+    impl Evaluator for RefreshEvaluator {
+        fn get_name(&self) -> String {
+            "RefreshEvaluator".to_string()
+        }
+
+        fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+            let accumulator = Accumulator::new(&self.0.network, chess_board);
+            self.0.accumulator_eval(&accumulator, chess_board)
+        }
+    }
+
+    #[test]
+    fn searches_see_the_same_evaluations_as_refreshes() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut network = NnueNetwork::zeros(32, 16, 16);
+        network
+            .feature_bias
+            .iter_mut()
+            .for_each(|value| *value = rng.gen_range(-64..64));
+        network
+            .feature_weights
+            .iter_mut()
+            .for_each(|value| *value = rng.gen_range(-64..64));
+        network
+            .hidden1_weights
+            .iter_mut()
+            .for_each(|value| *value = rng.gen());
+        network
+            .hidden2_weights
+            .iter_mut()
+            .for_each(|value| *value = rng.gen());
+        network
+            .output_weights
+            .iter_mut()
+            .for_each(|value| *value = rng.gen());
+
+        let evaluator = NnueEvaluator::new(network);
+        let reference = RefreshEvaluator(evaluator.clone());
+        let search = QuiescenceSearch::new(QuiescenceConfig::default());
+
+        for fen in [
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/1P6/8/8/8/8/6p1/4K3 w - - 0 1",
+        ] {
+            let chess_board = ChessBoard::from_fen(fen).unwrap();
+            let expected = search.search(
+                &chess_board,
+                &reference,
+                0,
+                -Score::INFINITE,
+                Score::INFINITE,
+            );
+
+            // Twice, as the second search starts from the accumulators left behind
+            for _ in 0..2 {
+                let score = search.search(
+                    &chess_board,
+                    &evaluator,
+                    0,
+                    -Score::INFINITE,
+                    Score::INFINITE,
+                );
+                assert_eq!(score, expected, "{}", fen);
+            }
+        }
+    }
+}
@@ -0,0 +1,415 @@
+use crate::chess_server::chess_types::{ChessBoard, Color, Piece, Square};
+use crate::engines::engine_traits::*;
+use crate::engines::eval_trace::EvalTrace;
+use crate::engines::evaluators::pesto_evaluator::game_phase;
+use crate::engines::score::Score;
+use crate::engines::zobrist_hash::PawnHashMap;
+
+use std::cell::RefCell;
+
+const PAWN_HASH_SIZE: usize = 1 << 14;
+
+// Scores in centipawns, middlegame then endgame
+const DOUBLED: [i32; 2] = [-10, -25];
+const ISOLATED: [i32; 2] = [-10, -15];
+const BACKWARD: [i32; 2] = [-8, -12];
+
+// By rank from the point of view of the pawn, the second rank being 1
+const CONNECTED: [[i32; 2]; 8] = [
+    [0, 0],
+    [3, 2],
+    [5, 3],
+    [8, 6],
+    [14, 12],
+    [24, 20],
+    [40, 35],
+    [0, 0],
+];
+const PASSED: [[i32; 2]; 8] = [
+    [0, 0],
+    [5, 10],
+    [8, 15],
+    [12, 25],
+    [25, 50],
+    [45, 90],
+    [75, 140],
+    [0, 0],
+];
+
+// Endgame only, for passed pawns with nothing in front of them
+const FREE_PATH: [i32; 8] = [0, 0, 0, 5, 12, 25, 45, 0];
+
+// Endgame bonus per square of distance between the square in front of a
+// passed pawn and the enemy king, and malus for the own king, multiplied by
+// how far the pawn went
+const ENEMY_KING_DISTANCE: i32 = 5;
+const OWN_KING_DISTANCE: i32 = 2;
+
+const MAX_PHASE: i32 = 24;
+
+// Terms kept in the pawn hash depend on the pawns only, the others are
+// computed for every position
+const TERM_NAMES: [&str; 7] = [
+    "Doubled",
+    "Isolated",
+    "Backward",
+    "Connected",
+    "Passed",
+    "King proximity",
+    "Free path",
+];
+const DOUBLED_TERM: usize = 0;
+const ISOLATED_TERM: usize = 1;
+const BACKWARD_TERM: usize = 2;
+const CONNECTED_TERM: usize = 3;
+const PASSED_TERM: usize = 4;
+const KING_PROXIMITY_TERM: usize = 5;
+const FREE_PATH_TERM: usize = 6;
+
+type Terms = [[i32; 2]; TERM_NAMES.len()];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PawnEntry {
+    terms: Terms,
+    // Squares of the passed pawns of both colors
+    passed: u64,
+}
+
+thread_local! {
+    static PAWN_HASH: RefCell<PawnHashMap<PawnEntry>> =
+        RefCell::new(PawnHashMap::with_capacity(PAWN_HASH_SIZE));
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+fn sign(color: Color) -> i32 {
+    match color {
+        Color::White => 1,
+        Color::Black => -1,
+    }
+}
+
+// Row a pawn of the color moves towards
+pub fn forward(color: Color) -> i8 {
+    match color {
+        Color::White => -1,
+        Color::Black => 1,
+    }
+}
+
+pub fn relative_rank(color: Color, row: u8) -> usize {
+    match color {
+        Color::White => 7 - row as usize,
+        Color::Black => row as usize,
+    }
+}
+
+// Rows in front of the row as a mask, from the point of view of the color
+pub fn rows_ahead(color: Color, row: u8) -> u8 {
+    match color {
+        Color::White => ((1u16 << row) - 1) as u8,
+        Color::Black => (0xffu16 << (row + 1)) as u8,
+    }
+}
+
+fn distance(square_1: &Square, square_2: &Square) -> i32 {
+    let (row_1, col_1) = square_1.get_coordinates();
+    let (row_2, col_2) = square_2.get_coordinates();
+
+    (row_1 as i32 - row_2 as i32)
+        .abs()
+        .max((col_1 as i32 - col_2 as i32).abs())
+}
+
+/// Doubled, isolated, backward, connected and passed pawns, with passed
+/// pawns scored by rank, distance to the kings and whether their path is
+/// free. Middlegame and endgame scores are tapered by the game phase as in
+/// PeSTO.
+///
+/// The terms that only depend on the pawns are kept in a pawn hash, one per
+/// thread.
+#[derive(Clone)]
+pub struct PawnStructureEvaluator;
+
+impl PawnStructureEvaluator {
+    pub fn new() -> PawnStructureEvaluator {
+        PawnStructureEvaluator {}
+    }
+
+    fn pawn_entry(&self, chess_board: &ChessBoard) -> PawnEntry {
+        PAWN_HASH.with(|pawn_hash| {
+            let mut pawn_hash = pawn_hash.borrow_mut();
+            let key = pawn_hash.get_key(chess_board);
+
+            if let Some(entry) = pawn_hash.get(key) {
+                return *entry;
+            }
+
+            let entry = self.evaluate_pawns(chess_board);
+            pawn_hash.insert(key, entry);
+            entry
+        })
+    }
+
+    fn evaluate_pawns(&self, chess_board: &ChessBoard) -> PawnEntry {
+        let mut terms = [[0; 2]; TERM_NAMES.len()];
+        let mut passed = 0;
+
+        let mut add = |term: usize, score: [i32; 2], sign: i32| {
+            terms[term][0] += sign * score[0];
+            terms[term][1] += sign * score[1];
+        };
+
+        // Rows of the pawns of every color and file, as masks
+        let mut pawns = [[0u8; 8]; 2];
+        for (square, content) in chess_board.iter_coordinates() {
+            if let Some(content) = content {
+                if content.get_piece() == Piece::Pawn {
+                    let (row, col) = square.get_coordinates();
+                    pawns[color_index(content.get_color())][col as usize] |= 1 << row;
+                }
+            }
+        }
+
+        for color in [Color::White, Color::Black] {
+            let own = &pawns[color_index(color)];
+            let enemy = &pawns[color_index(color.opposite())];
+            let sign = sign(color);
+
+            for col in 0..8 {
+                let count = own[col].count_ones() as i32;
+                if count > 1 {
+                    add(DOUBLED_TERM, DOUBLED, sign * (count - 1));
+                }
+
+                let adjacent_cols = col.saturating_sub(1)..=(col + 1).min(7);
+                let adjacent = |pawns: &[u8; 8]| {
+                    adjacent_cols
+                        .clone()
+                        .filter(|&adjacent_col| adjacent_col != col)
+                        .fold(0, |mask, adjacent_col| mask | pawns[adjacent_col])
+                };
+                let own_adjacent = adjacent(own);
+                let enemy_adjacent = adjacent(enemy);
+
+                for row in 0..8u8 {
+                    if own[col] & (1 << row) == 0 {
+                        continue;
+                    }
+
+                    let rank = relative_rank(color, row);
+                    let ahead = rows_ahead(color, row);
+                    let behind_row = row as i8 - forward(color);
+                    let stop_row = row as i8 + forward(color);
+
+                    if own_adjacent == 0 {
+                        add(ISOLATED_TERM, ISOLATED, sign);
+                    } else if own_adjacent & !ahead == 0 {
+                        // Only pawns in front of it on the adjacent files, and
+                        // an enemy pawn keeping it from catching up
+                        let attacker_row = stop_row + forward(color);
+                        if (0..8).contains(&attacker_row)
+                            && enemy_adjacent & (1 << attacker_row) != 0
+                        {
+                            add(BACKWARD_TERM, BACKWARD, sign);
+                        }
+                    }
+
+                    let phalanx = own_adjacent & (1 << row) != 0;
+                    let supported =
+                        (0..8).contains(&behind_row) && own_adjacent & (1 << behind_row) != 0;
+                    if phalanx || supported {
+                        add(CONNECTED_TERM, CONNECTED[rank], sign);
+                    }
+
+                    if (enemy[col] | enemy_adjacent) & ahead == 0 {
+                        add(PASSED_TERM, PASSED[rank], sign);
+                        passed |= 1 << (8 * row as usize + col);
+                    }
+                }
+            }
+        }
+
+        PawnEntry { terms, passed }
+    }
+
+    // Terms of the passed pawns that depend on the other pieces
+    fn evaluate_passed(&self, chess_board: &ChessBoard, passed: u64, terms: &mut Terms) {
+        let kings = [
+            chess_board.find_king(Color::White),
+            chess_board.find_king(Color::Black),
+        ];
+
+        for index in 0..64 {
+            if passed & (1 << index) == 0 {
+                continue;
+            }
+
+            let square = Square::from_index(index).unwrap();
+            let Some(content) = chess_board.get_square_content(&square) else {
+                continue;
+            };
+            let color = content.get_color();
+            let rank = relative_rank(color, square.get_coordinates().0);
+
+            // Pawns about to promote have no stop square
+            let Some(stop_square) = square.add(forward(color), 0) else {
+                continue;
+            };
+
+            let weight = rank as i32 - 2;
+            if weight > 0 {
+                let enemy_distance = distance(&kings[color_index(color.opposite())], &stop_square);
+                let own_distance = distance(&kings[color_index(color)], &stop_square);
+
+                terms[KING_PROXIMITY_TERM][1] += sign(color)
+                    * weight
+                    * (ENEMY_KING_DISTANCE * enemy_distance - OWN_KING_DISTANCE * own_distance);
+            }
+
+            let mut free = true;
+            let mut path_square = Some(stop_square);
+            while let Some(square) = path_square {
+                if chess_board.get_square_content(&square).is_some() {
+                    free = false;
+                    break;
+                }
+                path_square = square.add(forward(color), 0);
+            }
+            if free {
+                terms[FREE_PATH_TERM][1] += sign(color) * FREE_PATH[rank];
+            }
+        }
+    }
+
+    fn terms(&self, chess_board: &ChessBoard) -> (Terms, i32) {
+        let entry = self.pawn_entry(chess_board);
+        let mut terms = entry.terms;
+        self.evaluate_passed(chess_board, entry.passed, &mut terms);
+
+        (terms, game_phase(chess_board))
+    }
+}
+
+// Tapered score in pawns
+fn taper(score: [i32; 2], phase: i32) -> f64 {
+    (score[0] * phase + score[1] * (MAX_PHASE - phase)) as f64 / (100 * MAX_PHASE) as f64
+}
+
+unsafe impl Send for PawnStructureEvaluator {}
+unsafe impl Sync for PawnStructureEvaluator {}
+
+impl Evaluator for PawnStructureEvaluator {
+    fn get_name(&self) -> String {
+        "PawnStructureEvaluator".to_string()
+    }
+
+    #[inline(always)]
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        let (terms, phase) = self.terms(chess_board);
+
+        let mut score = [0; 2];
+        for term in terms {
+            score[0] += term[0];
+            score[1] += term[1];
+        }
+
+        Score::from_pawns(taper(score, phase)).relative(chess_board.get_turn_color())
+    }
+
+    fn explain(&self, chess_board: &ChessBoard) -> EvalTrace {
+        let (terms, phase) = self.terms(chess_board);
+
+        let children = TERM_NAMES
+            .iter()
+            .zip(terms)
+            .map(|(name, term)| EvalTrace::new(name, taper(term, phase)).weighted(1.))
+            .collect();
+
+        EvalTrace::with_children(
+            &format!("PawnStructureEvaluator (phase {}/{})", phase, MAX_PHASE),
+            self.evaluate_white(chess_board).to_pawns(),
+            children,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pawn_terms(fen: &str) -> Terms {
+        PawnStructureEvaluator::new()
+            .evaluate_pawns(&ChessBoard::from_fen(fen).unwrap())
+            .terms
+    }
+
+    #[test]
+    fn doubled_pawns_on_their_own_are_isolated() {
+        let terms = pawn_terms("4k3/8/8/8/8/P7/P7/4K3 w - - 0 1");
+
+        assert_eq!(terms[DOUBLED_TERM], DOUBLED);
+        assert_eq!(terms[ISOLATED_TERM], [2 * ISOLATED[0], 2 * ISOLATED[1]]);
+        assert_eq!(terms[BACKWARD_TERM], [0, 0]);
+    }
+
+    #[test]
+    fn pawns_left_behind_under_an_enemy_pawn_are_backward() {
+        // d3 can't catch up with c4 because of the pawn on e5
+        let terms = pawn_terms("4k3/8/8/4p3/2P5/3P4/8/4K3 w - - 0 1");
+        assert_eq!(terms[BACKWARD_TERM], BACKWARD);
+
+        // Nothing stops it without the pawn
+        let terms = pawn_terms("4k3/8/8/8/2P5/3P4/8/4K3 w - - 0 1");
+        assert_eq!(terms[BACKWARD_TERM], [0, 0]);
+    }
+
+    #[test]
+    fn passed_pawns_of_both_colors_are_scored_the_same() {
+        let evaluator = PawnStructureEvaluator::new();
+        let white = ChessBoard::from_fen("4k3/8/8/4P3/8/8/p7/2K5 w - - 0 1").unwrap();
+        let black = ChessBoard::from_fen("2k5/P7/8/8/4p3/8/8/4K3 b - - 0 1").unwrap();
+
+        let (white_terms, phase) = evaluator.terms(&white);
+        let (black_terms, _) = evaluator.terms(&black);
+        let passed = [PASSED[4][0] - PASSED[6][0], PASSED[4][1] - PASSED[6][1]];
+        assert_eq!(white_terms[PASSED_TERM], passed);
+        for (white_term, black_term) in white_terms.iter().zip(black_terms) {
+            assert_eq!(*white_term, black_term.map(|score| -score));
+        }
+        assert_ne!(white_terms[KING_PROXIMITY_TERM], [0, 0]);
+        assert_ne!(white_terms[FREE_PATH_TERM], [0, 0]);
+
+        assert_eq!(evaluator.evaluate(&white), evaluator.evaluate(&black));
+        assert_eq!(evaluator.evaluate_white(&white), -evaluator.evaluate_white(&black));
+        assert_eq!(phase, 0);
+    }
+
+    #[test]
+    fn cached_entries_are_the_fresh_ones() {
+        let evaluator = PawnStructureEvaluator::new();
+        let fens = [
+            "r1bqkbnr/pp1ppppp/2n5/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            "4k3/8/8/4p3/2P5/3P4/8/4K3 w - - 0 1",
+        ];
+
+        for _ in 0..2 {
+            for fen in fens {
+                let chess_board = ChessBoard::from_fen(fen).unwrap();
+                let fresh = evaluator.evaluate_pawns(&chess_board);
+                assert_eq!(evaluator.pawn_entry(&chess_board), fresh);
+            }
+        }
+
+        let chess_board = ChessBoard::from_fen(fens[0]).unwrap();
+        PAWN_HASH.with(|pawn_hash| {
+            let pawn_hash = pawn_hash.borrow();
+            assert!(pawn_hash.get(pawn_hash.get_key(&chess_board)).is_some());
+        });
+    }
+}
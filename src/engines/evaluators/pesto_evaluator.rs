@@ -1,13 +1,139 @@
-use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+use std::sync::Arc;
+
+use crate::{engines::engine_traits::*, chess_server::chess_types::{ChessBoard, Square, Color, Piece}};
+use crate::engines::eval_trace::EvalTrace;
+use crate::engines::score::Score;
+
+/// Piece values and piece-square tables, in centipawns, for the middlegame
+/// and the endgame. Pieces are ordered pawn, knight, bishop, rook, queen,
+/// king and squares from a8 to h1, from the point of view of white.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "PestoParamsFile", into = "PestoParamsFile")]
+pub struct PestoParams {
+    pub mg_value: [i32; 6],
+    pub eg_value: [i32; 6],
+    pub mg_tables: [[i32; 64]; 6],
+    pub eg_tables: [[i32; 64]; 6],
+}
+
+// Serde doesn't handle arrays of 64 values
+#[derive(Serialize, Deserialize)]
+struct PestoParamsFile {
+    mg_value: Vec<i32>,
+    eg_value: Vec<i32>,
+    mg_tables: Vec<Vec<i32>>,
+    eg_tables: Vec<Vec<i32>>,
+}
+
+impl TryFrom<PestoParamsFile> for PestoParams {
+    type Error = String;
+
+    fn try_from(file: PestoParamsFile) -> Result<Self, Self::Error> {
+        let tables = |tables: Vec<Vec<i32>>, name: &str| {
+            let tables: Vec<[i32; 64]> = tables
+                .into_iter()
+                .map(|table| table.try_into())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("Every table of {} needs 64 values", name))?;
+
+            <[[i32; 64]; 6]>::try_from(tables).map_err(|_| format!("{} needs 6 tables", name))
+        };
+
+        Ok(PestoParams {
+            mg_value: file.mg_value.try_into().map_err(|_| "mg_value needs 6 values")?,
+            eg_value: file.eg_value.try_into().map_err(|_| "eg_value needs 6 values")?,
+            mg_tables: tables(file.mg_tables, "mg_tables")?,
+            eg_tables: tables(file.eg_tables, "eg_tables")?,
+        })
+    }
+}
+
+impl From<PestoParams> for PestoParamsFile {
+    fn from(params: PestoParams) -> Self {
+        PestoParamsFile {
+            mg_value: params.mg_value.to_vec(),
+            eg_value: params.eg_value.to_vec(),
+            mg_tables: params.mg_tables.iter().map(|table| table.to_vec()).collect(),
+            eg_tables: params.eg_tables.iter().map(|table| table.to_vec()).collect(),
+        }
+    }
+}
+
+impl Default for PestoParams {
+    fn default() -> Self {
+        PestoParams {
+            mg_value,
+            eg_value,
+            mg_tables: mg_pesto_table,
+            eg_tables: eg_pesto_table,
+        }
+    }
+}
 
-use crate::{engines::engine_traits::*, chess_server::chess_types::{Square, Color, Piece}};
+impl PestoParams {
+    pub fn from_file(path: &str) -> Result<PestoParams, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        serde_json::from_str(&text).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = serde_json::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(path, text).map_err(|err| format!("{}: {}", path, err))
+    }
 
+    // Values of pawn, bishop, knight, rook and queen in pawns, as used by
+    // the CaptureEvaluator
+    pub fn piece_values(&self) -> [f64; 5] {
+        let value = |piece: usize| (self.mg_value[piece] + self.eg_value[piece]) as f64 / 200.;
+
+        [value(PAWN), value(BISHOP), value(KNIGHT), value(ROOK), value(QUEEN)]
+    }
+}
+
+/// Game phase as counted by PeSTO, from 24 with all the pieces on the board
+/// to 0 with kings and pawns only.
+pub fn game_phase(chess_board: &ChessBoard) -> i32 {
+    let mut phase = 0;
+    for (_, content) in chess_board.iter_coordinates() {
+        if let Some(content) = content {
+            phase += match content.get_piece() {
+                Piece::Knight | Piece::Bishop => 1,
+                Piece::Rook => 2,
+                Piece::Queen => 4,
+                Piece::Pawn | Piece::King => 0,
+            };
+        }
+    }
+
+    // In case of early promotion
+    phase.min(24)
+}
+
+// Middlegame and endgame tables of every color piece
+type PestoTables = ([[i32; 64]; 12], [[i32; 64]; 12]);
+
+// Tables are built once, as clones share them
 #[derive(Clone)]
-pub struct PestoEvaluator;
+pub struct PestoEvaluator {
+    tables: Arc<PestoTables>,
+}
 
 impl PestoEvaluator {
     pub fn new() -> PestoEvaluator {
-        PestoEvaluator {}
+        PestoEvaluator::with_params(&PestoParams::default())
+    }
+
+    pub fn with_params(params: &PestoParams) -> PestoEvaluator {
+        PestoEvaluator {
+            tables: Arc::new(init_tables(params)),
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<PestoEvaluator, String> {
+        Ok(PestoEvaluator::with_params(&PestoParams::from_file(path)?))
     }
 }
 
@@ -202,7 +328,7 @@ const fn flip(sq: usize) -> usize {
     sq ^ 56
 }
 
-fn init_tables() -> ([[i32; 64]; 12], [[i32; 64]; 12])
+fn init_tables(params: &PestoParams) -> PestoTables
 {
 
     let mut p = PAWN;
@@ -214,10 +340,10 @@ fn init_tables() -> ([[i32; 64]; 12], [[i32; 64]; 12])
 
     while p <= KING {
         for sq in 0..64 {
-            mg_table[pc]  [sq] = mg_value[p] + mg_pesto_table[p][sq];
-            eg_table[pc]  [sq] = eg_value[p] + eg_pesto_table[p][sq];
-            mg_table[pc+1][sq] = mg_value[p] + mg_pesto_table[p][flip(sq)];
-            eg_table[pc+1][sq] = eg_value[p] + eg_pesto_table[p][flip(sq)];
+            mg_table[pc]  [sq] = params.mg_value[p] + params.mg_tables[p][sq];
+            eg_table[pc]  [sq] = params.eg_value[p] + params.eg_tables[p][sq];
+            mg_table[pc+1][sq] = params.mg_value[p] + params.mg_tables[p][flip(sq)];
+            eg_table[pc+1][sq] = params.eg_value[p] + params.eg_tables[p][flip(sq)];
         }
 
         p += 1;
@@ -258,12 +384,12 @@ impl Evaluator for PestoEvaluator {
         "PestoEvaluator".into()
     }
 
-    fn evaluate(&self, chess_board: &crate::chess_server::chess_types::ChessBoard) -> ordered_float::OrderedFloat<f64> {
+    fn evaluate(&self, chess_board: &crate::chess_server::chess_types::ChessBoard) -> Score {
         let mut mg = [0, 0];
         let mut eg = [0, 0];
         let mut game_phase = 0;
 
-        let (mg_table, eg_table) = init_tables();
+        let (mg_table, eg_table) = &*self.tables;
 
         for index in 0..64 {
             let square = unsafe { Square::from_index_unchecked(index) };
@@ -292,7 +418,53 @@ impl Evaluator for PestoEvaluator {
         let  egPhase = 24 - mgPhase;
         
         
-        OrderedFloat( (mgScore * mgPhase + egScore * egPhase) as f64 / 2_400. )
+        Score::cp((mgScore * mgPhase + egScore * egPhase) / 24).relative(chess_board.get_turn_color())
+
+    }
+
+    // Middlegame and endgame scores of every piece, weighted by the phase
+    fn explain(&self, chess_board: &ChessBoard) -> EvalTrace {
+        let mut mg = [0; 6];
+        let mut eg = [0; 6];
+        let mut game_phase = 0;
+
+        let (mg_table, eg_table) = &*self.tables;
+
+        for (square, content) in chess_board.iter_coordinates() {
+            if let Some(content) = content {
+                let piece = content.get_piece().to_index();
+                let pc = 2 * piece + content.get_color().to_index();
+                let sq = square.get_index() as usize;
+                let sign = match content.get_color() {
+                    Color::White => 1,
+                    Color::Black => -1,
+                };
+
+                mg[piece] += sign * mg_table[pc][sq];
+                eg[piece] += sign * eg_table[pc][sq];
+                game_phase += gamephaseInc[pc];
+            }
+        }
 
+        let mg_phase = game_phase.min(24);
+        let eg_phase = 24 - mg_phase;
+
+        let names = ["Pawns", "Knights", "Bishops", "Rooks", "Queens", "Kings"];
+        let phase_trace = |name: &str, scores: [i32; 6], phase: i32| {
+            let children = names
+                .iter()
+                .zip(scores)
+                .map(|(name, score)| EvalTrace::new(name, score as f64 / 100.).weighted(1.))
+                .collect();
+            let score = scores.iter().sum::<i32>() as f64 / 100.;
+
+            EvalTrace::with_children(name, score, children).weighted(phase as f64 / 24.)
+        };
+
+        EvalTrace::with_children(
+            &format!("PestoEvaluator (phase {}/24)", mg_phase),
+            self.evaluate_white(chess_board).to_pawns(),
+            vec![phase_trace("Middlegame", mg, mg_phase), phase_trace("Endgame", eg, eg_phase)],
+        )
     }
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 use crate::chess_server::chess_types::{ChessBoard, Color, Piece, Square};
+use crate::engines::attack_map::AttackMap;
 use crate::engines::engine_traits::*;
-
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
 const VALUE_PAWN: f64 = 5.;
 const VALUE_BISHOP: f64 = 3.0;
@@ -10,6 +10,26 @@ const VALUE_ROOK: f64 = 2.;
 const VALUE_QUEEN: f64 = 1.;
 const VALUE_KING: f64 = 0.5;
 
+const PIECES: [Piece; 6] = [
+    Piece::Pawn,
+    Piece::Bishop,
+    Piece::Knight,
+    Piece::Rook,
+    Piece::Queen,
+    Piece::King,
+];
+
+fn piece_value(piece: Piece) -> f64 {
+    match piece {
+        Piece::Pawn => VALUE_PAWN,
+        Piece::Bishop => VALUE_BISHOP,
+        Piece::Knight => VALUE_KNIGHT,
+        Piece::Rook => VALUE_ROOK,
+        Piece::Queen => VALUE_QUEEN,
+        Piece::King => VALUE_KING,
+    }
+}
+
 #[derive(Clone)]
 pub struct PressureEvaluator;
 
@@ -20,50 +40,20 @@ impl PressureEvaluator {
     pub fn new() -> PressureEvaluator {
         PressureEvaluator {}
     }
-}
-
-// TODO: Include Checks
-
-impl Evaluator for PressureEvaluator {
-    fn get_name(&self) -> String {
-        "PressureEvaluator".to_string()
-    }
 
-    #[inline(always)]
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
+    // In pawns for white, before it gets clamped into a Score
+    // TODO: Include Checks
+    pub fn evaluate_pawns(&self, chess_board: &ChessBoard) -> f64 {
         let mut eval = 0.;
-        let mut heatmap_white = [0.; 64];
-        let mut heatmap_black = [0.; 64];
+        let attack_map = AttackMap::shared(chess_board);
 
+        // Pawns going forward
         for (coordinate, content) in chess_board.iter_coordinates() {
             if let Some(content) = content {
-                let piece_value = match content.get_piece() {
-                    Piece::Pawn => VALUE_PAWN,
-                    Piece::Bishop => VALUE_BISHOP,
-                    Piece::Knight => VALUE_KNIGHT,
-                    Piece::Rook => VALUE_ROOK,
-                    Piece::Queen => VALUE_QUEEN,
-                    Piece::King => VALUE_KING,
-                };
-
-                let attacked_squares = chess_board.squares_attacked_by_piece(&coordinate);
-
-                match content.get_color() {
-                    Color::White => {
-                        for square in attacked_squares.iter() {
-                            heatmap_white[square.get_index() as usize] += piece_value;
-                        }
-                        if content.get_piece() == Piece::Pawn {
-                            eval += 15.0 * (6 - coordinate.get_coordinates().0) as f64;
-                        }
-                    }
-                    Color::Black => {
-                        for square in attacked_squares.iter() {
-                            heatmap_black[square.get_index() as usize] += piece_value;
-                        }
-                        if content.get_piece() == Piece::Pawn {
-                            eval += -15.0 * (coordinate.get_coordinates().0 - 1) as f64;
-                        }
+                if content.get_piece() == Piece::Pawn {
+                    match content.get_color() {
+                        Color::White => eval += 15.0 * (6 - coordinate.get_coordinates().0) as f64,
+                        Color::Black => eval += -15.0 * (coordinate.get_coordinates().0 - 1) as f64,
                     }
                 }
             }
@@ -73,23 +63,20 @@ impl Evaluator for PressureEvaluator {
             let square = unsafe { Square::from_index_unchecked(square_index) };
             let content = chess_board.get_square_content(&square);
 
-            let pressure =
-                heatmap_white[square_index as usize] - heatmap_black[square_index as usize];
+            let heat = |color: Color| -> f64 {
+                PIECES
+                    .iter()
+                    .map(|piece| {
+                        attack_map.get_attackers_by_piece(&square, color, *piece) as f64
+                            * piece_value(*piece)
+                    })
+                    .sum()
+            };
+            let pressure = heat(Color::White) - heat(Color::Black);
 
             match content {
                 None => eval += pressure,
-                Some(content) => {
-                    let piece_value = match content.get_piece() {
-                        Piece::Pawn => VALUE_PAWN,
-                        Piece::Bishop => VALUE_BISHOP,
-                        Piece::Knight => VALUE_KNIGHT,
-                        Piece::Rook => VALUE_ROOK,
-                        Piece::Queen => VALUE_QUEEN,
-                        Piece::King => VALUE_KING,
-                    };
-
-                    eval += 6. * pressure / piece_value;
-                }
+                Some(content) => eval += 6. * pressure / piece_value(content.get_piece()),
             };
 
             let (row, col) = square.get_coordinates();
@@ -105,6 +92,17 @@ impl Evaluator for PressureEvaluator {
             }
         }
 
-        OrderedFloat(eval)
+        eval
+    }
+}
+
+impl Evaluator for PressureEvaluator {
+    fn get_name(&self) -> String {
+        "PressureEvaluator".to_string()
+    }
+
+    #[inline(always)]
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        Score::from_pawns(self.evaluate_pawns(chess_board)).relative(chess_board.get_turn_color())
     }
 }
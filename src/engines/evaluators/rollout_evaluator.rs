@@ -1,13 +1,19 @@
 use crate::chess_server::chess_types::{ChessBoard, ChessStatus, Color};
 use crate::engines::engine_traits::*;
+use crate::engines::score::Score;
 
 use ordered_float::OrderedFloat;
 
+// Rollouts are meant for evaluations clamped to a pawn, unless told
+// otherwise, so that results are worth as much as the best evaluation
+const WIN: Score = Score::cp(100);
+
 #[derive(Clone)]
 pub struct RolloutEvaluator<P: Policy, E: Evaluator> {
     policy: P,
     evaluator: E,
     max_depth: usize,
+    win: Score,
 }
 
 unsafe impl<P: Policy, E: Evaluator> Send for RolloutEvaluator<P, E>
@@ -26,6 +32,16 @@ where
 
 impl<P: Policy, E: Evaluator> RolloutEvaluator<P, E> {
     pub fn new(policy: P, evaluator: E, max_depth: usize) -> RolloutEvaluator<P, E> {
+        RolloutEvaluator::with_win(policy, evaluator, max_depth, WIN)
+    }
+
+    // `win` is what a won game is worth, the threshold of a clamped evaluator
+    pub fn with_win(
+        policy: P,
+        evaluator: E,
+        max_depth: usize,
+        win: Score,
+    ) -> RolloutEvaluator<P, E> {
         if max_depth == 0 {
             panic!("Max Depth must be positive")
         }
@@ -34,6 +50,7 @@ impl<P: Policy, E: Evaluator> RolloutEvaluator<P, E> {
             policy,
             evaluator,
             max_depth,
+            win,
         }
     }
 }
@@ -44,10 +61,11 @@ impl<P: Policy, E: Evaluator> Evaluator for RolloutEvaluator<P, E> {
     }
 
     #[inline(always)]
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+        let color = chess_board.get_turn_color();
         let mut chess_board = *chess_board;
 
-        let sign = match chess_board.get_turn_color() {
+        let sign = match color {
             Color::White => OrderedFloat(1.),
             Color::Black => OrderedFloat(-1.),
         };
@@ -60,9 +78,9 @@ impl<P: Policy, E: Evaluator> Evaluator for RolloutEvaluator<P, E> {
             let allowed_moves = chess_board.get_allowed_moves(chess_board.get_turn_color());
 
             match chess_board.get_game_status_from_precomputed(&allowed_moves) {
-                ChessStatus::Draw => return OrderedFloat(0.),
-                ChessStatus::WhiteWon => return OrderedFloat(1.),
-                ChessStatus::BlackWon => return OrderedFloat(-1.),
+                ChessStatus::Draw => return Score::DRAW,
+                ChessStatus::WhiteWon => return self.win.relative(color),
+                ChessStatus::BlackWon => return (-self.win).relative(color),
                 ChessStatus::Ongoing => {
                     let priors = self.policy.get_priors(&chess_board, &allowed_moves);
 
@@ -81,6 +99,6 @@ impl<P: Policy, E: Evaluator> Evaluator for RolloutEvaluator<P, E> {
             }
         }
 
-        self.evaluator.evaluate(&chess_board)
+        self.evaluator.evaluate_white(&chess_board).relative(color)
     }
 }
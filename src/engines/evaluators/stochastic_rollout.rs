@@ -1,7 +1,6 @@
 use crate::chess_server::chess_types::{ChessBoard, ChessStatus};
 use crate::engines::engine_traits::*;
-
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
 use rand::distributions::WeightedIndex;
 use rand::prelude::*;
@@ -9,6 +8,9 @@ use rand::prelude::*;
 use smallvec::SmallVec;
 use std::thread;
 
+// As in the rollout evaluator, for evaluations clamped to a pawn
+const WIN: Score = Score::cp(100);
+
 #[derive(Clone)]
 pub struct StochasticRollout<P: Policy, E: Evaluator>
 where
@@ -19,6 +21,8 @@ where
     evaluator: E,
     max_depth: usize,
     iter_per_thread: usize,
+    // Value of a won game in pawns
+    win: f64,
 }
 
 unsafe impl<P: Policy, E: Evaluator> Send for StochasticRollout<P, E>
@@ -45,6 +49,17 @@ where
         evaluator: E,
         max_depth: usize,
         iter_per_thread: usize,
+    ) -> StochasticRollout<P, E> {
+        StochasticRollout::with_win(policy, evaluator, max_depth, iter_per_thread, WIN)
+    }
+
+    // Games won during a rollout count as `win`
+    pub fn with_win(
+        policy: P,
+        evaluator: E,
+        max_depth: usize,
+        iter_per_thread: usize,
+        win: Score,
     ) -> StochasticRollout<P, E> {
         if max_depth == 0 {
             panic!("Max Depth must be positive")
@@ -55,6 +70,7 @@ where
             evaluator,
             max_depth,
             iter_per_thread,
+            win: win.to_pawns(),
         }
     }
 }
@@ -64,7 +80,8 @@ where
     P: Send + Sync,
     E: Send + Sync,
 {
-    fn evaluate_monte_carlo(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
+    // Average of the rollouts from the point of view of white, in pawns
+    fn evaluate_monte_carlo(&self, chess_board: &ChessBoard) -> f64 {
         let mut rng = rand::thread_rng();
 
         let iteration = |rng: &mut ThreadRng| {
@@ -77,9 +94,9 @@ where
                 let allowed_moves = chess_board.get_allowed_moves(chess_board.get_turn_color());
 
                 match chess_board.get_game_status_from_precomputed(&allowed_moves) {
-                    ChessStatus::Draw => return OrderedFloat(0.),
-                    ChessStatus::WhiteWon => return OrderedFloat(1.),
-                    ChessStatus::BlackWon => return OrderedFloat(-1.),
+                    ChessStatus::Draw => return 0.,
+                    ChessStatus::WhiteWon => return self.win,
+                    ChessStatus::BlackWon => return -self.win,
                     ChessStatus::Ongoing => {
                         let priors = self.policy.get_priors(&chess_board, &allowed_moves);
 
@@ -96,16 +113,16 @@ where
                 }
             }
 
-            self.evaluator.evaluate(&chess_board)
+            self.evaluator.evaluate_white(&chess_board).to_pawns()
         };
 
-        let mut sum = OrderedFloat(0.);
+        let mut sum = 0.;
 
         for _ in 0..self.iter_per_thread {
             sum += iteration(&mut rng);
         }
 
-        sum / OrderedFloat(self.iter_per_thread as f64)
+        sum / self.iter_per_thread as f64
     }
 }
 
@@ -119,10 +136,10 @@ where
     }
 
     #[inline(always)]
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
         const N_THREADS: usize = 16;
 
-        let sum: OrderedFloat<f64> = thread::scope(|s| {
+        let sum: f64 = thread::scope(|s| {
             let mut handles: SmallVec<[_; N_THREADS]> = SmallVec::new();
 
             for _ in 0..N_THREADS {
@@ -131,7 +148,7 @@ where
                 handles.push(handle);
             }
 
-            let mut sum = OrderedFloat(0.);
+            let mut sum = 0.;
             for handle in handles {
                 sum += handle.join().unwrap()
             }
@@ -139,6 +156,6 @@ where
             sum
         });
 
-        sum / OrderedFloat(N_THREADS as f64)
+        Score::from_pawns(sum / N_THREADS as f64).relative(chess_board.get_turn_color())
     }
 }
@@ -1,7 +1,7 @@
 use crate::chess_server::chess_types::{ChessBoard, Color};
+use crate::engines::attack_map::AttackMap;
 use crate::engines::engine_traits::*;
-
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
 #[derive(Clone)]
 pub struct StructureEvaluator;
@@ -21,10 +21,13 @@ impl Evaluator for StructureEvaluator {
     }
 
     #[inline(always)]
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
         // 1 point of advantage for player who has the move
         let mut eval = 0.;
 
+        let attack_map = AttackMap::shared(chess_board);
+
+        // Defended pieces
         for (coordinate, content) in chess_board.iter_coordinates() {
             if let Some(content) = content {
                 let sign = match content.get_color() {
@@ -32,14 +35,10 @@ impl Evaluator for StructureEvaluator {
                     Color::Black => -1.,
                 };
 
-                for coord in chess_board.squares_attacked_by_piece(&coordinate) {
-                    if chess_board.contains_piece_of_color(&coord, content.get_color()) {
-                        eval += sign;
-                    }
-                }
+                eval += sign * attack_map.get_attackers(&coordinate, content.get_color()) as f64;
             }
         }
 
-        OrderedFloat(eval)
+        Score::from_pawns(eval).relative(chess_board.get_turn_color())
     }
 }
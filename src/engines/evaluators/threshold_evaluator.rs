@@ -1,15 +1,13 @@
 use crate::engines::engine_traits::*;
+use crate::engines::eval_trace::EvalTrace;
 
 use crate::chess_server::chess_types::ChessBoard;
-
-use ordered_float::OrderedFloat;
-
-const INF: OrderedFloat<f64> = OrderedFloat(1000.0);
+use crate::engines::score::Score;
 
 #[derive(Clone)]
 pub struct ThresholdEvaluator<E: Evaluator> {
     evaluator: E,
-    threshold: OrderedFloat<f64>,
+    threshold: Score,
 }
 
 unsafe impl<E: Evaluator> Send for ThresholdEvaluator<E> where E: Send {}
@@ -17,7 +15,7 @@ unsafe impl<E: Evaluator> Send for ThresholdEvaluator<E> where E: Send {}
 unsafe impl<E: Evaluator> Sync for ThresholdEvaluator<E> where E: Sync {}
 
 impl<E: Evaluator> ThresholdEvaluator<E> {
-    pub fn new(evaluator: E, threshold: OrderedFloat<f64>) -> ThresholdEvaluator<E> {
+    pub fn new(evaluator: E, threshold: Score) -> ThresholdEvaluator<E> {
         ThresholdEvaluator {
             evaluator,
             threshold,
@@ -31,15 +29,37 @@ impl<E: Evaluator> Evaluator for ThresholdEvaluator<E> {
     }
 
     #[inline(always)]
-    fn evaluate(&self, chess_board: &ChessBoard) -> OrderedFloat<f64> {
+    fn evaluate(&self, chess_board: &ChessBoard) -> Score {
         let eval = self.evaluator.evaluate(chess_board);
 
+        // Decided positions get the largest evaluation that isn't a mate
         if eval > self.threshold {
-            INF
+            Score::MAX_EVAL
         } else if eval < -self.threshold {
-            -INF
+            -Score::MAX_EVAL
         } else {
             eval
         }
     }
+
+    fn explain(&self, chess_board: &ChessBoard) -> EvalTrace {
+        let trace = self.evaluator.explain(chess_board);
+        let eval = self.evaluate_white(chess_board).to_pawns();
+
+        // Below the threshold the evaluation is passed through
+        let name = format!("ThresholdEvaluator (won above {})", self.threshold);
+        if eval == trace.value {
+            EvalTrace::with_children(&name, eval, vec![trace.weighted(1.)])
+        } else {
+            EvalTrace::with_children(&name, eval, vec![trace])
+        }
+    }
+
+    fn push_position(&self, chess_board: &ChessBoard) {
+        self.evaluator.push_position(chess_board)
+    }
+
+    fn pop_position(&self) {
+        self.evaluator.pop_position()
+    }
 }
@@ -1,7 +1,6 @@
 use crate::chess_server::chess_types::ChessBoard;
 use crate::engines::engine_traits::*;
-
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
 #[derive(Clone)]
 pub struct TrivialEvaluator;
@@ -21,7 +20,7 @@ impl Evaluator for TrivialEvaluator {
     }
 
     #[inline(always)]
-    fn evaluate(&self, _chess_board: &ChessBoard) -> OrderedFloat<f64> {
-        OrderedFloat(0.)
+    fn evaluate(&self, _chess_board: &ChessBoard) -> Score {
+        Score::cp(0)
     }
 }
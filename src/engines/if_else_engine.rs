@@ -1,5 +1,8 @@
-use crate::chess_server::chess_types::{ChessBoard, Move};
-use crate::chess_server::game::Player;
+use crate::chess_server::chess_types::ChessBoard;
+use crate::chess_server::game::{Decision, GameContext, Player};
+use crate::engines::score::Score;
+
+use std::cell::Cell;
 
 // 0.6.1
 
@@ -11,6 +14,8 @@ where
     player_1: P1,
     player_2: P2,
     func: F,
+    // Whether the last decision was taken by the first player
+    used_player_1: Cell<bool>,
 }
 
 impl<P1: Player, P2: Player, F> IfElseEngine<P1, P2, F>
@@ -22,6 +27,7 @@ where
             player_1,
             player_2,
             func,
+            used_player_1: Cell::new(true),
         }
     }
 }
@@ -30,11 +36,21 @@ impl<P1: Player, P2: Player, F> Player for IfElseEngine<P1, P2, F>
 where
     F: Fn(&ChessBoard) -> bool,
 {
-    fn select_move(&self, chess_board: &ChessBoard) -> Move {
-        if (self.func)(chess_board) {
-            self.player_1.select_move(chess_board)
+    fn decide(&self, context: &GameContext) -> Decision {
+        self.used_player_1.set((self.func)(&context.chess_board));
+
+        if self.used_player_1.get() {
+            self.player_1.decide(context)
+        } else {
+            self.player_2.decide(context)
+        }
+    }
+
+    fn get_last_score(&self) -> Option<Score> {
+        if self.used_player_1.get() {
+            self.player_1.get_last_score()
         } else {
-            self.player_2.select_move(chess_board)
+            self.player_2.get_last_score()
         }
     }
 }
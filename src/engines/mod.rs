@@ -3,8 +3,24 @@ pub mod random_engine;
 
 pub mod engine_traits;
 
+pub mod engine_config;
+
+pub mod eval_trace;
+
+pub mod score;
+
 pub mod zobrist_hash;
 
+pub mod attack_map;
+
+pub mod move_ordering;
+
+pub mod nnue;
+
+pub mod quiescence;
+
+pub mod time_manager;
+
 pub mod evaluators;
 pub mod policies;
 pub mod searchers;
@@ -0,0 +1,284 @@
+use crate::chess_server::chess_types::chess_board::MoveContainer;
+use crate::chess_server::chess_types::{ChessBoard, Color, Move, Piece};
+
+pub const MAX_PLY: usize = 128;
+
+const HASH_MOVE_SCORE: i32 = 1_000_000;
+const CAPTURE_SCORE: i32 = 500_000;
+const KILLER_SCORES: [i32; 2] = [400_000, 390_000];
+const COUNTER_MOVE_SCORE: i32 = 380_000;
+const LOSING_CAPTURE_SCORE: i32 = -500_000;
+
+// History scores are kept in [-MAX_HISTORY, MAX_HISTORY] so that quiet moves
+// never jump ahead of killers and countermoves
+const MAX_HISTORY: i32 = 100_000;
+
+fn piece_order(piece: Piece) -> i32 {
+    match piece {
+        Piece::Pawn => 1,
+        Piece::Knight => 2,
+        Piece::Bishop => 3,
+        Piece::Rook => 4,
+        Piece::Queen => 5,
+        Piece::King => 6,
+    }
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+fn move_squares(mv: &Move) -> (usize, usize) {
+    (
+        mv.get_current_square().get_index() as usize,
+        mv.get_next_square().get_index() as usize,
+    )
+}
+
+/// Most Valuable Victim - Least Valuable Attacker
+pub fn mvv_lva(chess_board: &ChessBoard, mv: &Move) -> i32 {
+    let victim = match chess_board.get_square_content(&mv.get_next_square()) {
+        Some(color_piece) => piece_order(color_piece.get_piece()),
+        None => 0,
+    };
+
+    let attacker = match chess_board.get_square_content(&mv.get_current_square()) {
+        Some(color_piece) => piece_order(color_piece.get_piece()),
+        None => 0,
+    };
+
+    let promotion = match mv.get_promotion_piece() {
+        Some(piece) => piece_order(piece),
+        None => 0,
+    };
+
+    16 * (victim + promotion) - attacker
+}
+
+pub fn is_tactical(chess_board: &ChessBoard, mv: &Move) -> bool {
+    mv.get_is_promotion() || chess_board.get_square_content(&mv.get_next_square()).is_some()
+}
+
+/// Move ordering shared by the alpha-beta searchers.
+///
+/// Moves are tried in the following order: hash move, captures and
+/// promotions that don't lose material (MVV-LVA), the two killer moves of
+/// the ply, the countermove to the previous move, the quiet moves sorted by
+/// history and finally the captures losing material.
+#[derive(Clone)]
+pub struct MoveOrderer {
+    killers: [[Option<Move>; 2]; MAX_PLY],
+    history: [[[i32; 64]; 64]; 2],
+    counter_moves: [[Option<Move>; 64]; 64],
+}
+
+impl MoveOrderer {
+    pub fn new() -> MoveOrderer {
+        MoveOrderer {
+            killers: [[None; 2]; MAX_PLY],
+            history: [[[0; 64]; 64]; 2],
+            counter_moves: [[None; 64]; 64],
+        }
+    }
+
+    // Called at the start of every search: killers are position specific
+    // while history and countermoves are only made less relevant.
+    pub fn new_search(&mut self) {
+        self.killers = [[None; 2]; MAX_PLY];
+        self.age_history();
+    }
+
+    pub fn age_history(&mut self) {
+        for table in self.history.iter_mut() {
+            for row in table.iter_mut() {
+                for value in row.iter_mut() {
+                    *value /= 2;
+                }
+            }
+        }
+    }
+
+    pub fn get_killers(&self, ply: usize) -> [Option<Move>; 2] {
+        if ply < MAX_PLY {
+            self.killers[ply]
+        } else {
+            [None; 2]
+        }
+    }
+
+    pub fn get_history(&self, color: Color, mv: &Move) -> i32 {
+        let (from, to) = move_squares(mv);
+        self.history[color_index(color)][from][to]
+    }
+
+    pub fn get_counter_move(&self, previous_move: Option<Move>) -> Option<Move> {
+        let (from, to) = move_squares(&previous_move?);
+        self.counter_moves[from][to]
+    }
+
+    pub fn score_move(
+        &self,
+        chess_board: &ChessBoard,
+        mv: &Move,
+        ply: usize,
+        hash_move: Option<Move>,
+        previous_move: Option<Move>,
+    ) -> i32 {
+        if Some(*mv) == hash_move {
+            return HASH_MOVE_SCORE;
+        }
+
+        if is_tactical(chess_board, mv) {
+            // Losing captures are tried last, after the quiet moves
+            return if chess_board.see_ge(mv, 0) {
+                CAPTURE_SCORE + mvv_lva(chess_board, mv)
+            } else {
+                LOSING_CAPTURE_SCORE + mvv_lva(chess_board, mv)
+            };
+        }
+
+        let killers = self.get_killers(ply);
+        for (slot, killer) in killers.iter().enumerate() {
+            if Some(*mv) == *killer {
+                return KILLER_SCORES[slot];
+            }
+        }
+
+        if Some(*mv) == self.get_counter_move(previous_move) {
+            return COUNTER_MOVE_SCORE;
+        }
+
+        self.get_history(chess_board.get_turn_color(), mv)
+    }
+
+    pub fn order_moves(
+        &self,
+        chess_board: &ChessBoard,
+        moves: &mut MoveContainer,
+        ply: usize,
+        hash_move: Option<Move>,
+        previous_move: Option<Move>,
+    ) {
+        moves.sort_by_cached_key(|mv| {
+            -self.score_move(chess_board, mv, ply, hash_move, previous_move)
+        });
+    }
+
+    fn update_history(&mut self, color: Color, mv: &Move, bonus: i32) {
+        let (from, to) = move_squares(mv);
+        let entry = &mut self.history[color_index(color)][from][to];
+
+        // History gravity: the closer to the bound, the smaller the update
+        *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+    }
+
+    /// Records a beta cutoff produced by `mv`.
+    ///
+    /// `searched_quiets` are the quiet moves tried before `mv` in the same
+    /// node; they failed to produce a cutoff and get a history penalty.
+    pub fn record_cutoff(
+        &mut self,
+        chess_board: &ChessBoard,
+        mv: Move,
+        ply: usize,
+        depth: usize,
+        previous_move: Option<Move>,
+        searched_quiets: &[Move],
+    ) {
+        if is_tactical(chess_board, &mv) {
+            return;
+        }
+
+        if ply < MAX_PLY && self.killers[ply][0] != Some(mv) {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = Some(mv);
+        }
+
+        let color = chess_board.get_turn_color();
+        let bonus = ((depth * depth) as i32).min(MAX_HISTORY / 8);
+
+        self.update_history(color, &mv, bonus);
+        for quiet in searched_quiets.iter().filter(|&&quiet| quiet != mv) {
+            self.update_history(color, quiet, -bonus);
+        }
+
+        if let Some(previous_move) = previous_move {
+            let (from, to) = move_squares(&previous_move);
+            self.counter_moves[from][to] = Some(mv);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_server::io::utils::parse_uci_move;
+
+    // The knight can take a free pawn on g5 or a defended one on d6
+    const FEN: &str = "4k3/2p5/3p4/6p1/4N3/8/8/R3K3 w - - 0 1";
+
+    fn moves(chess_board: &ChessBoard, move_strs: &[&str]) -> Vec<Move> {
+        move_strs
+            .iter()
+            .map(|move_str| parse_uci_move(chess_board, move_str).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn orders_by_hash_captures_killers_countermove_and_history() {
+        let chess_board = ChessBoard::from_fen(FEN).unwrap();
+        let black_board = ChessBoard::from_fen(&FEN.replace(" w ", " b ")).unwrap();
+        let mv = |move_str| moves(&chess_board, &[move_str])[0];
+        let previous_move = Some(moves(&black_board, &["e8f8"])[0]);
+
+        let mut orderer = MoveOrderer::new();
+        orderer.record_cutoff(&chess_board, mv("e4f2"), 0, 5, None, &[mv("e1d2")]);
+        orderer.record_cutoff(&chess_board, mv("a1a7"), 3, 4, None, &[]);
+        orderer.record_cutoff(&chess_board, mv("e4c5"), 3, 4, None, &[]);
+        orderer.record_cutoff(&chess_board, mv("e4c5"), 3, 4, None, &[]);
+        orderer.record_cutoff(&chess_board, mv("e1d1"), 5, 2, previous_move, &[]);
+        // Captures are neither killers nor in the history
+        orderer.record_cutoff(&chess_board, mv("e4g5"), 3, 4, previous_move, &[]);
+
+        assert_eq!(orderer.get_killers(3), [Some(mv("e4c5")), Some(mv("a1a7"))]);
+        assert_eq!(orderer.get_counter_move(previous_move), Some(mv("e1d1")));
+        assert_eq!(orderer.get_history(Color::White, &mv("e4f2")), 25);
+        assert_eq!(orderer.get_history(Color::White, &mv("e1d2")), -25);
+        assert_eq!(orderer.get_history(Color::White, &mv("e4g5")), 0);
+
+        let mut ordered = chess_board.get_allowed_moves(Color::White);
+        orderer.order_moves(&chess_board, &mut ordered, 3, Some(mv("e1f1")), previous_move);
+
+        let first = moves(&chess_board, &["e1f1", "e4g5", "e4c5", "a1a7", "e1d1", "e4f2"]);
+        assert_eq!(&ordered[..first.len()], &first[..]);
+        let last = moves(&chess_board, &["e1d2", "e4d6"]);
+        assert_eq!(&ordered[ordered.len() - last.len()..], &last[..]);
+    }
+
+    #[test]
+    fn new_searches_forget_killers_and_age_history() {
+        let chess_board = ChessBoard::from_fen(FEN).unwrap();
+        let black_board = ChessBoard::from_fen(&FEN.replace(" w ", " b ")).unwrap();
+        let mv = |move_str| moves(&chess_board, &[move_str])[0];
+        let previous_move = Some(moves(&black_board, &["e8f8"])[0]);
+
+        let mut orderer = MoveOrderer::new();
+        orderer.record_cutoff(&chess_board, mv("e4f2"), 2, 5, previous_move, &[mv("e1d2")]);
+        orderer.new_search();
+
+        assert_eq!(orderer.get_killers(2), [None, None]);
+        assert_eq!(orderer.get_counter_move(previous_move), Some(mv("e4f2")));
+        assert_eq!(orderer.get_history(Color::White, &mv("e4f2")), 12);
+        assert_eq!(orderer.get_history(Color::White, &mv("e1d2")), -12);
+
+        // However many cutoffs, history stays below killers and countermoves
+        for _ in 0..1000 {
+            orderer.record_cutoff(&chess_board, mv("e4f2"), 2, 100, None, &[mv("e1d2")]);
+        }
+        assert!(orderer.get_history(Color::White, &mv("e4f2")) <= MAX_HISTORY);
+        assert!(orderer.get_history(Color::White, &mv("e1d2")) >= -MAX_HISTORY);
+    }
+}
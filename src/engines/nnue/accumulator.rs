@@ -0,0 +1,259 @@
+use crate::chess_server::chess_types::{ChessBoard, Color, Square};
+
+use super::network::NnueNetwork;
+use super::simd;
+
+// Beyond this many changed squares, starting over is cheaper
+const MAX_CHANGED_SQUARES: usize = 12;
+
+const COLORS: [Color; 2] = [Color::White, Color::Black];
+
+/// Output of the feature transformer for both sides, white first.
+///
+/// A move only changes a few features, so the accumulator of a position is
+/// best found by updating the one of a position close to it, e.g. its parent
+/// or one of its siblings in a search. When the king of a side moves all its
+/// features change and its half is computed again.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Accumulator {
+    pub values: [Vec<i16>; 2],
+}
+
+impl Accumulator {
+    pub fn new(network: &NnueNetwork, chess_board: &ChessBoard) -> Accumulator {
+        let mut accumulator = Accumulator {
+            values: [Vec::new(), Vec::new()],
+        };
+
+        for perspective in COLORS {
+            accumulator.refresh(network, chess_board, perspective);
+        }
+
+        accumulator
+    }
+
+    pub fn refresh(&mut self, network: &NnueNetwork, chess_board: &ChessBoard, perspective: Color) {
+        let king_square = chess_board.find_king(perspective).get_index() as usize;
+        let values = &mut self.values[perspective as usize];
+
+        values.clear();
+        values.extend_from_slice(&network.feature_bias);
+
+        for (square, content) in chess_board.iter_coordinates() {
+            let feature = content.and_then(|color_piece| {
+                NnueNetwork::feature_index(
+                    perspective,
+                    king_square,
+                    color_piece,
+                    square.get_index() as usize,
+                )
+            });
+
+            if let Some(feature) = feature {
+                simd::add_assign(values, network.feature_weights(feature));
+            }
+        }
+    }
+
+    /// Turns the accumulator of `previous` into the one of `chess_board`.
+    pub fn update(&mut self, network: &NnueNetwork, previous: &ChessBoard, chess_board: &ChessBoard) {
+        // Changed squares as a mask, which keeps updates free of allocations
+        let mut changed_squares = 0_u64;
+        for index in 0..64 {
+            let square = Square::from_index(index).unwrap();
+            if previous.get_square_content(&square) != chess_board.get_square_content(&square) {
+                changed_squares |= 1 << index;
+            }
+        }
+
+        for perspective in COLORS {
+            let king_square = chess_board.find_king(perspective);
+
+            if changed_squares.count_ones() as usize > MAX_CHANGED_SQUARES
+                || previous.find_king(perspective) != king_square
+            {
+                self.refresh(network, chess_board, perspective);
+                continue;
+            }
+
+            let king_square = king_square.get_index() as usize;
+            let values = &mut self.values[perspective as usize];
+
+            let mut remaining = changed_squares;
+            while remaining != 0 {
+                let square = Square::from_index(remaining.trailing_zeros() as i8).unwrap();
+                remaining &= remaining - 1;
+
+                let feature = |board: &ChessBoard| {
+                    board.get_square_content(&square).and_then(|color_piece| {
+                        NnueNetwork::feature_index(
+                            perspective,
+                            king_square,
+                            color_piece,
+                            square.get_index() as usize,
+                        )
+                    })
+                };
+
+                if let Some(feature) = feature(previous) {
+                    simd::sub_assign(values, network.feature_weights(feature));
+                }
+                if let Some(feature) = feature(chess_board) {
+                    simd::add_assign(values, network.feature_weights(feature));
+                }
+            }
+        }
+    }
+}
+
+/// Accumulators of the positions along the line being searched, one per ply.
+///
+/// Searchers push the position after every move they make and pop it when
+/// they unmake the move, so each accumulator is updated from the one of its
+/// parent. Positions that aren't the last one pushed, e.g. from searchers
+/// that don't push, are updated from the closest accumulator into `base`.
+/// Popped plies keep their buffers, so going back down to the same depth
+/// doesn't allocate.
+#[derive(Clone, Debug)]
+pub struct AccumulatorStack {
+    base: (ChessBoard, Accumulator),
+    plies: Vec<(ChessBoard, Accumulator)>,
+    len: usize,
+}
+
+impl AccumulatorStack {
+    pub fn new(network: &NnueNetwork, chess_board: &ChessBoard) -> AccumulatorStack {
+        AccumulatorStack {
+            base: (*chess_board, Accumulator::new(network, chess_board)),
+            plies: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, network: &NnueNetwork, chess_board: &ChessBoard) {
+        if self.plies.len() == self.len {
+            self.plies.push((*chess_board, Accumulator::default()));
+        }
+
+        let (previous_plies, next_plies) = self.plies.split_at_mut(self.len);
+        let (previous, parent) = previous_plies.last().unwrap_or(&self.base);
+        let (position, accumulator) = &mut next_plies[0];
+
+        accumulator.clone_from(parent);
+        accumulator.update(network, previous, chess_board);
+        *position = *chess_board;
+        self.len += 1;
+    }
+
+    // Popping more than was pushed is a no-op, the base stays
+    pub fn pop(&mut self) {
+        self.len = self.len.saturating_sub(1);
+    }
+
+    pub fn accumulator(&mut self, network: &NnueNetwork, chess_board: &ChessBoard) -> &Accumulator {
+        if self.len > 0 {
+            let (position, accumulator) = &self.plies[self.len - 1];
+            if position == chess_board {
+                return &self.plies[self.len - 1].1;
+            }
+            self.base.0 = *position;
+            self.base.1.clone_from(accumulator);
+        }
+
+        let (previous, accumulator) = &mut self.base;
+        accumulator.update(network, previous, chess_board);
+        *previous = *chess_board;
+        accumulator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    fn random_network(rng: &mut StdRng) -> NnueNetwork {
+        let mut network = NnueNetwork::zeros(24, 8, 8);
+        network
+            .feature_bias
+            .iter_mut()
+            .for_each(|value| *value = rng.gen_range(-64..64));
+        network
+            .feature_weights
+            .iter_mut()
+            .for_each(|value| *value = rng.gen_range(-64..64));
+        network
+    }
+
+    // Positions of random games, starting over when a game ends
+    fn random_line(rng: &mut StdRng, length: usize) -> Vec<ChessBoard> {
+        let mut line = vec![ChessBoard::starting_position()];
+
+        for _ in 0..length {
+            let chess_board = *line.last().unwrap();
+            let moves = chess_board.get_allowed_moves(chess_board.get_turn_color());
+            match moves.choose(rng) {
+                Some(mv) => line.push(chess_board.next_state(mv)),
+                None => line.push(ChessBoard::starting_position()),
+            }
+        }
+
+        line
+    }
+
+    #[test]
+    fn updates_match_refreshes() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let network = random_network(&mut rng);
+
+        for _ in 0..5 {
+            let line = random_line(&mut rng, 120);
+            let mut accumulator = Accumulator::new(&network, &line[0]);
+
+            for positions in line.windows(2) {
+                accumulator.update(&network, &positions[0], &positions[1]);
+                assert_eq!(accumulator, Accumulator::new(&network, &positions[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn stack_follows_pushes_and_pops() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let network = random_network(&mut rng);
+        let line = random_line(&mut rng, 40);
+        let mut stack = AccumulatorStack::new(&network, &line[0]);
+
+        // Down the line, back up half of it and down another one
+        for position in &line[1..] {
+            stack.push(&network, position);
+            let expected = Accumulator::new(&network, position);
+            assert_eq!(stack.accumulator(&network, position), &expected);
+        }
+
+        for depth in (20..line.len() - 1).rev() {
+            stack.pop();
+            let expected = Accumulator::new(&network, &line[depth]);
+            assert_eq!(stack.accumulator(&network, &line[depth]), &expected);
+        }
+
+        let branch = line[20];
+        let moves = branch.get_allowed_moves(branch.get_turn_color());
+        for mv in moves.iter() {
+            let next_board = branch.next_state(mv);
+            stack.push(&network, &next_board);
+            let expected = Accumulator::new(&network, &next_board);
+            assert_eq!(stack.accumulator(&network, &next_board), &expected);
+            stack.pop();
+        }
+
+        // Positions that weren't pushed still get their own accumulator
+        for position in line.iter().rev() {
+            let expected = Accumulator::new(&network, position);
+            assert_eq!(stack.accumulator(&network, position), &expected);
+        }
+    }
+}
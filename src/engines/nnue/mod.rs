@@ -0,0 +1,6 @@
+pub mod accumulator;
+pub mod network;
+pub mod simd;
+
+pub use accumulator::{Accumulator, AccumulatorStack};
+pub use network::{LayerBuffers, NnueNetwork};
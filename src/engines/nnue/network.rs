@@ -0,0 +1,376 @@
+use crate::chess_server::chess_types::{Color, ColorPiece, Piece};
+
+use super::accumulator::Accumulator;
+use super::simd;
+
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// King square, then the 10 pieces that aren't kings on their square
+pub const HALFKP_FEATURES: usize = 64 * 10 * 64;
+
+// Activations are clipped to [0, ACTIVATION_MAX], which stands for 1
+pub const ACTIVATION_MAX: i32 = 127;
+// Hidden weights are scaled by 2^WEIGHT_SHIFT
+pub const WEIGHT_SHIFT: u32 = 6;
+// Output units per centipawn
+pub const OUTPUT_SCALE: i32 = 16;
+
+const MAGIC: &[u8; 4] = b"NNUE";
+const VERSION: u32 = 1;
+
+// Lets evaluators tell networks apart when they keep accumulators around
+static NEXT_NETWORK_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A HalfKP network: the features of both sides go through the same
+/// feature transformer into an accumulator, followed by two clipped ReLU
+/// hidden layers and the output.
+///
+/// The weights file is little endian: `NNUE`, the version, the accumulator
+/// and hidden layer sizes as u32, then the feature biases and weights
+/// (i16, feature by feature), the hidden biases (i32) and weights (i8,
+/// neuron by neuron) of both layers, the output bias (i32) and weights (i8).
+/// The first hidden layer takes the accumulator of the side to move first.
+#[derive(Clone, Debug)]
+pub struct NnueNetwork {
+    id: usize,
+    pub accumulator_size: usize,
+    pub feature_bias: Vec<i16>,
+    pub feature_weights: Vec<i16>,
+    pub hidden1_bias: Vec<i32>,
+    pub hidden1_weights: Vec<i8>,
+    pub hidden2_bias: Vec<i32>,
+    pub hidden2_weights: Vec<i8>,
+    pub output_bias: i32,
+    pub output_weights: Vec<i8>,
+}
+
+/// Outputs of the layers, kept between evaluations so that propagating
+/// doesn't allocate.
+#[derive(Clone, Debug, Default)]
+pub struct LayerBuffers {
+    inputs: Vec<u8>,
+    hidden1: Vec<u8>,
+    hidden2: Vec<u8>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        if self.bytes.len() < N {
+            return Err(String::from("Truncated network file"));
+        }
+
+        let (value, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        Ok(value.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> Result<usize, String> {
+        Ok(u32::from_le_bytes(self.take()?) as usize)
+    }
+
+    fn i32s(&mut self, count: usize) -> Result<Vec<i32>, String> {
+        (0..count).map(|_| Ok(i32::from_le_bytes(self.take()?))).collect()
+    }
+
+    fn i16s(&mut self, count: usize) -> Result<Vec<i16>, String> {
+        (0..count).map(|_| Ok(i16::from_le_bytes(self.take()?))).collect()
+    }
+
+    fn i8s(&mut self, count: usize) -> Result<Vec<i8>, String> {
+        (0..count).map(|_| Ok(i8::from_le_bytes(self.take()?))).collect()
+    }
+}
+
+impl NnueNetwork {
+    // All weights are zero, to be filled in by hand
+    pub fn zeros(accumulator_size: usize, hidden1_size: usize, hidden2_size: usize) -> NnueNetwork {
+        NnueNetwork {
+            id: NEXT_NETWORK_ID.fetch_add(1, Ordering::Relaxed),
+            accumulator_size,
+            feature_bias: vec![0; accumulator_size],
+            feature_weights: vec![0; HALFKP_FEATURES * accumulator_size],
+            hidden1_bias: vec![0; hidden1_size],
+            hidden1_weights: vec![0; hidden1_size * 2 * accumulator_size],
+            hidden2_bias: vec![0; hidden2_size],
+            hidden2_weights: vec![0; hidden2_size * hidden1_size],
+            output_bias: 0,
+            output_weights: vec![0; hidden2_size],
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<NnueNetwork, String> {
+        let mut reader = Reader { bytes };
+
+        if &reader.take::<4>()? != MAGIC {
+            return Err(String::from("Not a network file"));
+        }
+        let version = reader.u32()?;
+        if version != VERSION as usize {
+            return Err(format!("Unsupported network version {}", version));
+        }
+
+        let accumulator_size = reader.u32()?;
+        let hidden1_size = reader.u32()?;
+        let hidden2_size = reader.u32()?;
+
+        if accumulator_size == 0 || hidden1_size == 0 || hidden2_size == 0 {
+            return Err(String::from("Layer sizes of the network must be positive"));
+        }
+
+        // The sizes are checked against the file before anything is
+        // allocated from them
+        let expected_size = NnueNetwork::weights_size(accumulator_size, hidden1_size, hidden2_size);
+        if expected_size != Some(reader.bytes.len()) {
+            return Err(String::from("Layer sizes don't match the size of the network file"));
+        }
+
+        let mut network = NnueNetwork::zeros(accumulator_size, hidden1_size, hidden2_size);
+        network.feature_bias = reader.i16s(accumulator_size)?;
+        network.feature_weights = reader.i16s(HALFKP_FEATURES * accumulator_size)?;
+        network.hidden1_bias = reader.i32s(hidden1_size)?;
+        network.hidden1_weights = reader.i8s(hidden1_size * 2 * accumulator_size)?;
+        network.hidden2_bias = reader.i32s(hidden2_size)?;
+        network.hidden2_weights = reader.i8s(hidden2_size * hidden1_size)?;
+        network.output_bias = reader.i32s(1)?[0];
+        network.output_weights = reader.i8s(hidden2_size)?;
+
+        Ok(network)
+    }
+
+    // Bytes of the file after the header, None when it can't even be counted
+    fn weights_size(
+        accumulator_size: usize,
+        hidden1_size: usize,
+        hidden2_size: usize,
+    ) -> Option<usize> {
+        let features = HALFKP_FEATURES.checked_mul(accumulator_size)?.checked_mul(2)?;
+        let hidden1 = hidden1_size.checked_mul(accumulator_size)?.checked_mul(2)?;
+        let hidden2 = hidden2_size.checked_mul(hidden1_size)?;
+
+        [
+            accumulator_size.checked_mul(2)?,
+            features,
+            hidden1_size.checked_mul(4)?,
+            hidden1,
+            hidden2_size.checked_mul(4)?,
+            hidden2,
+            4,
+            hidden2_size,
+        ]
+        .into_iter()
+        .try_fold(0_usize, |total, size| total.checked_add(size))
+    }
+
+    pub fn from_file(path: &str) -> Result<NnueNetwork, String> {
+        let bytes = fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+        NnueNetwork::from_bytes(&bytes).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    // Networks are trained outside of the engine, only the tests write them
+    #[cfg(test)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+
+        let hidden_sizes = [self.hidden1_bias.len(), self.hidden2_bias.len()];
+        for size in [VERSION as usize, self.accumulator_size, hidden_sizes[0], hidden_sizes[1]] {
+            bytes.extend((size as u32).to_le_bytes());
+        }
+        bytes.extend(self.feature_bias.iter().flat_map(|value| value.to_le_bytes()));
+        bytes.extend(self.feature_weights.iter().flat_map(|value| value.to_le_bytes()));
+        bytes.extend(self.hidden1_bias.iter().flat_map(|value| value.to_le_bytes()));
+        bytes.extend(self.hidden1_weights.iter().flat_map(|value| value.to_le_bytes()));
+        bytes.extend(self.hidden2_bias.iter().flat_map(|value| value.to_le_bytes()));
+        bytes.extend(self.hidden2_weights.iter().flat_map(|value| value.to_le_bytes()));
+        bytes.extend(self.output_bias.to_le_bytes());
+        bytes.extend(self.output_weights.iter().flat_map(|value| value.to_le_bytes()));
+
+        bytes
+    }
+
+    #[cfg(test)]
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_bytes()).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
+    /// Index of a piece on a square seen by one side, whose king is on
+    /// `king_square`. Black sees the board flipped, so that both sides use
+    /// the same weights. Kings aren't features.
+    pub fn feature_index(
+        perspective: Color,
+        king_square: usize,
+        color_piece: ColorPiece,
+        square: usize,
+    ) -> Option<usize> {
+        let piece = match color_piece.get_piece() {
+            Piece::Pawn => 0,
+            Piece::Knight => 1,
+            Piece::Bishop => 2,
+            Piece::Rook => 3,
+            Piece::Queen => 4,
+            Piece::King => return None,
+        };
+        let theirs = (color_piece.get_color() != perspective) as usize;
+
+        let orient = |square: usize| match perspective {
+            Color::White => square,
+            Color::Black => square ^ 56,
+        };
+
+        Some((orient(king_square) * 10 + 2 * piece + theirs) * 64 + orient(square))
+    }
+
+    pub fn feature_weights(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.accumulator_size..(feature + 1) * self.accumulator_size]
+    }
+
+    fn dense(inputs: &[u8], bias: &[i32], weights: &[i8], outputs: &mut Vec<u8>) {
+        outputs.clear();
+        outputs.extend(bias.iter().zip(weights.chunks_exact(inputs.len())).map(
+            |(bias, weights)| {
+                let value = (bias + simd::dot(inputs, weights)) >> WEIGHT_SHIFT;
+                value.clamp(0, ACTIVATION_MAX) as u8
+            },
+        ));
+    }
+
+    /// Evaluation in centipawns for the side to move.
+    pub fn propagate(
+        &self,
+        accumulator: &Accumulator,
+        side_to_move: Color,
+        buffers: &mut LayerBuffers,
+    ) -> i32 {
+        let clip = |value: &i16| (*value as i32).clamp(0, ACTIVATION_MAX) as u8;
+
+        buffers.inputs.clear();
+        buffers.inputs.extend(
+            accumulator.values[side_to_move as usize]
+                .iter()
+                .chain(accumulator.values[side_to_move.opposite() as usize].iter())
+                .map(clip),
+        );
+
+        let LayerBuffers {
+            inputs,
+            hidden1,
+            hidden2,
+        } = buffers;
+        NnueNetwork::dense(inputs, &self.hidden1_bias, &self.hidden1_weights, hidden1);
+        NnueNetwork::dense(hidden1, &self.hidden2_bias, &self.hidden2_weights, hidden2);
+
+        (self.output_bias + simd::dot(hidden2, &self.output_weights)) / OUTPUT_SCALE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use std::env;
+    use std::process;
+
+    fn random_network() -> NnueNetwork {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut network = NnueNetwork::zeros(4, 3, 2);
+
+        network.feature_bias.iter_mut().for_each(|value| *value = rng.gen());
+        network.feature_weights.iter_mut().for_each(|value| *value = rng.gen());
+        network.hidden1_bias.iter_mut().for_each(|value| *value = rng.gen());
+        network.hidden1_weights.iter_mut().for_each(|value| *value = rng.gen());
+        network.hidden2_bias.iter_mut().for_each(|value| *value = rng.gen());
+        network.hidden2_weights.iter_mut().for_each(|value| *value = rng.gen());
+        network.output_bias = rng.gen();
+        network.output_weights.iter_mut().for_each(|value| *value = rng.gen());
+
+        network
+    }
+
+    // Overwrites the u32 of the header at `index`, 0 being the version
+    fn set_header(bytes: &mut [u8], index: usize, value: u32) {
+        let start = 4 + 4 * index;
+        bytes[start..start + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn networks_survive_a_round_trip() {
+        let network = random_network();
+        let bytes = network.to_bytes();
+        let read = NnueNetwork::from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            (read.accumulator_size, read.hidden1_bias.len(), read.hidden2_bias.len()),
+            (4, 3, 2)
+        );
+        assert_eq!(read.feature_bias, network.feature_bias);
+        assert_eq!(read.feature_weights, network.feature_weights);
+        assert_eq!(read.hidden1_bias, network.hidden1_bias);
+        assert_eq!(read.hidden1_weights, network.hidden1_weights);
+        assert_eq!(read.hidden2_bias, network.hidden2_bias);
+        assert_eq!(read.hidden2_weights, network.hidden2_weights);
+        assert_eq!(read.output_bias, network.output_bias);
+        assert_eq!(read.output_weights, network.output_weights);
+        assert_eq!(read.to_bytes(), bytes);
+        assert_ne!(read.get_id(), network.get_id());
+
+        let path = env::temp_dir().join(format!("network_{}.nnue", process::id()));
+        let path = path.to_str().unwrap();
+        network.save(path).unwrap();
+        let loaded = NnueNetwork::from_file(path);
+        fs::remove_file(path).unwrap();
+        assert_eq!(loaded.unwrap().to_bytes(), bytes);
+    }
+
+    #[test]
+    fn broken_files_are_rejected() {
+        let bytes = random_network().to_bytes();
+
+        assert!(NnueNetwork::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(NnueNetwork::from_bytes(&bytes[..10]).is_err());
+        assert!(NnueNetwork::from_bytes(&[]).is_err());
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(NnueNetwork::from_bytes(&trailing).is_err());
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(
+            NnueNetwork::from_bytes(&bad_magic).err(),
+            Some(String::from("Not a network file"))
+        );
+
+        let mut bad_version = bytes.clone();
+        set_header(&mut bad_version, 0, VERSION + 1);
+        assert!(NnueNetwork::from_bytes(&bad_version).is_err());
+
+        let mut empty_layer = bytes.clone();
+        set_header(&mut empty_layer, 2, 0);
+        assert!(NnueNetwork::from_bytes(&empty_layer).is_err());
+    }
+
+    #[test]
+    fn oversized_headers_are_rejected_before_allocating() {
+        let bytes = random_network().to_bytes();
+
+        for layer in 1..=3 {
+            let mut oversized = bytes.clone();
+            set_header(&mut oversized, layer, u32::MAX);
+            assert_eq!(
+                NnueNetwork::from_bytes(&oversized).err(),
+                Some(String::from("Layer sizes don't match the size of the network file"))
+            );
+        }
+    }
+}
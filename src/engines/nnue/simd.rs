@@ -0,0 +1,177 @@
+// Vector operations of the network, with AVX2 when the CPU has it and a
+// scalar fallback otherwise
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+#[cfg(target_arch = "x86_64")]
+fn has_avx2() -> bool {
+    is_x86_feature_detected!("avx2")
+}
+
+pub fn add_assign(values: &mut [i16], deltas: &[i16]) {
+    assert_eq!(values.len(), deltas.len());
+
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // Safe as the CPU supports AVX2 and the slices have the same length
+        unsafe { add_assign_avx2(values, deltas) };
+        return;
+    }
+
+    add_assign_scalar(values, deltas);
+}
+
+pub fn sub_assign(values: &mut [i16], deltas: &[i16]) {
+    assert_eq!(values.len(), deltas.len());
+
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        unsafe { sub_assign_avx2(values, deltas) };
+        return;
+    }
+
+    sub_assign_scalar(values, deltas);
+}
+
+/// Dot product of activations, which are at most 127, and weights.
+pub fn dot(inputs: &[u8], weights: &[i8]) -> i32 {
+    assert_eq!(inputs.len(), weights.len());
+
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        return unsafe { dot_avx2(inputs, weights) };
+    }
+
+    dot_scalar(inputs, weights)
+}
+
+fn add_assign_scalar(values: &mut [i16], deltas: &[i16]) {
+    for (value, delta) in values.iter_mut().zip(deltas) {
+        *value = value.wrapping_add(*delta);
+    }
+}
+
+fn sub_assign_scalar(values: &mut [i16], deltas: &[i16]) {
+    for (value, delta) in values.iter_mut().zip(deltas) {
+        *value = value.wrapping_sub(*delta);
+    }
+}
+
+fn dot_scalar(inputs: &[u8], weights: &[i8]) -> i32 {
+    inputs
+        .iter()
+        .zip(weights)
+        .map(|(&input, &weight)| input as i32 * weight as i32)
+        .sum()
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn add_assign_avx2(values: &mut [i16], deltas: &[i16]) {
+    let chunks = values.len() / 16;
+
+    for chunk in 0..chunks {
+        let value_ptr = values.as_mut_ptr().add(16 * chunk) as *mut __m256i;
+        let delta_ptr = deltas.as_ptr().add(16 * chunk) as *const __m256i;
+
+        let sum = _mm256_add_epi16(_mm256_loadu_si256(value_ptr), _mm256_loadu_si256(delta_ptr));
+        _mm256_storeu_si256(value_ptr, sum);
+    }
+
+    add_assign_scalar(&mut values[16 * chunks..], &deltas[16 * chunks..]);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn sub_assign_avx2(values: &mut [i16], deltas: &[i16]) {
+    let chunks = values.len() / 16;
+
+    for chunk in 0..chunks {
+        let value_ptr = values.as_mut_ptr().add(16 * chunk) as *mut __m256i;
+        let delta_ptr = deltas.as_ptr().add(16 * chunk) as *const __m256i;
+
+        let difference =
+            _mm256_sub_epi16(_mm256_loadu_si256(value_ptr), _mm256_loadu_si256(delta_ptr));
+        _mm256_storeu_si256(value_ptr, difference);
+    }
+
+    sub_assign_scalar(&mut values[16 * chunks..], &deltas[16 * chunks..]);
+}
+
+// The products of two neighbours are summed in 16 bits, which can't
+// saturate since inputs are at most 127
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn dot_avx2(inputs: &[u8], weights: &[i8]) -> i32 {
+    let chunks = inputs.len() / 32;
+    let ones = _mm256_set1_epi16(1);
+    let mut sums = _mm256_setzero_si256();
+
+    for chunk in 0..chunks {
+        let input = _mm256_loadu_si256(inputs.as_ptr().add(32 * chunk) as *const __m256i);
+        let weight = _mm256_loadu_si256(weights.as_ptr().add(32 * chunk) as *const __m256i);
+
+        let products = _mm256_maddubs_epi16(input, weight);
+        sums = _mm256_add_epi32(sums, _mm256_madd_epi16(products, ones));
+    }
+
+    let mut lanes = [0i32; 8];
+    _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sums);
+
+    lanes.iter().sum::<i32>() + dot_scalar(&inputs[32 * chunks..], &weights[32 * chunks..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn avx2_matches_scalar() {
+        if !has_avx2() {
+            return;
+        }
+
+        let mut rng = StdRng::seed_from_u64(42);
+
+        // Lengths with and without a remainder after the vectors
+        for len in [0, 1, 15, 16, 31, 32, 33, 100, 256] {
+            let values: Vec<i16> = (0..len).map(|_| rng.gen()).collect();
+            let deltas: Vec<i16> = (0..len).map(|_| rng.gen()).collect();
+
+            let mut expected = values.clone();
+            let mut actual = values.clone();
+            add_assign_scalar(&mut expected, &deltas);
+            unsafe { add_assign_avx2(&mut actual, &deltas) };
+            assert_eq!(actual, expected, "add_assign of {} values", len);
+
+            sub_assign_scalar(&mut expected, &deltas);
+            unsafe { sub_assign_avx2(&mut actual, &deltas) };
+            assert_eq!(actual, values, "sub_assign of {} values", len);
+            assert_eq!(actual, expected, "sub_assign of {} values", len);
+
+            let inputs: Vec<u8> = (0..len).map(|_| rng.gen_range(0..=127)).collect();
+            let weights: Vec<i8> = (0..len).map(|_| rng.gen()).collect();
+            assert_eq!(
+                unsafe { dot_avx2(&inputs, &weights) },
+                dot_scalar(&inputs, &weights),
+                "dot of {} values",
+                len
+            );
+        }
+
+        // Extreme inputs and weights, where 16 bit sums would overflow first
+        let inputs = vec![127_u8; 64];
+        for weight in [i8::MIN, i8::MAX] {
+            let weights = vec![weight; 64];
+            assert_eq!(
+                unsafe { dot_avx2(&inputs, &weights) },
+                dot_scalar(&inputs, &weights)
+            );
+        }
+    }
+}
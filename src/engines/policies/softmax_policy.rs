@@ -1,10 +1,9 @@
 use std::iter::Sum;
 
 use crate::chess_server::chess_types::chess_board::{MoveContainer, MOVE_CONTAINER_SIZE};
-use crate::chess_server::chess_types::{ChessBoard, Color};
+use crate::chess_server::chess_types::ChessBoard;
 use crate::engines::engine_traits::*;
 
-use ordered_float::OrderedFloat;
 use smallvec::SmallVec;
 
 #[derive(Clone)]
@@ -32,17 +31,13 @@ impl<E: Evaluator> Policy for SoftmaxPolicy<E> {
         chess_board: &ChessBoard,
         moves: &MoveContainer,
     ) -> SmallVec<[f64; MOVE_CONTAINER_SIZE]> {
-        let color = chess_board.get_turn_color();
-        let sign = match color {
-            Color::White => OrderedFloat(1.),
-            Color::Black => OrderedFloat(-1.),
-        };
-
+        // Positions after the move are evaluated from the point of view of
+        // the opponent
         let mut output: SmallVec<[_; MOVE_CONTAINER_SIZE]> = moves
             .iter()
             .map(|x| {
-                (sign * self.evaluator.evaluate(&chess_board.next_state(x)) / self.temperature)
-                    .exp()
+                let eval = -self.evaluator.evaluate(&chess_board.next_state(x));
+                (eval.to_pawns() / self.temperature).exp()
             })
             .collect();
 
@@ -0,0 +1,318 @@
+use crate::chess_server::chess_types::chess_board::SEE_VALUES;
+use crate::chess_server::chess_types::{ChessBoard, ChessStatus, Color};
+use crate::engines::engine_traits::Evaluator;
+use crate::engines::move_ordering::{is_tactical, mvv_lva, MAX_PLY};
+use crate::engines::score::Score;
+
+use std::cmp::max;
+
+/// Features of [`QuiescenceSearch`].
+///
+/// `delta_margin` is the slack given to a capture before delta pruning
+/// decides that it can't bring the score back above alpha.
+/// `max_depth` bounds the number of quiescence plies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuiescenceConfig {
+    pub delta_pruning: bool,
+    pub delta_margin: Score,
+    pub see_pruning: bool,
+    pub check_evasions: bool,
+    pub quiet_checks: bool,
+    pub max_depth: usize,
+}
+
+impl Default for QuiescenceConfig {
+    fn default() -> Self {
+        QuiescenceConfig {
+            delta_pruning: true,
+            delta_margin: Score::cp(200),
+            see_pruning: true,
+            check_evasions: true,
+            quiet_checks: false,
+            max_depth: 16,
+        }
+    }
+}
+
+/// Quiescence search that can be plugged at the leaves of any searcher.
+///
+/// Only captures and promotions are searched, after a stand pat on the static
+/// evaluation. When the side to move is in check every evasion is searched
+/// instead, and with `quiet_checks` the first ply also tries quiet checking
+/// moves. Scores are negamax scores from the side to move, with mates
+/// counted from the root of the search, `ply` plies above.
+#[derive(Clone, Copy, Debug)]
+pub struct QuiescenceSearch {
+    config: QuiescenceConfig,
+}
+
+impl QuiescenceSearch {
+    pub fn new(config: QuiescenceConfig) -> QuiescenceSearch {
+        QuiescenceSearch { config }
+    }
+
+    pub fn search<E: Evaluator>(
+        &self,
+        chess_board: &ChessBoard,
+        evaluator: &E,
+        ply: usize,
+        alpha: Score,
+        beta: Score,
+    ) -> Score {
+        self.search_with_stop(chess_board, evaluator, ply, alpha, beta, &|| false)
+    }
+
+    /// Same as [`QuiescenceSearch::search`], `stop` is called once per node
+    /// and the search unwinds with meaningless scores once it returns true.
+    pub fn search_with_stop<E: Evaluator, F: Fn() -> bool>(
+        &self,
+        chess_board: &ChessBoard,
+        evaluator: &E,
+        ply: usize,
+        alpha: Score,
+        beta: Score,
+        stop: &F,
+    ) -> Score {
+        self.search_impl(chess_board, evaluator, 0, ply, alpha, beta, stop)
+    }
+
+    /// Quiescence search for the minimax searchers, where scores are always
+    /// given from the point of view of white.
+    pub fn search_white_perspective<E: Evaluator>(
+        &self,
+        chess_board: &ChessBoard,
+        evaluator: &E,
+        alpha: Score,
+        beta: Score,
+    ) -> Score {
+        let color = chess_board.get_turn_color();
+        let (alpha, beta) = match color {
+            Color::White => (alpha, beta),
+            Color::Black => (-beta, -alpha),
+        };
+
+        self.search(chess_board, evaluator, 0, alpha, beta)
+            .relative(color)
+    }
+
+    /// Value of a leaf of the minimax searchers, from the point of view of
+    /// white: the quiescence search when there is one, the static
+    /// evaluation otherwise.
+    pub fn evaluate_leaf<E: Evaluator>(
+        quiescence: Option<&QuiescenceSearch>,
+        chess_board: &ChessBoard,
+        evaluator: &E,
+        alpha: Score,
+        beta: Score,
+    ) -> Score {
+        match quiescence {
+            Some(quiescence) => {
+                quiescence.search_white_perspective(chess_board, evaluator, alpha, beta)
+            }
+            None => evaluator.evaluate_white(chess_board),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn search_impl<E: Evaluator, F: Fn() -> bool>(
+        &self,
+        chess_board: &ChessBoard,
+        evaluator: &E,
+        depth: usize,
+        ply: usize,
+        alpha: Score,
+        beta: Score,
+        stop: &F,
+    ) -> Score {
+        if stop() {
+            return Score::DRAW;
+        }
+
+        let color = chess_board.get_turn_color();
+        let mut moves = chess_board.get_allowed_moves(color);
+
+        match chess_board.get_game_status_from_precomputed(&moves) {
+            ChessStatus::Ongoing => (),
+            ChessStatus::Draw => return Score::DRAW,
+            // The side to move is always the one that got mated
+            ChessStatus::WhiteWon | ChessStatus::BlackWon => return Score::mated_in(ply as i32),
+        }
+
+        if depth >= self.config.max_depth || ply >= MAX_PLY - 1 {
+            return evaluator.evaluate(chess_board);
+        }
+
+        let in_check = self.config.check_evasions && chess_board.is_king_in_check(color);
+
+        // In check there is no stand pat, every evasion has to be searched
+        let (static_eval, mut alpha, mut best_score) = if in_check {
+            let mated = Score::mated_in(ply as i32);
+            (mated, alpha, mated)
+        } else {
+            let stand_pat = evaluator.evaluate(chess_board);
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            (stand_pat, max(alpha, stand_pat), stand_pat)
+        };
+
+        if !in_check {
+            let quiet_checks = self.config.quiet_checks && depth == 0;
+            moves.retain(|mv| {
+                is_tactical(chess_board, mv)
+                    || (quiet_checks
+                        && chess_board
+                            .next_state(mv)
+                            .is_king_in_check(color.opposite()))
+            });
+        }
+
+        moves.sort_by_cached_key(|mv| -mvv_lva(chess_board, mv));
+
+        for mv in moves {
+            if !in_check {
+                let capture = chess_board.get_square_content(&mv.get_next_square());
+
+                // Delta pruning: even winning the captured piece for free
+                // doesn't bring the score back to alpha
+                if let Some(victim) = capture {
+                    let victim_value = Score::cp(SEE_VALUES[victim.get_piece() as usize]);
+                    if self.config.delta_pruning
+                        && !mv.get_is_promotion()
+                        && static_eval + victim_value + self.config.delta_margin < alpha
+                    {
+                        continue;
+                    }
+                }
+
+                if self.config.see_pruning && capture.is_some() && !chess_board.see_ge(&mv, 0) {
+                    continue;
+                }
+            }
+
+            let next_board = chess_board.next_state(&mv);
+
+            evaluator.push_position(&next_board);
+            let score = -self.search_impl(
+                &next_board,
+                evaluator,
+                depth + 1,
+                ply + 1,
+                -beta,
+                -alpha,
+                stop,
+            );
+            evaluator.pop_position();
+
+            if score > best_score {
+                best_score = score;
+                alpha = max(alpha, score);
+            }
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best_score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::evaluators::MaterialEvaluator;
+
+    use std::cell::Cell;
+
+    // Material, counting the positions it evaluated
+    struct CountingEvaluator {
+        evaluator: MaterialEvaluator,
+        count: Cell<usize>,
+    }
+
+    impl Evaluator for CountingEvaluator {
+        fn get_name(&self) -> String {
+            "CountingEvaluator".to_string()
+        }
+
+        fn evaluate(&self, chess_board: &ChessBoard) -> Score {
+            self.count.set(self.count.get() + 1);
+            self.evaluator.evaluate(chess_board)
+        }
+    }
+
+    fn search(config: QuiescenceConfig, fen: &str, alpha: Score, beta: Score) -> (Score, usize) {
+        let evaluator = CountingEvaluator {
+            evaluator: MaterialEvaluator::new(),
+            count: Cell::new(0),
+        };
+        let chess_board = ChessBoard::from_fen(fen).unwrap();
+        let score = QuiescenceSearch::new(config).search(&chess_board, &evaluator, 0, alpha, beta);
+        (score, evaluator.count.get())
+    }
+
+    fn full_window(fen: &str) -> (Score, usize) {
+        search(
+            QuiescenceConfig::default(),
+            fen,
+            -Score::INFINITE,
+            Score::INFINITE,
+        )
+    }
+
+    #[test]
+    fn stands_pat_in_quiet_positions() {
+        assert_eq!(
+            full_window("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"),
+            (Score::cp(100), 1)
+        );
+        assert_eq!(
+            full_window("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1"),
+            (Score::cp(-100), 1)
+        );
+
+        // The stand pat alone fails high, the capture isn't even tried
+        let fen = "4k3/8/8/3r4/8/4N3/4P3/4K3 w - - 0 1";
+        let config = QuiescenceConfig::default();
+        assert_eq!(
+            search(config, fen, -Score::INFINITE, Score::cp(-300)),
+            (Score::cp(-100), 1)
+        );
+        assert_eq!(full_window(fen).0, Score::cp(400));
+    }
+
+    #[test]
+    fn prunes_captures_losing_material() {
+        // Qxd6 loses the queen to cxd6
+        let fen = "4k3/2p5/3p4/8/8/8/8/3QK3 w - - 0 1";
+        let no_see_pruning = QuiescenceConfig {
+            see_pruning: false,
+            ..QuiescenceConfig::default()
+        };
+
+        let (score, count) = full_window(fen);
+        assert_eq!((score, count), (Score::cp(700), 1));
+
+        let (unpruned_score, unpruned_count) =
+            search(no_see_pruning, fen, -Score::INFINITE, Score::INFINITE);
+        assert_eq!(unpruned_score, score);
+        assert!(unpruned_count > count);
+    }
+
+    #[test]
+    fn searches_every_evasion_in_check() {
+        // Whatever the king does, the knight takes the rook
+        let fen = "4k3/8/8/8/8/8/2n5/R3K3 w - - 0 1";
+        let no_evasions = QuiescenceConfig {
+            check_evasions: false,
+            ..QuiescenceConfig::default()
+        };
+
+        assert_eq!(full_window(fen).0, Score::cp(-300));
+        assert_eq!(
+            search(no_evasions, fen, -Score::INFINITE, Score::INFINITE).0,
+            Score::cp(200)
+        );
+    }
+}
@@ -1,5 +1,5 @@
-use crate::chess_server::chess_types::{ChessBoard, Move};
-use crate::chess_server::game::Player;
+use crate::chess_server::chess_types::ChessStatus;
+use crate::chess_server::game::{Decision, GameContext, Player};
 
 use rand::{rngs::ThreadRng, seq::SliceRandom, thread_rng}; // 0.6.1
 use std::cell::RefCell;
@@ -17,14 +17,15 @@ impl RandomEngine {
 }
 
 impl Player for RandomEngine {
-    fn select_move(&self, chess_board: &ChessBoard) -> Move {
-        let allowed_moves = chess_board.get_allowed_moves(chess_board.get_turn_color());
+    fn decide(&self, context: &GameContext) -> Decision {
+        if context.game_status != ChessStatus::Ongoing {
+            panic!("Game is Over - {:?}", context.game_status);
+        }
+
+        let chess_board = &context.chess_board;
+        let allowed_moves = chess_board.get_allowed_moves(context.get_turn_color());
         let mut rng = self.rng.borrow_mut();
 
-        if let Some(random_move) = allowed_moves.choose(&mut *rng) {
-            *random_move
-        } else {
-            panic!("Game is Over - No allowed moves.");
-        }
+        Decision::Move(*allowed_moves.choose(&mut *rng).unwrap())
     }
 }
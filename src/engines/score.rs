@@ -0,0 +1,305 @@
+use crate::chess_server::chess_types::Color;
+use crate::engines::move_ordering::MAX_PLY;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+const MATE: i32 = 32_000;
+// Scores closer than this to MATE are mates, everything else is centipawns
+const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+
+// Logistic model of the expected results: a side up by WDL_MIDPOINT
+// centipawns wins half of its games
+const WDL_MIDPOINT: f64 = 150.;
+const WDL_SCALE: f64 = 60.;
+
+/// Evaluation of a position in centipawns, from the point of view of the
+/// side to move.
+///
+/// Mates are encoded as `MATE - ply`, `ply` being the number of plies from
+/// the node the score belongs to until the mate, so that shorter mates are
+/// preferred by plain comparisons.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Score(i32);
+
+impl Score {
+    pub const DRAW: Score = Score(0);
+    /// Bound above every score, mates included.
+    pub const INFINITE: Score = Score(MATE + 1);
+    /// Largest score that isn't a mate.
+    pub const MAX_EVAL: Score = Score(MATE_BOUND - 1);
+
+    pub const fn cp(centipawns: i32) -> Score {
+        Score(centipawns)
+    }
+
+    /// Evaluations outside of the centipawn range are clamped so that they
+    /// can't be mistaken for a mate.
+    pub fn from_pawns(pawns: f64) -> Score {
+        let centipawns = (pawns * 100.).round();
+        let max_eval = Score::MAX_EVAL.0 as f64;
+
+        Score(centipawns.clamp(-max_eval, max_eval) as i32)
+    }
+
+    /// Sum of two evaluations, kept in the centipawn range so that adding
+    /// to a decided position can't make a mate out of it.
+    pub fn saturating_eval_add(self, other: Score) -> Score {
+        let max_eval = Score::MAX_EVAL.0;
+
+        Score((self.0 + other.0).clamp(-max_eval, max_eval))
+    }
+
+    /// The side to move mates in `ply` plies.
+    pub const fn mate_in(ply: i32) -> Score {
+        Score(MATE - ply)
+    }
+
+    /// The side to move gets mated in `ply` plies.
+    pub const fn mated_in(ply: i32) -> Score {
+        Score(-(MATE - ply))
+    }
+
+    /// Centipawns, or the encoding of the mate for mate scores.
+    pub fn get_centipawns(self) -> i32 {
+        self.0
+    }
+
+    pub fn to_pawns(self) -> f64 {
+        self.0 as f64 / 100.
+    }
+
+    pub fn is_mate(self) -> bool {
+        self.0.abs() > MATE_BOUND
+    }
+
+    /// Plies until the mate, whichever side gets mated.
+    pub fn get_mate_ply(self) -> Option<i32> {
+        self.is_mate().then_some(MATE - self.0.abs())
+    }
+
+    // Moves until the mate, negative when the side to move gets mated
+    fn mate_moves(self) -> Option<i32> {
+        self.get_mate_ply()
+            .map(|ply| if self.0 > 0 { (ply + 1) / 2 } else { -ply / 2 })
+    }
+
+    /// Score of the parent node given the score of a child node: the point
+    /// of view changes and mates get one ply further away.
+    pub fn forward(self) -> Score {
+        if self.0 > MATE_BOUND {
+            Score(-self.0 + 1)
+        } else if self.0 < -MATE_BOUND {
+            Score(-self.0 - 1)
+        } else {
+            Score(-self.0)
+        }
+    }
+
+    /// Inverse of [`Score::forward`], for bounds passed down to a child.
+    pub fn backward(self) -> Score {
+        if self.0 > MATE_BOUND {
+            Score(-self.0 - 1)
+        } else if self.0 < -MATE_BOUND {
+            Score(-self.0 + 1)
+        } else {
+            Score(-self.0)
+        }
+    }
+
+    /// Turns a score from the point of view of the side to move into one
+    /// from the point of view of white when black is to move, and the other
+    /// way around.
+    pub fn relative(self, color: Color) -> Score {
+        match color {
+            Color::White => self,
+            Color::Black => -self,
+        }
+    }
+
+    /// The score as sent by UCI engines, `cp <centipawns>` or
+    /// `mate <moves>`, negative when getting mated.
+    pub fn to_uci(self) -> String {
+        match self.mate_moves() {
+            Some(moves) => format!("mate {}", moves),
+            None => format!("cp {}", self.0),
+        }
+    }
+
+    pub fn from_uci(text: &str) -> Result<Score, String> {
+        let mut tokens = text.split_whitespace();
+
+        let kind = tokens.next();
+        let value: i32 = match tokens.next().map(str::parse) {
+            Some(Ok(value)) => value,
+            _ => return Err(format!("Invalid UCI score: {}", text)),
+        };
+
+        // Mates further than MAX_PLY can't be told apart from evaluations
+        let mate_ply = |ply: Option<i32>| {
+            ply.filter(|ply| *ply < MAX_PLY as i32)
+                .ok_or_else(|| format!("Mate too far away: {}", text))
+        };
+
+        match kind {
+            Some("cp") => Ok(Score::from_pawns(value as f64 / 100.)),
+            Some("mate") if value > 0 => {
+                let ply = value.checked_mul(2).map(|ply| ply - 1);
+                Ok(Score::mate_in(mate_ply(ply)?))
+            }
+            Some("mate") => Ok(Score::mated_in(mate_ply(value.checked_mul(-2))?)),
+            _ => Err(format!("Invalid UCI score: {}", text)),
+        }
+    }
+
+    /// Win, draw and loss probabilities of the side to move, per mille.
+    pub fn wdl(self) -> [u32; 3] {
+        if self.is_mate() {
+            return if self.0 > 0 {
+                [1000, 0, 0]
+            } else {
+                [0, 0, 1000]
+            };
+        }
+
+        let centipawns = self.0 as f64;
+        let win = 1. / (1. + ((WDL_MIDPOINT - centipawns) / WDL_SCALE).exp());
+        let loss = 1. / (1. + ((WDL_MIDPOINT + centipawns) / WDL_SCALE).exp());
+
+        let win = (1000. * win).round() as u32;
+        let loss = (1000. * loss).round() as u32;
+        [win, 1000 - win - loss, loss]
+    }
+}
+
+impl Neg for Score {
+    type Output = Score;
+
+    fn neg(self) -> Score {
+        Score(-self.0)
+    }
+}
+
+// Margins and windows; mates are only meant to be compared, and evaluations
+// are added with Score::saturating_eval_add
+impl Add for Score {
+    type Output = Score;
+
+    fn add(self, other: Score) -> Score {
+        Score(self.0 + other.0)
+    }
+}
+
+impl Sub for Score {
+    type Output = Score;
+
+    fn sub(self, other: Score) -> Score {
+        Score(self.0 - other.0)
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Score;
+
+    fn mul(self, factor: i32) -> Score {
+        Score(self.0 * factor)
+    }
+}
+
+impl Div<i32> for Score {
+    type Output = Score;
+
+    fn div(self, divisor: i32) -> Score {
+        Score(self.0 / divisor)
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mate_moves() {
+            Some(moves) => write!(f, "#{}", moves),
+            None => write!(f, "{:.2}", self.to_pawns()),
+        }
+    }
+}
+
+// Configs give scores in pawns, like the weights and thresholds around them
+impl Serialize for Score {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_pawns())
+    }
+}
+
+impl<'de> Deserialize<'de> for Score {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Score, D::Error> {
+        f64::deserialize(deserializer).map(Score::from_pawns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forward_undoes_backward() {
+        let scores = [
+            Score(MATE_BOUND - 1),
+            Score(MATE_BOUND),
+            Score(MATE_BOUND + 1),
+            Score::INFINITE,
+            Score::DRAW,
+        ];
+
+        for score in scores.into_iter().flat_map(|score| [score, -score]) {
+            assert_eq!(score.backward().forward(), score, "{:?}", score);
+        }
+    }
+
+    #[test]
+    fn uci_round_trips() {
+        for text in [
+            "mate 1", "mate -1", "mate 0", "mate 64", "mate -63", "cp 0", "cp -35",
+        ] {
+            assert_eq!(Score::from_uci(text).unwrap().to_uci(), text);
+        }
+
+        assert_eq!(Score::from_uci("mate 1"), Ok(Score::mate_in(1)));
+        assert_eq!(Score::from_uci("mate 0"), Ok(Score::mated_in(0)));
+        assert_eq!(Score::from_uci("mate -1"), Ok(Score::mated_in(2)));
+    }
+
+    #[test]
+    fn uci_mates_stay_mates() {
+        for text in [
+            "mate 65",
+            "mate -64",
+            "mate 2000000000",
+            "mate -2000000000",
+            "cp",
+        ] {
+            assert!(Score::from_uci(text).is_err(), "{}", text);
+        }
+        assert_eq!(Score::from_uci("cp 99999"), Ok(Score::MAX_EVAL));
+    }
+
+    #[test]
+    fn wdl_sums_to_1000() {
+        let scores = (-MATE - 1..=MATE + 1).step_by(7).map(Score);
+
+        for score in scores.chain([Score::mate_in(1), Score::mated_in(0), -Score::MAX_EVAL]) {
+            let [win, draw, loss] = score.wdl();
+            assert_eq!(win + draw + loss, 1000, "{:?}", score);
+        }
+    }
+
+    #[test]
+    fn scores_are_serialized_in_pawns() {
+        assert_eq!(serde_json::to_string(&Score::cp(-35)).unwrap(), "-0.35");
+        assert_eq!(serde_json::from_str::<Score>("0.5").unwrap(), Score::cp(50));
+
+        // Only evaluations can be read back
+        assert_eq!(serde_json::from_str::<Score>("1000.0").unwrap(), Score::MAX_EVAL);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::chess_server::chess_types::ChessStatus;
 use crate::chess_server::chess_types::Color;
 use crate::engines::engine_traits::*;
+use crate::engines::quiescence::QuiescenceSearch;
 use std::cmp::max;
 use std::cmp::min;
 use std::marker::PhantomData;
@@ -8,12 +9,13 @@ use std::marker::PhantomData;
 use crate::chess_server::chess_types::ChessBoard;
 use crate::chess_server::chess_types::Move;
 
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
-const INF: OrderedFloat<f64> = OrderedFloat(1000.);
+const INF: Score = Score::INFINITE;
 
 pub struct AlphaBetaSearcher<E: Evaluator> {
     max_depth: usize,
+    quiescence: Option<QuiescenceSearch>,
     phantom: PhantomData<E>,
 }
 
@@ -25,20 +27,36 @@ impl<E: Evaluator> AlphaBetaSearcher<E> {
 
         AlphaBetaSearcher {
             max_depth,
+            quiescence: None,
             phantom: PhantomData,
         }
     }
 
+    // Leaves are resolved by a quiescence search instead of the static evaluation
+    pub fn with_quiescence(max_depth: usize, quiescence: QuiescenceSearch) -> AlphaBetaSearcher<E> {
+        AlphaBetaSearcher {
+            quiescence: Some(quiescence),
+            ..AlphaBetaSearcher::new(max_depth)
+        }
+    }
+
     fn search_impl(
         &self,
         chess_board: &ChessBoard,
         evaluator: &E,
         depth: usize,
-        alpha: OrderedFloat<f64>,
-        beta: OrderedFloat<f64>,
-    ) -> (OrderedFloat<f64>, Option<Move>) {
+        alpha: Score,
+        beta: Score,
+    ) -> (Score, Option<Move>) {
         if depth == self.max_depth {
-            (evaluator.evaluate(chess_board), None)
+            let eval = QuiescenceSearch::evaluate_leaf(
+                self.quiescence.as_ref(),
+                chess_board,
+                evaluator,
+                alpha,
+                beta,
+            );
+            (eval, None)
         } else {
             match chess_board.get_game_status() {
                 ChessStatus::Ongoing => {
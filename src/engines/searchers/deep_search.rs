@@ -5,6 +5,7 @@ use crate::chess_server::chess_types::ChessStatus;
 use crate::chess_server::chess_types::Color;
 use crate::chess_server::chess_types::Piece;
 use crate::engines::engine_traits::*;
+use crate::engines::quiescence::QuiescenceSearch;
 
 use std::cmp::max;
 use std::cmp::min;
@@ -13,17 +14,18 @@ use std::marker::PhantomData;
 use crate::chess_server::chess_types::ChessBoard;
 use crate::chess_server::chess_types::Move;
 
+use crate::engines::score::Score;
 use log::info;
-use ordered_float::OrderedFloat;
 
 use crate::engines::zobrist_hash::ZobristHashMap;
 
-const INF: OrderedFloat<f64> = OrderedFloat(1000.);
+const INF: Score = Score::INFINITE;
 
 #[derive(Clone)]
 pub struct DeepSearch<E: Evaluator> {
     max_depth: usize,
-    cache: RefCell<ZobristHashMap<(OrderedFloat<f64>, Move, u8)>>,
+    quiescence: Option<QuiescenceSearch>,
+    cache: RefCell<ZobristHashMap<(Score, Move, u8)>>,
     phantom: PhantomData<E>,
 }
 
@@ -35,12 +37,21 @@ impl<E: Evaluator> DeepSearch<E> {
 
         DeepSearch {
             max_depth,
+            quiescence: None,
             phantom: PhantomData,
             cache: RefCell::new(ZobristHashMap::new()),
         }
     }
 
-    fn get_cached(&self, chess_board: &ChessBoard) -> Option<(OrderedFloat<f64>, Move, u8)> {
+    // Leaves are resolved by a quiescence search instead of the static evaluation
+    pub fn with_quiescence(max_depth: usize, quiescence: QuiescenceSearch) -> DeepSearch<E> {
+        DeepSearch {
+            quiescence: Some(quiescence),
+            ..DeepSearch::new(max_depth)
+        }
+    }
+
+    fn get_cached(&self, chess_board: &ChessBoard) -> Option<(Score, Move, u8)> {
         if let Some(&(eval, move_, depth_from_point)) =
             self.cache.borrow().get_key_value(chess_board)
         {
@@ -54,7 +65,7 @@ impl<E: Evaluator> DeepSearch<E> {
         &self,
         chess_board: &ChessBoard,
         depth_from_point: usize,
-        eval: OrderedFloat<f64>,
+        eval: Score,
         move_: Move,
     ) {
         self.cache
@@ -67,12 +78,18 @@ impl<E: Evaluator> DeepSearch<E> {
         chess_board: &ChessBoard,
         evaluator: &E,
         depth: usize,
-        alpha: OrderedFloat<f64>,
-        beta: OrderedFloat<f64>,
+        alpha: Score,
+        beta: Score,
         max_depth: usize,
-    ) -> (OrderedFloat<f64>, Option<Move>) {
+    ) -> (Score, Option<Move>) {
         if depth == max_depth {
-            let eval = evaluator.evaluate(chess_board);
+            let eval = QuiescenceSearch::evaluate_leaf(
+                self.quiescence.as_ref(),
+                chess_board,
+                evaluator,
+                alpha,
+                beta,
+            );
             return (eval, None);
         }
 
@@ -113,11 +130,11 @@ impl<E: Evaluator> DeepSearch<E> {
                 if depth == 0 {
                     if color == Color::White {
                         allowed_moves.sort_by_key(|&move_| {
-                            -evaluator.evaluate(&chess_board.next_state(&move_))
+                            -evaluator.evaluate_white(&chess_board.next_state(&move_))
                         });
                     } else {
                         allowed_moves.sort_by_key(|&move_| {
-                            evaluator.evaluate(&chess_board.next_state(&move_))
+                            evaluator.evaluate_white(&chess_board.next_state(&move_))
                         });
                     }
                 } else {
@@ -235,7 +252,7 @@ impl<E: Evaluator> DeepSearch<E> {
         }
     }
 
-    pub fn search_ext(&self, chess_board: &ChessBoard, evaluator: &E) -> (OrderedFloat<f64>, Move) {
+    pub fn search_ext(&self, chess_board: &ChessBoard, evaluator: &E) -> (Score, Move) {
         info!("Size of cache: {}", self.cache.borrow().len());
         self.cache.borrow_mut().clear();
 
@@ -4,6 +4,7 @@ use crate::chess_server::chess_types::ChessStatus;
 use crate::chess_server::chess_types::Color;
 use crate::chess_server::chess_types::Piece;
 use crate::engines::engine_traits::*;
+use crate::engines::quiescence::QuiescenceSearch;
 
 use std::cmp::max;
 use std::cmp::min;
@@ -12,15 +13,16 @@ use std::marker::PhantomData;
 use crate::chess_server::chess_types::ChessBoard;
 use crate::chess_server::chess_types::Move;
 
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
 use crate::engines::zobrist_hash::ZobristHashMap;
 
-const INF: OrderedFloat<f64> = OrderedFloat(1000.);
+const INF: Score = Score::INFINITE;
 
 pub struct IterativeDeepening<E: Evaluator> {
     max_depth: usize,
-    cache: RefCell<ZobristHashMap<(OrderedFloat<f64>, Move, u8)>>,
+    quiescence: Option<QuiescenceSearch>,
+    cache: RefCell<ZobristHashMap<(Score, Move, u8)>>,
     phantom: PhantomData<E>,
 }
 
@@ -32,12 +34,21 @@ impl<E: Evaluator> IterativeDeepening<E> {
 
         IterativeDeepening {
             max_depth,
+            quiescence: None,
             phantom: PhantomData,
             cache: RefCell::new(ZobristHashMap::new()),
         }
     }
 
-    fn get_cached(&self, chess_board: &ChessBoard) -> Option<(OrderedFloat<f64>, Move, u8)> {
+    // Leaves are resolved by a quiescence search instead of the static evaluation
+    pub fn with_quiescence(max_depth: usize, quiescence: QuiescenceSearch) -> IterativeDeepening<E> {
+        IterativeDeepening {
+            quiescence: Some(quiescence),
+            ..IterativeDeepening::new(max_depth)
+        }
+    }
+
+    fn get_cached(&self, chess_board: &ChessBoard) -> Option<(Score, Move, u8)> {
         if let Some(&(eval, move_, depth_from_point)) =
             self.cache.borrow().get_key_value(chess_board)
         {
@@ -51,7 +62,7 @@ impl<E: Evaluator> IterativeDeepening<E> {
         &self,
         chess_board: &ChessBoard,
         depth_from_point: usize,
-        eval: OrderedFloat<f64>,
+        eval: Score,
         move_: Move,
     ) {
         self.cache
@@ -64,12 +75,19 @@ impl<E: Evaluator> IterativeDeepening<E> {
         chess_board: &ChessBoard,
         evaluator: &E,
         depth: usize,
-        alpha: OrderedFloat<f64>,
-        beta: OrderedFloat<f64>,
+        alpha: Score,
+        beta: Score,
         max_depth: usize,
-    ) -> (OrderedFloat<f64>, Option<Move>) {
+    ) -> (Score, Option<Move>) {
         if depth == max_depth {
-            return (evaluator.evaluate(chess_board), None);
+            let eval = QuiescenceSearch::evaluate_leaf(
+                self.quiescence.as_ref(),
+                chess_board,
+                evaluator,
+                alpha,
+                beta,
+            );
+            return (eval, None);
         }
 
         let mut best_move = None;
@@ -90,11 +108,11 @@ impl<E: Evaluator> IterativeDeepening<E> {
                 if depth == 0 {
                     if color == Color::White {
                         allowed_moves.sort_by_key(|&move_| {
-                            -evaluator.evaluate(&chess_board.next_state(&move_))
+                            -evaluator.evaluate_white(&chess_board.next_state(&move_))
                         });
                     } else {
                         allowed_moves.sort_by_key(|&move_| {
-                            evaluator.evaluate(&chess_board.next_state(&move_))
+                            evaluator.evaluate_white(&chess_board.next_state(&move_))
                         });
                     }
                 } else {
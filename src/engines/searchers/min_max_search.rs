@@ -6,9 +6,9 @@ use std::marker::PhantomData;
 use crate::chess_server::chess_types::ChessBoard;
 use crate::chess_server::chess_types::Move;
 
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
-const INF: OrderedFloat<f64> = OrderedFloat(1000.);
+const INF: Score = Score::INFINITE;
 pub struct MinMaxSearcher<E: Evaluator> {
     max_depth: usize,
     phantom: PhantomData<E>,
@@ -31,9 +31,9 @@ impl<E: Evaluator> MinMaxSearcher<E> {
         chess_board: &ChessBoard,
         evaluator: &E,
         depth: usize,
-    ) -> (OrderedFloat<f64>, Option<Move>) {
+    ) -> (Score, Option<Move>) {
         if depth == self.max_depth {
-            (evaluator.evaluate(chess_board), None)
+            (evaluator.evaluate_white(chess_board), None)
         } else {
             match chess_board.get_game_status() {
                 ChessStatus::Ongoing => {
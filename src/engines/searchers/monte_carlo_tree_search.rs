@@ -10,6 +10,7 @@ use crate::chess_server::chess_types::chess_board::MoveContainer;
 use crate::chess_server::chess_types::chess_board::MOVE_CONTAINER_SIZE;
 
 use crate::engines::engine_traits::*;
+use crate::engines::score::Score;
 
 use std::cmp::max;
 
@@ -73,6 +74,8 @@ pub struct MonteCarloTreeSearch<E: Evaluator, P: Policy> {
     max_depth: usize,
     max_iter: usize,
     c_puct: f64,
+    // Value of a won game in pawns
+    win: f64,
     cache: RefCell<ZobristHashMap<Rc<RefCell<MctsNode>>>>,
     phantom: PhantomData<E>,
 }
@@ -83,6 +86,17 @@ impl<E: Evaluator, P: Policy> MonteCarloTreeSearch<E, P> {
         max_depth: usize,
         max_iter: usize,
         c_puct: f64,
+    ) -> MonteCarloTreeSearch<E, P> {
+        MonteCarloTreeSearch::with_win(policy, max_depth, max_iter, c_puct, Score::cp(100))
+    }
+
+    // Evaluations are divided by `win`, which should be the most they can be
+    pub fn with_win(
+        policy: P,
+        max_depth: usize,
+        max_iter: usize,
+        c_puct: f64,
+        win: Score,
     ) -> MonteCarloTreeSearch<E, P> {
         if max_depth == 0 {
             panic!("Max depth must be at least 1.")
@@ -93,11 +107,18 @@ impl<E: Evaluator, P: Policy> MonteCarloTreeSearch<E, P> {
             max_depth,
             max_iter,
             c_puct,
+            win: win.to_pawns(),
             cache: RefCell::new(ZobristHashMap::new()),
             phantom: PhantomData,
         }
     }
 
+    // Values are from the point of view of white, between the results of -1
+    // and 1 as long as evaluations stay within a won game
+    fn white_eval(&self, evaluator: &E, chess_board: &ChessBoard) -> OrderedFloat<f64> {
+        OrderedFloat(evaluator.evaluate_white(chess_board).to_pawns() / self.win)
+    }
+
     fn get_mut_node(&self, chess_board: &ChessBoard) -> Option<Rc<RefCell<MctsNode>>> {
         self.cache.borrow_mut().get_key_value(chess_board).cloned()
     }
@@ -119,7 +140,7 @@ impl<E: Evaluator, P: Policy> MonteCarloTreeSearch<E, P> {
         };
 
         if depth == self.max_depth {
-            return evaluator.evaluate(chess_board);
+            return self.white_eval(evaluator, chess_board);
         }
 
         let (node_ref, was_visited) = {
@@ -129,7 +150,7 @@ impl<E: Evaluator, P: Policy> MonteCarloTreeSearch<E, P> {
                 let allowed_moves = chess_board.get_allowed_moves(color);
                 let priors = self.policy.get_priors(chess_board, &allowed_moves);
 
-                let value = evaluator.evaluate(chess_board);
+                let value = self.white_eval(evaluator, chess_board);
 
                 let node = Rc::new(RefCell::new(MctsNode::new(allowed_moves, priors, value.0)));
 
@@ -146,7 +167,7 @@ impl<E: Evaluator, P: Policy> MonteCarloTreeSearch<E, P> {
         match chess_board.get_game_status_from_precomputed(allowed_moves) {
             ChessStatus::Ongoing => {
                 if !was_visited {
-                    return evaluator.evaluate(chess_board);
+                    return self.white_eval(evaluator, chess_board);
                 }
                 // Was visited before
 
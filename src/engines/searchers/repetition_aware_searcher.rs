@@ -1,6 +1,7 @@
 use std::cell::RefCell;
 
 use crate::chess_server::chess_types::ChessStatus;
+use crate::chess_server::game::GameContext;
 use crate::chess_server::chess_types::Color;
 use crate::chess_server::chess_types::Piece;
 use crate::engines::engine_traits::*;
@@ -12,15 +13,15 @@ use std::marker::PhantomData;
 use crate::chess_server::chess_types::ChessBoard;
 use crate::chess_server::chess_types::Move;
 
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
 use crate::engines::zobrist_hash::ZobristHashMap;
 
-const INF: OrderedFloat<f64> = OrderedFloat(1000.);
+const INF: Score = Score::INFINITE;
 
 pub struct RepetitionAwareSearcher<E: Evaluator> {
     max_depth: usize,
-    cache: RefCell<ZobristHashMap<(OrderedFloat<f64>, Move, u8)>>,
+    cache: RefCell<ZobristHashMap<(Score, Move, u8)>>,
     seen_positions: RefCell<Vec<ChessBoard>>,
     phantom: PhantomData<E>,
 }
@@ -39,7 +40,7 @@ impl<E: Evaluator> RepetitionAwareSearcher<E> {
         }
     }
 
-    fn get_cached(&self, chess_board: &ChessBoard) -> Option<(OrderedFloat<f64>, Move, u8)> {
+    fn get_cached(&self, chess_board: &ChessBoard) -> Option<(Score, Move, u8)> {
         if let Some(&(eval, move_, depth_from_point)) =
             self.cache.borrow().get_key_value(chess_board)
         {
@@ -53,7 +54,7 @@ impl<E: Evaluator> RepetitionAwareSearcher<E> {
         &self,
         chess_board: &ChessBoard,
         depth_from_point: usize,
-        eval: OrderedFloat<f64>,
+        eval: Score,
         move_: Move,
     ) {
         self.cache
@@ -66,17 +67,17 @@ impl<E: Evaluator> RepetitionAwareSearcher<E> {
         chess_board: &ChessBoard,
         evaluator: &E,
         depth: usize,
-        alpha: OrderedFloat<f64>,
-        beta: OrderedFloat<f64>,
+        alpha: Score,
+        beta: Score,
         max_depth: usize,
-    ) -> (OrderedFloat<f64>, Option<Move>) {
+    ) -> (Score, Option<Move>) {
         let is_position_repeated = self.seen_positions.borrow().contains(chess_board);
 
         let return_value = |eval| {
             if is_position_repeated {
                 match chess_board.get_turn_color() {
-                    Color::White => min(OrderedFloat(0.), eval),
-                    Color::Black => max(OrderedFloat(0.), eval),
+                    Color::White => min(Score::DRAW, eval),
+                    Color::Black => max(Score::DRAW, eval),
                 }
             } else {
                 eval
@@ -84,7 +85,7 @@ impl<E: Evaluator> RepetitionAwareSearcher<E> {
         };
 
         if depth == max_depth {
-            let eval = evaluator.evaluate(chess_board);
+            let eval = evaluator.evaluate_white(chess_board);
             return (return_value(eval), None);
         }
 
@@ -111,11 +112,11 @@ impl<E: Evaluator> RepetitionAwareSearcher<E> {
                 if depth == 0 {
                     if color == Color::White {
                         allowed_moves.sort_by_key(|&move_| {
-                            -evaluator.evaluate(&chess_board.next_state(&move_))
+                            -evaluator.evaluate_white(&chess_board.next_state(&move_))
                         });
                     } else {
                         allowed_moves.sort_by_key(|&move_| {
-                            evaluator.evaluate(&chess_board.next_state(&move_))
+                            evaluator.evaluate_white(&chess_board.next_state(&move_))
                         });
                     }
                 } else {
@@ -294,6 +295,13 @@ impl<E: Evaluator> RepetitionAwareSearcher<E> {
 }
 
 impl<E: Evaluator> Searcher<E> for RepetitionAwareSearcher<E> {
+    // The positions of the game replace the ones seen by previous searches
+    fn set_game_context(&self, context: &GameContext) {
+        if !context.history.is_empty() {
+            *self.seen_positions.borrow_mut() = context.history.to_vec();
+        }
+    }
+
     fn search(&self, chess_board: &ChessBoard, evaluator: &E) -> Move {
         for max_depth in 1..self.max_depth {
             self.search_impl(chess_board, evaluator, 0, -INF, INF, max_depth);
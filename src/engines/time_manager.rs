@@ -0,0 +1,257 @@
+use crate::chess_server::chess_types::{Color, Move};
+use crate::engines::score::Score;
+
+use std::time::Duration;
+use std::time::Instant;
+
+// Time kept aside for the communication and the move generation
+const DEFAULT_MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+// Number of moves the remaining time has to last when the time control
+// doesn't say it
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+// The hard limit is a multiple of the soft one, but never more than a
+// fraction of the clock so that the engine can't flag
+const HARD_LIMIT_FACTOR: f64 = 4.;
+const MAX_CLOCK_USAGE: f64 = 0.8;
+
+// Share of the increment that is spent on every move
+const INCREMENT_USAGE: f64 = 0.75;
+
+// Stretching of the soft limit
+const MAX_INSTABILITY_FACTOR: f64 = 2.;
+// Score drops are measured in pawns
+const SCORE_DROP_SCALE: f64 = 1.;
+const MAX_SCORE_DROP_FACTOR: f64 = 2.;
+
+/// Remaining time of both players, as in the UCI `go` command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClockState {
+    pub wtime: Duration,
+    pub btime: Duration,
+    pub winc: Duration,
+    pub binc: Duration,
+    pub movestogo: Option<u32>,
+}
+
+impl ClockState {
+    pub fn get_time(&self, color: Color) -> Duration {
+        match color {
+            Color::White => self.wtime,
+            Color::Black => self.btime,
+        }
+    }
+
+    pub fn get_increment(&self, color: Color) -> Duration {
+        match color {
+            Color::White => self.winc,
+            Color::Black => self.binc,
+        }
+    }
+}
+
+/// Time allowed for one search.
+///
+/// The searcher shouldn't start a new iteration once the soft limit is
+/// reached, and has to stop right away at the hard limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeLimits {
+    pub soft: Duration,
+    pub hard: Duration,
+}
+
+impl TimeLimits {
+    pub fn fixed(avail_time: Duration) -> TimeLimits {
+        TimeLimits {
+            soft: avail_time,
+            hard: avail_time,
+        }
+    }
+}
+
+/// Splits the remaining clock of a player into limits for the next search.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeManager {
+    move_overhead: Duration,
+}
+
+impl TimeManager {
+    pub fn new() -> TimeManager {
+        TimeManager::with_move_overhead(DEFAULT_MOVE_OVERHEAD)
+    }
+
+    pub fn with_move_overhead(move_overhead: Duration) -> TimeManager {
+        TimeManager { move_overhead }
+    }
+
+    pub fn limits(&self, clock: &ClockState, color: Color) -> TimeLimits {
+        let available = clock.get_time(color).saturating_sub(self.move_overhead);
+        let increment = clock.get_increment(color);
+        let moves_to_go = clock.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+
+        let base = available / moves_to_go + increment.mul_f64(INCREMENT_USAGE);
+
+        let hard = base
+            .mul_f64(HARD_LIMIT_FACTOR)
+            .min(available.mul_f64(MAX_CLOCK_USAGE));
+        let soft = base.min(hard);
+
+        TimeLimits { soft, hard }
+    }
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        TimeManager::new()
+    }
+}
+
+/// Keeps track of the time of a running search.
+///
+/// The soft limit is stretched when the best move keeps changing between
+/// iterations or when the score drops, but never beyond the hard limit.
+#[derive(Clone, Copy, Debug)]
+pub struct SearchTimer {
+    start_time: Instant,
+    limits: TimeLimits,
+    best_move: Option<Move>,
+    best_move_changes: f64,
+    previous_score: Option<Score>,
+    score_drop: f64,
+}
+
+impl SearchTimer {
+    pub fn new(limits: TimeLimits) -> SearchTimer {
+        SearchTimer {
+            start_time: Instant::now(),
+            limits,
+            best_move: None,
+            best_move_changes: 0.,
+            previous_score: None,
+            score_drop: 0.,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Instant::now() - self.start_time
+    }
+
+    pub fn soft_limit(&self) -> Duration {
+        let instability = (1. + self.best_move_changes).min(MAX_INSTABILITY_FACTOR);
+        let score_drop = (1. + self.score_drop / SCORE_DROP_SCALE).min(MAX_SCORE_DROP_FACTOR);
+
+        self.limits
+            .soft
+            .mul_f64(instability * score_drop)
+            .min(self.limits.hard)
+    }
+
+    pub fn hard_limit_reached(&self) -> bool {
+        self.elapsed() > self.limits.hard
+    }
+
+    pub fn can_start_iteration(&self) -> bool {
+        self.elapsed() < self.soft_limit()
+    }
+
+    /// Called after every completed iteration with its best move and score.
+    /// Older best move changes count for less.
+    pub fn record_iteration(&mut self, best_move: Move, score: Score) {
+        let changed = self.best_move.is_some_and(|mv| mv != best_move);
+        self.best_move_changes = self.best_move_changes / 2. + if changed { 1. } else { 0. };
+        self.best_move = Some(best_move);
+
+        self.score_drop = match self.previous_score {
+            Some(previous_score) => (previous_score - score).to_pawns().max(0.),
+            None => 0.,
+        };
+        self.previous_score = Some(score);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_server::chess_types::ChessBoard;
+    use crate::chess_server::io::utils::parse_uci_move;
+
+    fn clock(time: Duration, increment: Duration, movestogo: Option<u32>) -> ClockState {
+        ClockState {
+            wtime: time,
+            btime: time,
+            winc: increment,
+            binc: increment,
+            movestogo,
+        }
+    }
+
+    #[test]
+    fn never_uses_more_than_a_fraction_of_the_clock() {
+        let time_manager = TimeManager::new();
+        let times = [0, 1, 30, 50, 51, 200, 60_000, 3_600_000, 1_000_000_000];
+
+        for time in times.map(Duration::from_millis) {
+            let available = time.saturating_sub(DEFAULT_MOVE_OVERHEAD);
+
+            for movestogo in [None, Some(1), Some(2), Some(40)] {
+                for increment in [Duration::ZERO, Duration::from_secs(2)] {
+                    let clock = clock(time, increment, movestogo);
+                    let limits = time_manager.limits(&clock, Color::White);
+
+                    assert!(limits.hard <= available.mul_f64(MAX_CLOCK_USAGE));
+                    assert!(limits.soft <= limits.hard);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spreads_the_clock_over_the_moves_to_go() {
+        let time_manager = TimeManager::with_move_overhead(Duration::ZERO);
+        let time = Duration::from_secs(400);
+
+        let limits = time_manager.limits(&clock(time, Duration::ZERO, Some(40)), Color::Black);
+        assert_eq!(limits.soft, Duration::from_secs(10));
+        let limits = time_manager.limits(&clock(time, Duration::ZERO, Some(10)), Color::Black);
+        assert_eq!(limits.soft, Duration::from_secs(40));
+
+        // Most of the increment is spent on top
+        let increment = Duration::from_secs(4);
+        let limits = time_manager.limits(&clock(time, increment, Some(40)), Color::Black);
+        assert_eq!(limits.soft, Duration::from_secs(13));
+        assert_eq!(limits.hard, Duration::from_secs(52));
+    }
+
+    #[test]
+    fn soft_limit_grows_with_instability_up_to_the_hard_limit() {
+        let chess_board = ChessBoard::starting_position();
+        let e4 = parse_uci_move(&chess_board, "e2e4").unwrap();
+        let d4 = parse_uci_move(&chess_board, "d2d4").unwrap();
+        let limits = TimeLimits {
+            soft: Duration::from_secs(1),
+            hard: Duration::from_secs(10),
+        };
+
+        let mut timer = SearchTimer::new(limits);
+        timer.record_iteration(e4, Score::cp(0));
+        timer.record_iteration(e4, Score::cp(0));
+        assert_eq!(timer.soft_limit(), Duration::from_secs(1));
+        timer.record_iteration(d4, Score::cp(0));
+        assert_eq!(timer.soft_limit(), Duration::from_secs(2));
+
+        let mut timer = SearchTimer::new(limits);
+        timer.record_iteration(e4, Score::cp(100));
+        timer.record_iteration(e4, Score::cp(50));
+        assert_eq!(timer.soft_limit(), Duration::from_millis(1500));
+
+        // Both at once would give four seconds
+        let mut timer = SearchTimer::new(TimeLimits {
+            hard: Duration::from_secs(3),
+            ..limits
+        });
+        timer.record_iteration(e4, Score::cp(100));
+        timer.record_iteration(d4, Score::cp(0));
+        assert_eq!(timer.soft_limit(), Duration::from_secs(3));
+    }
+}
@@ -10,10 +10,11 @@ use crate::chess_server::chess_types::ChessBoard;
 use crate::chess_server::chess_types::Move;
 
 use log::info;
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
-const BIG_INF: OrderedFloat<f64> = OrderedFloat(1001.);
-const SMALL_INF: OrderedFloat<f64> = OrderedFloat(999.);
+const BIG_INF: Score = Score::INFINITE;
+// Just below a win
+const SMALL_INF: Score = Score::mate_in(1);
 
 pub struct AlphaBeta<E: Evaluator> {
     max_depth: usize,
@@ -37,23 +38,22 @@ impl<E: Evaluator> AlphaBeta<E> {
         chess_board: &ChessBoard,
         evaluator: &E,
         depth: usize,
-        alpha: OrderedFloat<f64>,
-        beta: OrderedFloat<f64>,
+        alpha: Score,
+        beta: Score,
         start_time: Instant,
         avail_time: Duration
-    ) -> OrderedFloat<f64> {
+    ) -> Score {
         let color = chess_board.get_turn_color();
-        let sign = OrderedFloat(color.as_sign());
         if depth >= self.max_depth {
 
-            sign * evaluator.evaluate(chess_board)
+            evaluator.evaluate(chess_board)
 
         } else {
             match chess_board.get_game_status() {
                 ChessStatus::Ongoing => {
                     let allowed_moves = chess_board.get_allowed_moves(color);
 
-                    let mut value: OrderedFloat<f64> = -BIG_INF;
+                    let mut value: Score = -BIG_INF;
                     
                     let mut alpha = alpha;
 
@@ -84,8 +84,8 @@ impl<E: Evaluator> AlphaBeta<E> {
                     
                     value
                 }
-                ChessStatus::BlackWon => sign * EVAL_BLACK_WON,
-                ChessStatus::WhiteWon => sign * EVAL_WHITE_WON,
+                ChessStatus::BlackWon => EVAL_BLACK_WON.relative(color),
+                ChessStatus::WhiteWon => EVAL_WHITE_WON.relative(color),
                 ChessStatus::Draw => EVAL_DRAW,
             }
         }
@@ -102,7 +102,6 @@ impl<E: Evaluator> TimedSearcher<E> for AlphaBeta<E> {
         );
 
         let color = chess_board.get_turn_color();
-        let sign = OrderedFloat(color.as_sign());
 
         let allowed_moves = chess_board.get_allowed_moves(color);
 
@@ -140,7 +139,7 @@ impl<E: Evaluator> TimedSearcher<E> for AlphaBeta<E> {
             count += 1;
         }
 
-        info!("Eval: {}, Depth: {}. Analyzed {} moves out of {}", sign * value, self.max_depth, count, total_count);
+        info!("Eval: {}, Depth: {}. Analyzed {} moves out of {}", value.relative(color), self.max_depth, count, total_count);
 
         best_move
     }
@@ -11,28 +11,29 @@ use crate::chess_server::chess_types::ChessBoard;
 use crate::chess_server::chess_types::Move;
 
 use log::info;
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
 use std::cell::RefCell;
 use crate::engines::zobrist_hash::ZobristHashMap;
 
 #[derive(Clone, Copy)]
 enum NodeType {
-    PVNode(OrderedFloat<f64>),
-    AllNode(OrderedFloat<f64>),
-    CutNode(OrderedFloat<f64>)
+    PVNode(Score),
+    AllNode(Score),
+    CutNode(Score)
 }
 
 #[derive(Clone, Copy)]
 enum EvalType {
     WhiteMate(usize),
     BlackMate(usize),
-    ExactEval(OrderedFloat<f64>)
+    ExactEval(Score)
 }
 
 
-const BIG_INF: OrderedFloat<f64> = OrderedFloat(1001.);
-const SMALL_INF: OrderedFloat<f64> = OrderedFloat(999.);
+const BIG_INF: Score = Score::INFINITE;
+// Just below a win
+const SMALL_INF: Score = Score::mate_in(1);
 
 pub struct ClunkySearcher<E: Evaluator> {
     phantom: PhantomData<E>,
@@ -74,15 +75,14 @@ impl<E: Evaluator> ClunkySearcher<E> {
         chess_board: &ChessBoard,
         evaluator: &E,
         depth: usize,
-        alpha: OrderedFloat<f64>,
-        beta: OrderedFloat<f64>,
+        alpha: Score,
+        beta: Score,
         start_time: Instant,
         avail_time: Duration,
     ) -> NodeType {
         let color = chess_board.get_turn_color();
-        let sign = OrderedFloat(color.as_sign());
         if depth == 0 {
-            NodeType::PVNode(sign * evaluator.evaluate(chess_board))
+            NodeType::PVNode(evaluator.evaluate(chess_board))
         } else {
             match chess_board.get_game_status() {
                 ChessStatus::Ongoing => {
@@ -212,8 +212,8 @@ impl<E: Evaluator> ClunkySearcher<E> {
                     self.insert_cache(node_type, best_move, depth, chess_board);
                     node_type
                 }
-                ChessStatus::BlackWon => NodeType::PVNode(sign * EVAL_BLACK_WON),
-                ChessStatus::WhiteWon => NodeType::PVNode(sign * EVAL_WHITE_WON),
+                ChessStatus::BlackWon => NodeType::PVNode(EVAL_BLACK_WON.relative(color)),
+                ChessStatus::WhiteWon => NodeType::PVNode(EVAL_WHITE_WON.relative(color)),
                 ChessStatus::Draw => NodeType::PVNode(EVAL_DRAW),
             }
         }
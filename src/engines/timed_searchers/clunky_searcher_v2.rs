@@ -2,6 +2,7 @@ use crate::chess_server::chess_types::ChessStatus;
 use crate::chess_server::chess_types::Color;
 use crate::engines::engine_traits::*;
 use crate::engines::evaluators::cache_evaluator;
+use crate::engines::score::Score;
 use std::cmp::max;
 use std::marker::PhantomData;
 use std::time::Duration;
@@ -11,104 +12,15 @@ use crate::chess_server::chess_types::ChessBoard;
 use crate::chess_server::chess_types::Move;
 
 use log::info;
-use ordered_float::OrderedFloat;
 
 use std::cell::RefCell;
 use crate::engines::zobrist_hash::ZobristHashMap;
 
-use std::ops::Neg;
-use std::cmp::{PartialOrd, Ord, Ordering};
-
-use std::fmt::Display;
-
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-enum EvalType {
-    MaximizerMate(usize),
-    MinimizerMate(usize),
-    ExactEval(OrderedFloat<f64>)
-}
-
-impl EvalType {
-    fn forward(self) -> Self {
-        match self {
-            Self::MaximizerMate(depth) => {
-                Self::MinimizerMate(depth + 1)    
-            },
-            Self::MinimizerMate(depth) => {
-                Self::MaximizerMate(depth + 1)
-            },
-            Self::ExactEval(score) => {
-                Self::ExactEval(-score)
-            }
-        }
-    }
-}
-
-impl Neg for EvalType {
-    type Output = Self;
-    fn neg(self) -> Self {
-        match self {
-            Self::MaximizerMate(depth) => {
-                Self::MinimizerMate(depth)    
-            },
-            Self::MinimizerMate(depth) => {
-                Self::MaximizerMate(depth)
-            },
-            Self::ExactEval(score) => {
-                Self::ExactEval(-score)
-            }
-        }
-    }
-}
-
-impl PartialOrd for EvalType {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match *self {
-            Self::MaximizerMate(depth) => {
-                match *other {
-                    Self::MaximizerMate(other_depth) => {
-                        other_depth.partial_cmp(&depth) 
-                    },
-                    _ => Some(Ordering::Greater)
-                }
-            },
-            Self::MinimizerMate(depth) => {
-                match *other {
-                    Self::MinimizerMate(other_depth) => {
-                        depth.partial_cmp(&other_depth)
-                    },
-                    _ => Some(Ordering::Less)
-                }
-            },
-            Self::ExactEval(score) => {
-                match *other {
-                    Self::MaximizerMate(_) => Some(Ordering::Less),
-                    Self::MinimizerMate(_) => Some(Ordering::Greater),
-                    Self::ExactEval(other_score) => score.partial_cmp(&other_score) 
-                }
-            }
-        }
-    }
-}
-
-impl Ord for EvalType {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
-    }
-}
-
-impl Display for EvalType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
-    }
-}
-
-
 #[derive(Clone, Copy)]
 enum NodeType {
-    PVNode(EvalType),
-    AllNode(EvalType),
-    CutNode(EvalType)
+    PVNode(Score),
+    AllNode(Score),
+    CutNode(Score)
 }
 
 pub struct ClunkySearcherV2<E: Evaluator> {
@@ -151,15 +63,14 @@ impl<E: Evaluator> ClunkySearcherV2<E> {
         chess_board: &ChessBoard,
         evaluator: &E,
         depth: usize,
-        alpha: EvalType,
-        beta: EvalType,
+        alpha: Score,
+        beta: Score,
         start_time: Instant,
         avail_time: Duration,
     ) -> NodeType {
         let color = chess_board.get_turn_color();
         if depth == 0 {
-            let sign = OrderedFloat(color.as_sign());
-            NodeType::PVNode(EvalType::ExactEval(sign * evaluator.evaluate(chess_board)))
+            NodeType::PVNode(evaluator.evaluate(chess_board))
         } else {
             match chess_board.get_game_status() {
                 ChessStatus::Ongoing => {
@@ -297,17 +208,17 @@ impl<E: Evaluator> ClunkySearcherV2<E> {
                 }
                 ChessStatus::BlackWon => {
                     match color {
-                        Color::White => NodeType::PVNode(EvalType::MinimizerMate(0)),
-                        Color::Black => NodeType::PVNode(EvalType::MaximizerMate(0)),
+                        Color::White => NodeType::PVNode(Score::mated_in(0)),
+                        Color::Black => NodeType::PVNode(Score::mate_in(0)),
                     }
                 },
                 ChessStatus::WhiteWon => {
                     match color {
-                        Color::White => NodeType::PVNode(EvalType::MaximizerMate(0)),
-                        Color::Black => NodeType::PVNode(EvalType::MinimizerMate(0)),
+                        Color::White => NodeType::PVNode(Score::mate_in(0)),
+                        Color::Black => NodeType::PVNode(Score::mated_in(0)),
                     }
                 },
-                ChessStatus::Draw => NodeType::PVNode(EvalType::ExactEval(OrderedFloat(0.))),
+                ChessStatus::Draw => NodeType::PVNode(Score::DRAW),
             }
         }
     }
@@ -329,7 +240,7 @@ impl<E: Evaluator> TimedSearcher<E> for ClunkySearcherV2<E> {
         let color = chess_board.get_turn_color();
         let allowed_moves = chess_board.get_allowed_moves(color);
 
-        let mut value = EvalType::MinimizerMate(0);
+        let mut value = Score::mated_in(0);
 
         // Assign arbitrary move to assure that output won't be None
         let mut best_move = Some(allowed_moves[0]);
@@ -340,7 +251,7 @@ impl<E: Evaluator> TimedSearcher<E> for ClunkySearcherV2<E> {
                 break;
             }
 
-            let mut local_value = EvalType::MinimizerMate(0);
+            let mut local_value = Score::mated_in(0);
             let mut local_best_move = None;
 
             let mut time_cutoff = false;
@@ -369,7 +280,7 @@ impl<E: Evaluator> TimedSearcher<E> for ClunkySearcherV2<E> {
                         &chess_board.next_state(&mv),
                         evaluator,
                         max_depth,
-                        EvalType::MinimizerMate(0),
+                        Score::mated_in(0),
                         -local_value,
                         start_time,
                         avail_time,
@@ -407,7 +318,7 @@ impl<E: Evaluator> TimedSearcher<E> for ClunkySearcherV2<E> {
                     &chess_board.next_state(&mv),
                     evaluator,
                     max_depth,
-                    EvalType::MinimizerMate(0),
+                    Score::mated_in(0),
                     -local_value,
                     start_time,
                     avail_time,
@@ -1,13 +1,13 @@
 use crate::chess_server::chess_types::ChessStatus;
 use crate::chess_server::chess_types::Color;
-use crate::chess_server::chess_types::Piece;
 use crate::chess_server::chess_types::chess_board;
-use crate::chess_server::chess_types::chess_move::BitMove;
 use crate::chess_server::chess_types::color_piece;
 use crate::engines::engine_traits::*;
+use crate::engines::move_ordering::{is_tactical, MoveOrderer};
+use crate::engines::quiescence::{QuiescenceConfig, QuiescenceSearch};
+use crate::engines::score::Score;
 
-use crate::engines::evaluators::CaptureEvaluator;
-use crate::engines::evaluators::{TrivialEvaluator, CacheEvaluator};
+use crate::engines::evaluators::CacheEvaluator;
 
 use std::cmp::max;
 use std::marker::PhantomData;
@@ -18,152 +18,56 @@ use crate::chess_server::chess_types::ChessBoard;
 use crate::chess_server::chess_types::Move;
 
 use log::info;
-use ordered_float::OrderedFloat;
 use smallvec::SmallVec;
 
 use std::cell::RefCell;
 use crate::engines::zobrist_hash::ZobristHashMap;
 
-use std::ops::Neg;
-use std::cmp::{PartialOrd, Ord, Ordering};
-
-use std::fmt::Display;
-
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-enum EvalType {
-    MaximizerMate(i32),
-    MinimizerMate(i32),
-    ExactEval(OrderedFloat<f64>)
+#[derive(Clone, Copy)]
+enum NodeType {
+    PVNode(Score),
+    AllNode(Score),
+    CutNode(Score)
 }
 
-impl EvalType {
-    fn forward(self) -> Self {
-        match self {
-            Self::MaximizerMate(depth) => {
-                Self::MinimizerMate(depth + 1)    
-            },
-            Self::MinimizerMate(depth) => {
-                Self::MaximizerMate(depth + 1)
-            },
-            Self::ExactEval(score) => {
-                Self::ExactEval(-score)
-            }
-        }
-    }
-
-    fn backward(self) -> Self {
-        match self {
-            Self::MaximizerMate(depth) => {
-                Self::MinimizerMate(depth - 1)    
-            },
-            Self::MinimizerMate(depth) => {
-                Self::MaximizerMate(depth - 1)
-            },
-            Self::ExactEval(score) => {
-                Self::ExactEval(-score)
-            }
-        }
-    }
+// Information passed from a node to its children
+#[derive(Clone, Copy)]
+struct Node {
+    ply: usize,
+    previous_move: Option<Move>,
 }
 
-impl Neg for EvalType {
-    type Output = Self;
-    fn neg(self) -> Self {
-        match self {
-            Self::MaximizerMate(depth) => {
-                Self::MinimizerMate(depth)    
-            },
-            Self::MinimizerMate(depth) => {
-                Self::MaximizerMate(depth)
-            },
-            Self::ExactEval(score) => {
-                Self::ExactEval(-score)
-            }
+impl Node {
+    fn root() -> Node {
+        Node {
+            ply: 0,
+            previous_move: None,
         }
     }
-}
 
-impl PartialOrd for EvalType {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match *self {
-            Self::MaximizerMate(depth) => {
-                match *other {
-                    Self::MaximizerMate(other_depth) => {
-                        other_depth.partial_cmp(&depth) 
-                    },
-                    _ => Some(Ordering::Greater)
-                }
-            },
-            Self::MinimizerMate(depth) => {
-                match *other {
-                    Self::MinimizerMate(other_depth) => {
-                        depth.partial_cmp(&other_depth)
-                    },
-                    _ => Some(Ordering::Less)
-                }
-            },
-            Self::ExactEval(score) => {
-                match *other {
-                    Self::MaximizerMate(_) => Some(Ordering::Less),
-                    Self::MinimizerMate(_) => Some(Ordering::Greater),
-                    Self::ExactEval(other_score) => score.partial_cmp(&other_score) 
-                }
-            }
+    fn child(&self, mv: Move) -> Node {
+        Node {
+            ply: self.ply + 1,
+            previous_move: Some(mv),
         }
     }
 }
 
-impl Ord for EvalType {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
-    }
-}
-
-impl Display for EvalType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
-    }
-}
-
-
-#[derive(Clone, Copy)]
-enum NodeType {
-    PVNode(EvalType),
-    AllNode(EvalType),
-    CutNode(EvalType)
-}
-
 pub struct ClunkySearcherV4<E: Evaluator> {
     phantom: PhantomData<E>,
     cache: RefCell<ZobristHashMap<(NodeType, Move, u8, ChessBoard)>>,
-    quiet_eval: CaptureEvaluator<TrivialEvaluator>
+    move_orderer: RefCell<MoveOrderer>,
+    quiescence: QuiescenceSearch,
 }
 
 
-fn sort_moves(moves: &mut SmallVec<[BitMove; 64]>, chess_board: &ChessBoard) {
-    moves.sort_by_key(|mv| { 
-        match chess_board.get_square_content(&mv.get_next_square()) {
-            None => 0,
-            Some(color_piece) => {
-                match color_piece.get_piece() {
-                    Piece::Pawn => -1,
-                    Piece::Knight => -2,
-                    Piece::Bishop => -3,
-                    Piece::Rook => -4,
-                    Piece::Queen => -5,
-                    _ => 0,
-                }
-            }
-        }
-    })
-}
-
 impl<E: Evaluator> ClunkySearcherV4<E> {
     pub fn new() -> ClunkySearcherV4<E> {
         ClunkySearcherV4 {
             phantom: PhantomData,
             cache: RefCell::new(ZobristHashMap::new()),
-            quiet_eval: CaptureEvaluator::new(TrivialEvaluator::new())
+            move_orderer: RefCell::new(MoveOrderer::new()),
+            quiescence: QuiescenceSearch::new(QuiescenceConfig::default()),
         }
     }
 
@@ -189,109 +93,21 @@ impl<E: Evaluator> ClunkySearcherV4<E> {
             .insert(chess_board, (node_type, mv, depth_from_point as u8, *chess_board));
     }
 
-    fn is_quiet(&self, chess_board: &ChessBoard) -> bool {
-        self.quiet_eval.evaluate(chess_board) == OrderedFloat(0.)
-    }
-
     fn quiescence_search(
         &self,
         chess_board: &ChessBoard,
         evaluator: &E,
-        depth: usize,
-        alpha: EvalType,
-        beta: EvalType,
-        start_time: Instant,
-        avail_time: Duration,
+        alpha: Score,
+        beta: Score,
     ) -> NodeType {
-        
-        let color = chess_board.get_turn_color();
+        let score = self.quiescence.search(chess_board, evaluator, 0, alpha, beta);
 
-        if depth == 0 || self.is_quiet(chess_board) {
-            let sign = OrderedFloat(color.as_sign());
-            return NodeType::PVNode(EvalType::ExactEval(sign * evaluator.evaluate(chess_board))) 
-        }
-
-        let allowed_moves = chess_board.get_allowed_moves(color);
-
-        // Only keep captures
-        let mut captures: SmallVec<[BitMove; 64]> = allowed_moves.iter().filter(
-            |&&mv| { chess_board.get_square_content(&mv.get_next_square()).is_some() }
-        ).map(|&mv| { mv }).collect();
-
-        sort_moves(&mut captures, chess_board);
-
-        if captures.is_empty() {
-            let sign = OrderedFloat(color.as_sign());
-            NodeType::PVNode(EvalType::ExactEval(sign * evaluator.evaluate(chess_board)))
+        if score >= beta {
+            NodeType::CutNode(beta)
+        } else if score <= alpha {
+            NodeType::AllNode(alpha)
         } else {
-            match chess_board.get_game_status_from_precomputed(&allowed_moves) {
-                ChessStatus::Ongoing => {
-                    let imut_alpha = alpha;
-                    let mut alpha = alpha;
-
-                    for mv in captures {
-
-                        let eval_search = self.quiescence_search(
-                            &chess_board.next_state(&mv),
-                            evaluator,
-                            depth - 1,
-                            -beta,
-                            -alpha,
-                            start_time,
-                            avail_time,
-                        );
-
-                        match eval_search {
-                            NodeType::AllNode(cached_alpha) => {
-                                // cached_alpha is an upper bound of the next node value
-                                // So -cached_alpha is a lower bound of the score in this position
-                                let eval_move = cached_alpha.forward();
-
-                                if eval_move > alpha {
-                                    alpha = eval_move;
-                                }
-                            },
-                            NodeType::PVNode(score) => {
-                                // Exact score. Can do the same thing
-                                let eval_move = score.forward();
-
-                                if eval_move > alpha {
-                                    alpha = eval_move;
-                                }
-                            }
-                            NodeType::CutNode(_) => {
-                                // cached_beta is a lower bound of the next node value
-                            }
-                        }
-
-                        if alpha >= beta {
-                            // Beta cutoff
-                            return NodeType::CutNode(alpha)
-                        }
-                    }
-
-                    let node_type = if alpha > imut_alpha {
-                        NodeType::PVNode(alpha)
-                    } else {
-                        NodeType::AllNode(alpha)
-                    };
-
-                    node_type
-                }
-                ChessStatus::BlackWon => {
-                    match color {
-                        Color::White => NodeType::PVNode(EvalType::MinimizerMate(0)),
-                        Color::Black => NodeType::PVNode(EvalType::MaximizerMate(0)),
-                    }
-                },
-                ChessStatus::WhiteWon => {
-                    match color {
-                        Color::White => NodeType::PVNode(EvalType::MaximizerMate(0)),
-                        Color::Black => NodeType::PVNode(EvalType::MinimizerMate(0)),
-                    }
-                },
-                ChessStatus::Draw => NodeType::PVNode(EvalType::ExactEval(OrderedFloat(0.))),
-            }
+            NodeType::PVNode(score)
         }
     }
 
@@ -300,32 +116,13 @@ impl<E: Evaluator> ClunkySearcherV4<E> {
         chess_board: &ChessBoard,
         evaluator: &E,
         depth: usize,
-        alpha: EvalType,
-        beta: EvalType,
-        start_time: Instant,
-        avail_time: Duration,
+        node: Node,
+        alpha: Score,
+        beta: Score,
     ) -> NodeType {
         let color = chess_board.get_turn_color();
         if depth == 0 {
-            let sign = OrderedFloat(color.as_sign());
-            let this_eval = EvalType::ExactEval(sign * evaluator.evaluate(chess_board));
-
-            if this_eval >= beta {
-                NodeType::CutNode(beta)
-            } else if this_eval <= alpha {
-                NodeType::AllNode(alpha)
-            } else {
-                NodeType::PVNode(this_eval)
-            }
-            
-            // if self.is_quiet(chess_board) {
-            //     NodeType::PVNode(this_eval)
-            // } else {
-            //     // We use the Null-Move Observation
-            //     let alpha = this_eval;
-
-            //     self.quiescence_search(chess_board, evaluator, 2, alpha, beta, start_time, avail_time)
-            // }
+            self.quiescence_search(chess_board, evaluator, alpha, beta)
         } else {
 
             let mut checkmate_eval;
@@ -343,32 +140,28 @@ impl<E: Evaluator> ClunkySearcherV4<E> {
             }
 
             let mut allowed_moves = chess_board.get_allowed_moves(color);
-            sort_moves(&mut allowed_moves, chess_board);
+            self.move_orderer.borrow().order_moves(
+                chess_board,
+                &mut allowed_moves,
+                node.ply,
+                cached_move,
+                node.previous_move,
+            );
 
             match chess_board.get_game_status_from_precomputed(&allowed_moves) {
                 ChessStatus::Ongoing => {
-                    
-                    
-                    if let Some(mv) = cached_move {
-                        let index = allowed_moves.iter().position(|&other_mv| other_mv == mv).unwrap();
-                        allowed_moves.swap(0, index);
-                    }
-                    
                     let mut best_move = allowed_moves[0];
 
-                    for mv in allowed_moves {
-                        // if Some(mv) == cached_move {
-                        //     continue;
-                        // }
+                    let mut searched_quiets: SmallVec<[Move; 64]> = SmallVec::new();
 
+                    for mv in allowed_moves {
                         let eval_search = self.search_internals(
                             &chess_board.next_state(&mv),
                             evaluator,
                             depth - 1,
+                            node.child(mv),
                             beta.backward(),
                             alpha.backward(),
-                            start_time,
-                            avail_time,
                         );
 
                         match eval_search {
@@ -387,6 +180,15 @@ impl<E: Evaluator> ClunkySearcherV4<E> {
 
                                 self.insert_cache(node_type, mv, depth, chess_board);
 
+                                self.move_orderer.borrow_mut().record_cutoff(
+                                    chess_board,
+                                    mv,
+                                    node.ply,
+                                    depth,
+                                    node.previous_move,
+                                    &searched_quiets,
+                                );
+
                                 return NodeType::CutNode(beta);
 
                             },
@@ -416,6 +218,10 @@ impl<E: Evaluator> ClunkySearcherV4<E> {
                                 assert_eq!(beta_op, alpha.backward());
                             }
                         }
+
+                        if !is_tactical(chess_board, &mv) {
+                            searched_quiets.push(mv);
+                        }
                     }
 
                     let node_type = if improved_alpha {
@@ -429,8 +235,8 @@ impl<E: Evaluator> ClunkySearcherV4<E> {
             },
             ChessStatus::BlackWon => {
                 match color {
-                    Color::White => checkmate_eval = EvalType::MinimizerMate(0),
-                    Color::Black => checkmate_eval = EvalType::MaximizerMate(0)
+                    Color::White => checkmate_eval = Score::mated_in(0),
+                    Color::Black => checkmate_eval = Score::mate_in(0)
                 }
 
                 if checkmate_eval >= beta {
@@ -444,8 +250,8 @@ impl<E: Evaluator> ClunkySearcherV4<E> {
             },
             ChessStatus::WhiteWon => {
                 match color {
-                    Color::White => checkmate_eval = EvalType::MaximizerMate(0),
-                    Color::Black => checkmate_eval = EvalType::MinimizerMate(0)
+                    Color::White => checkmate_eval = Score::mate_in(0),
+                    Color::Black => checkmate_eval = Score::mated_in(0)
                 }
 
                 if checkmate_eval >= beta {
@@ -457,7 +263,7 @@ impl<E: Evaluator> ClunkySearcherV4<E> {
                 }
             },
             ChessStatus::Draw => {
-                checkmate_eval = EvalType::ExactEval(OrderedFloat(0.));
+                checkmate_eval = Score::DRAW;
 
                 if checkmate_eval >= beta {
                     NodeType::CutNode(beta)
@@ -482,15 +288,18 @@ impl<E: Evaluator> TimedSearcher<E> for ClunkySearcherV4<E> {
         let start_time = Instant::now();
 
         self.cache.borrow_mut().clear();
+        self.move_orderer.borrow_mut().new_search();
 
         let avail_time = Duration::from_nanos((avail_time.as_nanos() as f64 * 0.90) as u64);
 
         let color = chess_board.get_turn_color();
         let mut allowed_moves = chess_board.get_allowed_moves(color);
 
-        sort_moves(&mut allowed_moves, chess_board);
+        self.move_orderer
+            .borrow()
+            .order_moves(chess_board, &mut allowed_moves, 0, None, None);
 
-        let mut value = EvalType::MinimizerMate(0);
+        let mut value = Score::mated_in(0);
 
         // Assign arbitrary move to assure that output won't be None
         let mut best_move = Some(allowed_moves[0]);
@@ -501,7 +310,7 @@ impl<E: Evaluator> TimedSearcher<E> for ClunkySearcherV4<E> {
                 break;
             }
 
-            let mut local_value = EvalType::MinimizerMate(-1);
+            let mut local_value = Score::mated_in(-1);
 
             let mut time_cutoff = false;
 
@@ -548,10 +357,9 @@ impl<E: Evaluator> TimedSearcher<E> for ClunkySearcherV4<E> {
                     &chess_board.next_state(&mv),
                     evaluator,
                     max_depth,
-                    EvalType::MinimizerMate(-1),
+                    Node::root().child(mv),
+                    Score::mated_in(-1),
                     local_value.backward(),
-                    start_time,
-                    avail_time,
                 );
                 
                 match eval_search {
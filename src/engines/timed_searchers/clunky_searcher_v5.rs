@@ -0,0 +1,1148 @@
+use crate::chess_server::chess_types::ChessStatus;
+use crate::chess_server::chess_types::Color;
+use crate::chess_server::chess_types::Piece;
+use crate::chess_server::chess_types::Square;
+use crate::engines::engine_traits::*;
+use crate::engines::move_ordering::{is_tactical, MoveOrderer, MAX_PLY};
+use crate::engines::quiescence::{QuiescenceConfig, QuiescenceSearch};
+use crate::engines::score::Score;
+use crate::engines::time_manager::{SearchTimer, TimeLimits};
+
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::cmp::{max, min};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use crate::chess_server::chess_types::ChessBoard;
+use crate::chess_server::chess_types::Move;
+
+use log::info;
+
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+
+use crate::engines::zobrist_hash::ZobristHashMap;
+
+// Width of the windows used to test whether a move beats alpha
+const NULL_WINDOW: Score = Score::cp(1);
+
+const ASPIRATION_MIN_DEPTH: i32 = 4;
+const ASPIRATION_MAX_DELTA: Score = Score::cp(1_000);
+
+const NULL_MOVE_MIN_DEPTH: i32 = 3;
+const NULL_MOVE_VERIFICATION_DEPTH: i32 = 8;
+
+const LMR_MIN_DEPTH: i32 = 3;
+const LMR_MIN_INDEX: usize = 3;
+
+const REVERSE_FUTILITY_MAX_DEPTH: i32 = 6;
+const REVERSE_FUTILITY_MARGIN: Score = Score::cp(90);
+
+const FUTILITY_MAX_DEPTH: i32 = 3;
+const FUTILITY_MARGINS: [Score; 4] = [
+    Score::cp(0),
+    Score::cp(100),
+    Score::cp(200),
+    Score::cp(320),
+];
+
+const RAZORING_MAX_DEPTH: i32 = 2;
+const RAZORING_MARGIN: Score = Score::cp(300);
+
+// Singular extensions are only tried on deep nodes whose hash move was
+// searched at most SINGULAR_DEPTH_MARGIN plies shallower
+const SINGULAR_MIN_DEPTH: i32 = 6;
+const SINGULAR_DEPTH_MARGIN: i32 = 3;
+const SINGULAR_MARGIN: Score = Score::cp(2);
+
+// Time is only checked every few nodes
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+/// Selective search features of [`ClunkySearcherV5`].
+///
+/// Every technique can be switched off on its own so that its impact can be
+/// measured in engine matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PruningConfig {
+    pub null_move: bool,
+    pub late_move_reductions: bool,
+    pub reverse_futility: bool,
+    pub futility: bool,
+    pub razoring: bool,
+    pub mate_distance: bool,
+}
+
+impl PruningConfig {
+    pub fn all() -> PruningConfig {
+        PruningConfig {
+            null_move: true,
+            late_move_reductions: true,
+            reverse_futility: true,
+            futility: true,
+            razoring: true,
+            mate_distance: true,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn none() -> PruningConfig {
+        PruningConfig {
+            null_move: false,
+            late_move_reductions: false,
+            reverse_futility: false,
+            futility: false,
+            razoring: false,
+            mate_distance: false,
+        }
+    }
+}
+
+impl Default for PruningConfig {
+    fn default() -> Self {
+        PruningConfig::all()
+    }
+}
+
+/// Search window features of [`ClunkySearcherV5`].
+///
+/// With `principal_variation_search` only the first move of a node is
+/// searched with the full window, the others with a null window and a
+/// re-search if they fail high. With `aspiration_windows` every iteration
+/// starts with a window of `aspiration_delta` around the previous score,
+/// doubled on each failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub principal_variation_search: bool,
+    pub aspiration_windows: bool,
+    pub aspiration_delta: Score,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            principal_variation_search: true,
+            aspiration_windows: true,
+            aspiration_delta: Score::cp(50),
+        }
+    }
+}
+
+/// Search extensions of [`ClunkySearcherV5`].
+///
+/// An extended move is searched one ply deeper. Singular extensions apply to
+/// the hash move when a reduced search without it fails low, and pawn push
+/// extensions to pawns reaching the 7th rank. A line is extended at most
+/// `max_extensions` times so that long forcing sequences can't explode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExtensionConfig {
+    pub check: bool,
+    pub singular: bool,
+    pub recapture: bool,
+    pub pawn_push: bool,
+    pub max_extensions: i32,
+}
+
+impl ExtensionConfig {
+    pub fn all() -> ExtensionConfig {
+        ExtensionConfig {
+            check: true,
+            singular: true,
+            recapture: true,
+            pawn_push: true,
+            max_extensions: 16,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn none() -> ExtensionConfig {
+        ExtensionConfig {
+            check: false,
+            singular: false,
+            recapture: false,
+            pawn_push: false,
+            max_extensions: 0,
+        }
+    }
+}
+
+impl Default for ExtensionConfig {
+    fn default() -> Self {
+        ExtensionConfig::all()
+    }
+}
+
+/// Features of [`ClunkySearcherV5`], which bot configs can switch off, e.g.
+/// `{"pruning": {"null_move": false}}`. Margins keep their defaults.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    pub pruning: PruningConfig,
+    pub windows: WindowConfig,
+    pub extensions: ExtensionConfig,
+    #[serde(skip)]
+    pub quiescence: QuiescenceConfig,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy)]
+struct CacheEntry {
+    bound: Bound,
+    score: Score,
+    mv: Option<Move>,
+    depth: i32,
+    chess_board: ChessBoard,
+}
+
+// Information passed from a node to its children
+#[derive(Clone, Copy)]
+struct Node {
+    ply: usize,
+    previous_move: Option<Move>,
+    // Square of the capture that led to the node, if any
+    capture_square: Option<Square>,
+    extensions: i32,
+    allow_null: bool,
+    // Move skipped by the singular extension search
+    excluded_move: Option<Move>,
+}
+
+impl Node {
+    fn root() -> Node {
+        Node {
+            ply: 0,
+            previous_move: None,
+            capture_square: None,
+            extensions: 0,
+            allow_null: true,
+            excluded_move: None,
+        }
+    }
+
+    fn child(&self, chess_board: &ChessBoard, mv: Move, extension: i32) -> Node {
+        let capture_square = chess_board
+            .get_square_content(&mv.get_next_square())
+            .map(|_| mv.get_next_square());
+
+        Node {
+            ply: self.ply + 1,
+            previous_move: Some(mv),
+            capture_square,
+            extensions: self.extensions + extension,
+            allow_null: true,
+            excluded_move: None,
+        }
+    }
+}
+
+// Mate scores are stored relative to the node and not to the root
+fn score_to_cache(score: Score, ply: usize) -> Score {
+    if !score.is_mate() {
+        score
+    } else if score > Score::DRAW {
+        score + Score::cp(ply as i32)
+    } else {
+        score - Score::cp(ply as i32)
+    }
+}
+
+fn score_from_cache(score: Score, ply: usize) -> Score {
+    if !score.is_mate() {
+        score
+    } else if score > Score::DRAW {
+        score - Score::cp(ply as i32)
+    } else {
+        score + Score::cp(ply as i32)
+    }
+}
+
+fn has_non_pawn_material(chess_board: &ChessBoard, color: Color) -> bool {
+    chess_board.iter_coordinates().any(|(_, content)| match content {
+        Some(color_piece) => {
+            color_piece.get_color() == color
+                && !matches!(color_piece.get_piece(), Piece::Pawn | Piece::King)
+        }
+        None => false,
+    })
+}
+
+fn is_pawn_push_to_seventh(chess_board: &ChessBoard, mv: &Move) -> bool {
+    let (row, _) = mv.get_next_square().get_coordinates();
+
+    match chess_board.get_square_content(&mv.get_current_square()) {
+        Some(color_piece) if color_piece.get_piece() == Piece::Pawn => {
+            match color_piece.get_color() {
+                Color::White => row == 1,
+                Color::Black => row == 6,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn late_move_reduction(depth: i32, move_index: usize, history: i32) -> i32 {
+    let reduction = ((depth as f64).ln() * (move_index as f64).ln() / 2.25) as i32;
+
+    // Moves with a good history are reduced less
+    let reduction = if history > 0 {
+        reduction - 1
+    } else if history < 0 {
+        reduction + 1
+    } else {
+        reduction
+    };
+
+    reduction.clamp(0, depth - 2)
+}
+
+pub struct ClunkySearcherV5<E: Evaluator> {
+    phantom: PhantomData<E>,
+    config: SearchConfig,
+    cache: RefCell<ZobristHashMap<CacheEntry>>,
+    move_orderer: RefCell<MoveOrderer>,
+    quiescence: QuiescenceSearch,
+    timer: RefCell<SearchTimer>,
+    nodes: Cell<u64>,
+    aborted: Cell<bool>,
+    last_score: Cell<Option<Score>>,
+}
+
+impl<E: Evaluator> ClunkySearcherV5<E> {
+    pub fn new() -> ClunkySearcherV5<E> {
+        ClunkySearcherV5::with_config(SearchConfig::default())
+    }
+
+    pub fn with_config(config: SearchConfig) -> ClunkySearcherV5<E> {
+        ClunkySearcherV5 {
+            phantom: PhantomData,
+            config,
+            cache: RefCell::new(ZobristHashMap::new()),
+            move_orderer: RefCell::new(MoveOrderer::new()),
+            quiescence: QuiescenceSearch::new(config.quiescence),
+            timer: RefCell::new(SearchTimer::new(TimeLimits::fixed(Duration::ZERO))),
+            nodes: Cell::new(0),
+            aborted: Cell::new(false),
+            last_score: Cell::new(None),
+        }
+    }
+
+    fn get_cached(&self, chess_board: &ChessBoard) -> Option<CacheEntry> {
+        match self.cache.borrow().get_key_value(chess_board) {
+            Some(&entry) if entry.chess_board == *chess_board => Some(entry),
+            _ => None,
+        }
+    }
+
+    fn insert_cache(
+        &self,
+        chess_board: &ChessBoard,
+        bound: Bound,
+        score: Score,
+        mv: Option<Move>,
+        depth: i32,
+        ply: usize,
+    ) {
+        self.cache.borrow_mut().insert(
+            chess_board,
+            CacheEntry {
+                bound,
+                score: score_to_cache(score, ply),
+                mv,
+                depth,
+                chess_board: *chess_board,
+            },
+        );
+    }
+
+    fn is_time_up(&self) -> bool {
+        self.timer.borrow().hard_limit_reached()
+    }
+
+    // Counts the node and aborts the search once the time is over
+    fn visit_node(&self) -> bool {
+        let nodes = self.nodes.get() + 1;
+        self.nodes.set(nodes);
+
+        if nodes.is_multiple_of(TIME_CHECK_INTERVAL) && self.is_time_up() {
+            self.aborted.set(true);
+        }
+
+        self.aborted.get()
+    }
+
+    fn terminal_score(chess_board: &ChessBoard, status: ChessStatus, ply: usize) -> Score {
+        match status {
+            ChessStatus::Draw => Score::DRAW,
+            ChessStatus::WhiteWon | ChessStatus::BlackWon => {
+                // The side to move is always the one that got mated
+                debug_assert!(chess_board.is_king_in_check(chess_board.get_turn_color()));
+                Score::mated_in(ply as i32)
+            }
+            ChessStatus::Ongoing => unreachable!(),
+        }
+    }
+
+    fn quiescence_search(
+        &self,
+        chess_board: &ChessBoard,
+        evaluator: &E,
+        ply: usize,
+        alpha: Score,
+        beta: Score,
+    ) -> Score {
+        self.quiescence
+            .search_with_stop(chess_board, evaluator, ply, alpha, beta, &|| self.visit_node())
+    }
+
+    // Searches a child node and returns its score from the parent's point of
+    // view. Without PVS every move gets the full window; reduced moves that
+    // beat alpha are always searched again at full depth.
+    #[allow(clippy::too_many_arguments)]
+    fn search_move(
+        &self,
+        next_board: &ChessBoard,
+        evaluator: &E,
+        depth: i32,
+        reduction: i32,
+        alpha: Score,
+        beta: Score,
+        child: Node,
+        is_first_move: bool,
+    ) -> Score {
+        let search = |depth: i32, alpha: Score, beta: Score| {
+            -self.search_internals(next_board, evaluator, depth, -beta, -alpha, child)
+        };
+
+        if is_first_move {
+            return search(depth, alpha, beta);
+        }
+
+        let pvs = self.config.windows.principal_variation_search;
+        let zero_window_beta = if pvs { alpha + NULL_WINDOW } else { beta };
+
+        let mut score = search(depth - reduction, alpha, zero_window_beta);
+
+        if reduction > 0 && score > alpha && !self.aborted.get() {
+            score = search(depth, alpha, zero_window_beta);
+        }
+
+        if pvs && score > alpha && score < beta && !self.aborted.get() {
+            score = search(depth, alpha, beta);
+        }
+
+        score
+    }
+
+    fn extension(
+        &self,
+        chess_board: &ChessBoard,
+        mv: &Move,
+        gives_check: bool,
+        node: &Node,
+        singular_move: Option<Move>,
+    ) -> i32 {
+        let extensions = self.config.extensions;
+
+        if node.extensions >= extensions.max_extensions {
+            return 0;
+        }
+
+        let extend = (extensions.check && gives_check)
+            || (extensions.singular && singular_move == Some(*mv))
+            || (extensions.recapture && node.capture_square == Some(mv.get_next_square()))
+            || (extensions.pawn_push && is_pawn_push_to_seventh(chess_board, mv));
+
+        extend as i32
+    }
+
+    // The hash move is singular when every other move fails low against a
+    // bound slightly below its cached score
+    fn is_singular(
+        &self,
+        chess_board: &ChessBoard,
+        evaluator: &E,
+        depth: i32,
+        node: Node,
+        entry: &CacheEntry,
+    ) -> bool {
+        let singular_beta =
+            score_from_cache(entry.score, node.ply) - SINGULAR_MARGIN * depth;
+
+        let score = self.search_internals(
+            chess_board,
+            evaluator,
+            (depth - 1) / 2,
+            singular_beta - NULL_WINDOW,
+            singular_beta,
+            Node {
+                allow_null: false,
+                excluded_move: entry.mv,
+                ..node
+            },
+        );
+
+        score < singular_beta
+    }
+
+    fn search_internals(
+        &self,
+        chess_board: &ChessBoard,
+        evaluator: &E,
+        depth: i32,
+        alpha: Score,
+        beta: Score,
+        node: Node,
+    ) -> Score {
+        let ply = node.ply;
+        debug_assert!(node.extensions <= self.config.extensions.max_extensions);
+
+        if depth <= 0 || ply >= MAX_PLY - 1 {
+            return self.quiescence_search(chess_board, evaluator, ply, alpha, beta);
+        }
+
+        if self.visit_node() {
+            return Score::DRAW;
+        }
+
+        let color = chess_board.get_turn_color();
+
+        // Nodes searched with a null window are expected to fail high or low,
+        // only PV nodes need an exact score
+        let is_pv = beta - alpha > NULL_WINDOW * 2;
+
+        let mut alpha = alpha;
+        let mut beta = beta;
+
+        // Mate distance pruning: no line from here can beat a mate found
+        // closer to the root
+        if self.config.pruning.mate_distance {
+            alpha = max(alpha, Score::mated_in(ply as i32));
+            beta = min(beta, Score::mate_in(ply as i32 + 1));
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+
+        let original_alpha = alpha;
+        let excluded_move = node.excluded_move;
+
+        let cached_entry = self.get_cached(chess_board);
+        let hash_move = cached_entry.and_then(|entry| entry.mv);
+
+        if let Some(entry) = cached_entry {
+            if !is_pv && excluded_move.is_none() && entry.depth >= depth {
+                let score = score_from_cache(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => (),
+                }
+            }
+        }
+
+        let mut allowed_moves = chess_board.get_allowed_moves(color);
+
+        match chess_board.get_game_status_from_precomputed(&allowed_moves) {
+            ChessStatus::Ongoing => (),
+            status => return Self::terminal_score(chess_board, status, ply),
+        }
+
+        let in_check = chess_board.is_king_in_check(color);
+        let static_eval = if in_check {
+            -Score::INFINITE
+        } else {
+            evaluator.evaluate(chess_board)
+        };
+
+        if !is_pv && !in_check && excluded_move.is_none() && !beta.is_mate() {
+            // Reverse futility pruning (static null move pruning)
+            if self.config.pruning.reverse_futility
+                && depth <= REVERSE_FUTILITY_MAX_DEPTH
+                && static_eval - REVERSE_FUTILITY_MARGIN * depth >= beta
+            {
+                return static_eval;
+            }
+
+            // Razoring: hopeless positions are resolved by quiescence only
+            if self.config.pruning.razoring
+                && depth <= RAZORING_MAX_DEPTH
+                && static_eval + RAZORING_MARGIN * depth < alpha
+            {
+                let score = self.quiescence_search(chess_board, evaluator, ply, alpha, beta);
+                if score < alpha || self.aborted.get() {
+                    return score;
+                }
+            }
+
+            // Null move pruning. Positions with only pawns are skipped to
+            // avoid zugzwang, and deep cutoffs are verified by a reduced search
+            if self.config.pruning.null_move
+                && node.allow_null
+                && depth >= NULL_MOVE_MIN_DEPTH
+                && static_eval >= beta
+                && has_non_pawn_material(chess_board, color)
+            {
+                let reduction = 2 + depth / 4;
+                let null_board = chess_board.null_move_state();
+
+                evaluator.push_position(&null_board);
+                let score = -self.search_internals(
+                    &null_board,
+                    evaluator,
+                    depth - 1 - reduction,
+                    -beta,
+                    -beta + NULL_WINDOW,
+                    Node {
+                        ply: ply + 1,
+                        previous_move: None,
+                        capture_square: None,
+                        extensions: node.extensions,
+                        allow_null: false,
+                        excluded_move: None,
+                    },
+                );
+                evaluator.pop_position();
+
+                if self.aborted.get() {
+                    return Score::DRAW;
+                }
+
+                if score >= beta {
+                    let score = if score.is_mate() { beta } else { score };
+
+                    if depth < NULL_MOVE_VERIFICATION_DEPTH {
+                        return score;
+                    }
+
+                    let verification = self.search_internals(
+                        chess_board,
+                        evaluator,
+                        depth - 1 - reduction,
+                        beta - NULL_WINDOW,
+                        beta,
+                        Node {
+                            allow_null: false,
+                            ..node
+                        },
+                    );
+
+                    if verification >= beta {
+                        return score;
+                    }
+                }
+            }
+        }
+
+        let singular_move = match cached_entry {
+            Some(entry)
+                if self.config.extensions.singular
+                    && excluded_move.is_none()
+                    && depth >= SINGULAR_MIN_DEPTH
+                    && entry.depth >= depth - SINGULAR_DEPTH_MARGIN
+                    && matches!(entry.bound, Bound::Exact | Bound::Lower)
+                    && !entry.score.is_mate()
+                    && node.extensions < self.config.extensions.max_extensions
+                    && self.is_singular(chess_board, evaluator, depth, node, &entry) =>
+            {
+                entry.mv
+            }
+            _ => None,
+        };
+
+        if self.aborted.get() {
+            return Score::DRAW;
+        }
+
+        self.move_orderer.borrow().order_moves(
+            chess_board,
+            &mut allowed_moves,
+            ply,
+            hash_move,
+            node.previous_move,
+        );
+
+        // Futility pruning: at frontier nodes quiet moves that cannot raise
+        // the static evaluation above alpha are skipped
+        let futility_pruning = self.config.pruning.futility
+            && !is_pv
+            && !in_check
+            && depth <= FUTILITY_MAX_DEPTH
+            && !alpha.is_mate()
+            && static_eval + FUTILITY_MARGINS[depth as usize] <= alpha;
+
+        let mut best_score = -Score::INFINITE;
+        let mut best_move = None;
+        let mut searched_quiets: SmallVec<[Move; 64]> = SmallVec::new();
+        let mut move_index = 0;
+
+        for mv in allowed_moves {
+            if Some(mv) == excluded_move {
+                continue;
+            }
+
+            let next_board = chess_board.next_state(&mv);
+            let tactical = is_tactical(chess_board, &mv);
+            let gives_check = next_board.is_king_in_check(color.opposite());
+            let extension = self.extension(chess_board, &mv, gives_check, &node, singular_move);
+            let quiet = !tactical && !gives_check && extension == 0;
+
+            if futility_pruning && move_index > 0 && quiet {
+                continue;
+            }
+
+            let reduction = if self.config.pruning.late_move_reductions
+                && depth >= LMR_MIN_DEPTH
+                && move_index >= LMR_MIN_INDEX
+                && quiet
+                && !in_check
+            {
+                let history = self.move_orderer.borrow().get_history(color, &mv);
+                late_move_reduction(depth, move_index, history)
+            } else {
+                0
+            };
+
+            evaluator.push_position(&next_board);
+            let score = self.search_move(
+                &next_board,
+                evaluator,
+                depth - 1 + extension,
+                reduction,
+                alpha,
+                beta,
+                node.child(chess_board, mv, extension),
+                move_index == 0,
+            );
+            evaluator.pop_position();
+
+            if self.aborted.get() {
+                return Score::DRAW;
+            }
+
+            move_index += 1;
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+                alpha = max(alpha, score);
+            }
+
+            if alpha >= beta {
+                self.move_orderer.borrow_mut().record_cutoff(
+                    chess_board,
+                    mv,
+                    ply,
+                    depth as usize,
+                    node.previous_move,
+                    &searched_quiets,
+                );
+                if excluded_move.is_none() {
+                    self.insert_cache(chess_board, Bound::Lower, best_score, best_move, depth, ply);
+                }
+                return best_score;
+            }
+
+            if !tactical {
+                searched_quiets.push(mv);
+            }
+        }
+
+        // Searches without the excluded move don't describe the position
+        if excluded_move.is_some() {
+            return best_score;
+        }
+
+        let bound = if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+
+        self.insert_cache(chess_board, bound, best_score, best_move, depth, ply);
+        best_score
+    }
+}
+
+impl<E: Evaluator> ClunkySearcherV5<E> {
+    // Returns the best score and the move that raised alpha, if any
+    fn search_root(
+        &self,
+        chess_board: &ChessBoard,
+        evaluator: &E,
+        depth: i32,
+        alpha: Score,
+        beta: Score,
+        allowed_moves: &[Move],
+    ) -> (Score, Option<Move>) {
+        let root = Node::root();
+        let color = chess_board.get_turn_color();
+
+        let mut alpha = alpha;
+        let mut best_score = -Score::INFINITE;
+        let mut best_move = None;
+
+        // Root moves are pushed on top of the root, wherever the evaluator was
+        evaluator.push_position(chess_board);
+
+        for (move_index, &mv) in allowed_moves.iter().enumerate() {
+            let next_board = chess_board.next_state(&mv);
+            let gives_check = next_board.is_king_in_check(color.opposite());
+            let extension = self.extension(chess_board, &mv, gives_check, &root, None);
+
+            evaluator.push_position(&next_board);
+            let score = self.search_move(
+                &next_board,
+                evaluator,
+                depth - 1 + extension,
+                0,
+                alpha,
+                beta,
+                root.child(chess_board, mv, extension),
+                move_index == 0,
+            );
+            evaluator.pop_position();
+
+            if self.aborted.get() {
+                break;
+            }
+
+            best_score = max(best_score, score);
+
+            if score > alpha {
+                alpha = score;
+                best_move = Some(mv);
+            }
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        evaluator.pop_position();
+        (best_score, best_move)
+    }
+}
+
+impl<E: Evaluator> TimedSearcher<E> for ClunkySearcherV5<E> {
+    fn search(
+        &self,
+        chess_board: &ChessBoard,
+        evaluator: &E,
+        avail_time: Duration,
+    ) -> Option<Move> {
+        let limits = TimeLimits::fixed(avail_time.mul_f64(0.90));
+        self.search_with_timer(chess_board, evaluator, SearchTimer::new(limits))
+    }
+
+    fn search_with_timer(
+        &self,
+        chess_board: &ChessBoard,
+        evaluator: &E,
+        timer: SearchTimer,
+    ) -> Option<Move> {
+        *self.timer.borrow_mut() = timer;
+        self.nodes.set(0);
+        self.aborted.set(false);
+        self.last_score.set(None);
+
+        self.cache.borrow_mut().clear();
+        self.move_orderer.borrow_mut().new_search();
+
+        let color = chess_board.get_turn_color();
+        let mut allowed_moves = chess_board.get_allowed_moves(color);
+
+        self.move_orderer
+            .borrow()
+            .order_moves(chess_board, &mut allowed_moves, 0, None, None);
+
+        // Assign arbitrary move to assure that output won't be None
+        let mut best_move = *allowed_moves.first()?;
+        let mut value = -Score::INFINITE;
+
+        for max_depth in 1..MAX_PLY as i32 {
+            if !self.timer.borrow().can_start_iteration() {
+                info!("Cutoff at max depth: {}", max_depth - 1);
+                break;
+            }
+
+            let windows = self.config.windows;
+            let mut delta = windows.aspiration_delta;
+
+            let (mut alpha, mut beta) = if windows.aspiration_windows
+                && max_depth >= ASPIRATION_MIN_DEPTH
+                && !value.is_mate()
+            {
+                (value - delta, value + delta)
+            } else {
+                (-Score::INFINITE, Score::INFINITE)
+            };
+
+            loop {
+                let (score, local_best_move) =
+                    self.search_root(chess_board, evaluator, max_depth, alpha, beta, &allowed_moves);
+
+                if let Some(mv) = local_best_move {
+                    // The previous best move is searched first, so any move
+                    // that beat alpha is at least as good
+                    best_move = mv;
+                }
+
+                if self.aborted.get() {
+                    break;
+                }
+
+                delta = delta * 2;
+                let open_window = delta > ASPIRATION_MAX_DELTA;
+
+                if score <= alpha && alpha > -Score::INFINITE {
+                    info!("Aspiration fail low at depth {}: {}", max_depth, score);
+                    beta = (alpha + beta) / 2;
+                    alpha = if open_window { -Score::INFINITE } else { max(score - delta, -Score::INFINITE) };
+                } else if score >= beta && beta < Score::INFINITE {
+                    info!("Aspiration fail high at depth {}: {}", max_depth, score);
+                    beta = if open_window { Score::INFINITE } else { min(score + delta, Score::INFINITE) };
+                } else {
+                    value = score;
+                    break;
+                }
+            }
+
+            if self.aborted.get() {
+                info!("Time break at depth {}", max_depth);
+                break;
+            }
+
+            let index = allowed_moves.iter().position(|&other| other == best_move).unwrap();
+            allowed_moves[..=index].rotate_right(1);
+
+            self.insert_cache(chess_board, Bound::Exact, value, Some(best_move), max_depth, 0);
+            self.timer.borrow_mut().record_iteration(best_move, value);
+
+            info!(
+                "Completed depth {}. Eval {}. Best Move: {}. Nodes: {}",
+                max_depth,
+                value,
+                best_move,
+                self.nodes.get()
+            );
+
+            // Deeper searches may still find a longer defence against a mate
+            if value.is_mate() && value > Score::DRAW {
+                break;
+            }
+        }
+
+        info!("Completed Search: Eval {}. Best Move: {}", value, best_move);
+        self.last_score.set((value > -Score::INFINITE).then_some(value));
+        Some(best_move)
+    }
+
+    fn get_last_score(&self) -> Option<Score> {
+        self.last_score.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_server::io::utils::parse_uci_move;
+    use crate::engines::evaluators::MaterialEvaluator;
+
+    // Re8+ Rxe8 Rxe8 mates, after any other move black covers the back rank
+    const MATE_IN_TWO: &str = "r5k1/5ppp/8/8/8/8/4RPPP/4R1K1 w - - 0 1";
+
+    fn assert_finds_mate_in_two(config: SearchConfig) {
+        let searcher = ClunkySearcherV5::with_config(config);
+        let chess_board = ChessBoard::from_fen(MATE_IN_TWO).unwrap();
+
+        let mv = searcher.search(&chess_board, &MaterialEvaluator::new(), Duration::from_secs(5));
+        assert_eq!(mv.map(|mv| mv.to_string()), Some("e2e8".to_string()), "{:?}", config);
+        assert_eq!(searcher.get_last_score(), Some(Score::mate_in(3)), "{:?}", config);
+    }
+
+    #[test]
+    fn finds_mates_whatever_is_pruned() {
+        let switches: [fn(&mut PruningConfig); 6] = [
+            |pruning| pruning.null_move = false,
+            |pruning| pruning.late_move_reductions = false,
+            |pruning| pruning.reverse_futility = false,
+            |pruning| pruning.futility = false,
+            |pruning| pruning.razoring = false,
+            |pruning| pruning.mate_distance = false,
+        ];
+
+        for switch_off in switches {
+            let mut pruning = PruningConfig::all();
+            switch_off(&mut pruning);
+            assert_finds_mate_in_two(SearchConfig {
+                pruning,
+                ..SearchConfig::default()
+            });
+        }
+        assert_finds_mate_in_two(SearchConfig {
+            pruning: PruningConfig::none(),
+            ..SearchConfig::default()
+        });
+
+        // The side getting mated sees it coming
+        let chess_board = ChessBoard::from_fen(MATE_IN_TWO).unwrap();
+        let chess_board = chess_board.next_state(&parse_uci_move(&chess_board, "e2e8").unwrap());
+        let searcher = ClunkySearcherV5::new();
+        searcher.search(&chess_board, &MaterialEvaluator::new(), Duration::from_secs(5));
+        assert_eq!(searcher.get_last_score(), Some(Score::mated_in(2)));
+    }
+
+    #[test]
+    fn finds_mates_with_any_window() {
+        for (principal_variation_search, aspiration_windows) in
+            [(false, true), (true, false), (false, false)]
+        {
+            assert_finds_mate_in_two(SearchConfig {
+                windows: WindowConfig {
+                    principal_variation_search,
+                    aspiration_windows,
+                    ..WindowConfig::default()
+                },
+                ..SearchConfig::default()
+            });
+        }
+    }
+
+    #[test]
+    fn finds_mates_whatever_is_extended() {
+        let switches: [fn(&mut ExtensionConfig); 5] = [
+            |extensions| extensions.check = false,
+            |extensions| extensions.singular = false,
+            |extensions| extensions.recapture = false,
+            |extensions| extensions.pawn_push = false,
+            |extensions| extensions.max_extensions = 0,
+        ];
+
+        for switch_off in switches {
+            let mut extensions = ExtensionConfig::all();
+            switch_off(&mut extensions);
+            assert_finds_mate_in_two(SearchConfig {
+                extensions,
+                ..SearchConfig::default()
+            });
+        }
+        assert_finds_mate_in_two(SearchConfig {
+            extensions: ExtensionConfig::none(),
+            ..SearchConfig::default()
+        });
+    }
+
+    // Rook ladder: four checks in a row mate the king on the back rank
+    const LADDER_MATE: &str = "8/8/8/6k1/R7/1R6/8/7K w - - 0 1";
+
+    // Score of a search at a fixed depth from `node`, with the number of
+    // nodes visited
+    fn search_at_depth(
+        config: SearchConfig,
+        fen: &str,
+        depth: i32,
+        alpha: Score,
+        beta: Score,
+        node: Node,
+    ) -> (Score, u64) {
+        let searcher = ClunkySearcherV5::with_config(config);
+        *searcher.timer.borrow_mut() = SearchTimer::new(TimeLimits::fixed(Duration::from_secs(60)));
+
+        let chess_board = ChessBoard::from_fen(fen).unwrap();
+        let score = searcher.search_internals(
+            &chess_board,
+            &MaterialEvaluator::new(),
+            depth,
+            alpha,
+            beta,
+            node,
+        );
+        (score, searcher.nodes.get())
+    }
+
+    #[test]
+    fn check_extensions_find_mates_beyond_the_depth() {
+        let search = |extensions: ExtensionConfig| {
+            let config = SearchConfig {
+                extensions,
+                ..SearchConfig::default()
+            };
+            let window = (-Score::INFINITE, Score::INFINITE);
+            search_at_depth(config, LADDER_MATE, 4, window.0, window.1, Node::root()).0
+        };
+
+        let checks_only = ExtensionConfig {
+            check: true,
+            max_extensions: 16,
+            ..ExtensionConfig::none()
+        };
+        assert_eq!(search(checks_only), Score::mate_in(7));
+        assert!(!search(ExtensionConfig::none()).is_mate());
+
+        // Out of extensions the mate is as far as without them
+        let bounded = ExtensionConfig {
+            max_extensions: 2,
+            ..checks_only
+        };
+        assert!(!search(bounded).is_mate());
+    }
+
+    #[test]
+    fn max_extensions_bounds_the_extensions_of_a_line() {
+        let searcher = ClunkySearcherV5::<MaterialEvaluator>::with_config(SearchConfig {
+            extensions: ExtensionConfig {
+                max_extensions: 2,
+                ..ExtensionConfig::all()
+            },
+            ..SearchConfig::default()
+        });
+        let chess_board = ChessBoard::from_fen(LADDER_MATE).unwrap();
+        let check = parse_uci_move(&chess_board, "b3b5").unwrap();
+
+        let mut node = Node::root();
+        for extensions in [1, 2, 2] {
+            let extension = searcher.extension(&chess_board, &check, true, &node, None);
+            node = node.child(&chess_board, check, extension);
+            assert_eq!(node.extensions, extensions);
+        }
+
+        // Searches check the bound on every node in debug builds
+        searcher.search(&chess_board, &MaterialEvaluator::new(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn mate_distance_pruning_keeps_shorter_mates() {
+        // A mate in 2 plies was found closer to the root, nothing 3 plies
+        // below it can be shorter
+        let node = Node {
+            ply: 3,
+            ..Node::root()
+        };
+        let search = |mate_distance: bool| {
+            let config = SearchConfig {
+                pruning: PruningConfig {
+                    mate_distance,
+                    ..PruningConfig::all()
+                },
+                ..SearchConfig::default()
+            };
+            search_at_depth(config, MATE_IN_TWO, 4, Score::mate_in(2), Score::INFINITE, node)
+        };
+
+        assert_eq!(search(true), (Score::mate_in(2), 1));
+
+        let (score, nodes) = search(false);
+        assert!(score < Score::mate_in(2));
+        assert!(nodes > 1);
+    }
+}
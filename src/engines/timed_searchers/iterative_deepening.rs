@@ -10,13 +10,14 @@ use crate::chess_server::chess_types::ChessBoard;
 use crate::chess_server::chess_types::Move;
 
 use log::info;
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
 use std::cell::RefCell;
 use crate::engines::zobrist_hash::ZobristHashMap;
 
-const BIG_INF: OrderedFloat<f64> = OrderedFloat(1001.);
-const SMALL_INF: OrderedFloat<f64> = OrderedFloat(999.);
+const BIG_INF: Score = Score::INFINITE;
+// Just below a win
+const SMALL_INF: Score = Score::mate_in(1);
 
 pub struct IterativeDeepening<E: Evaluator> {
     phantom: PhantomData<E>,
@@ -58,19 +59,18 @@ impl<E: Evaluator> IterativeDeepening<E> {
         evaluator: &E,
         depth: usize,
         max_depth: usize,
-        alpha: OrderedFloat<f64>,
-        beta: OrderedFloat<f64>,
+        alpha: Score,
+        beta: Score,
         start_time: Instant,
         avail_time: Duration,
-    ) -> Option<OrderedFloat<f64>> {
+    ) -> Option<Score> {
         let color = chess_board.get_turn_color();
-        let sign = OrderedFloat(color.as_sign());
         if depth >= max_depth {
-            Some(sign * evaluator.evaluate(chess_board))
+            Some(evaluator.evaluate(chess_board))
         } else {
             match chess_board.get_game_status() {
                 ChessStatus::Ongoing => {
-                    let mut value: OrderedFloat<f64> = -BIG_INF;
+                    let mut value: Score = -BIG_INF;
                     let mut alpha = alpha;
                     
                     let mut cached_move = None;
@@ -158,8 +158,8 @@ impl<E: Evaluator> IterativeDeepening<E> {
                     self.insert_cache(best_move, max_depth - depth, chess_board);
                     Some(value)
                 }
-                ChessStatus::BlackWon => Some(sign * EVAL_BLACK_WON),
-                ChessStatus::WhiteWon => Some(sign * EVAL_WHITE_WON),
+                ChessStatus::BlackWon => Some(EVAL_BLACK_WON.relative(color)),
+                ChessStatus::WhiteWon => Some(EVAL_WHITE_WON.relative(color)),
                 ChessStatus::Draw => Some(EVAL_DRAW),
             }
         }
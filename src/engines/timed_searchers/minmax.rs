@@ -8,9 +8,9 @@ use std::time::Instant;
 use crate::chess_server::chess_types::ChessBoard;
 use crate::chess_server::chess_types::Move;
 
-use ordered_float::OrderedFloat;
+use crate::engines::score::Score;
 
-const INF: OrderedFloat<f64> = OrderedFloat(1000.);
+const INF: Score = Score::INFINITE;
 pub struct MinMax<E: Evaluator> {
     max_depth: usize,
     phantom: PhantomData<E>,
@@ -35,14 +35,13 @@ impl<E: Evaluator> MinMax<E> {
         depth: usize,
         start_time: Instant,
         avail_time: Duration
-    ) -> (OrderedFloat<f64>, Option<Move>) {
+    ) -> (Score, Option<Move>) {
         if depth == self.max_depth {
-            (evaluator.evaluate(chess_board), None)
+            (evaluator.evaluate_white(chess_board), None)
         } else {
             match chess_board.get_game_status() {
                 ChessStatus::Ongoing => {
                     let color = chess_board.get_turn_color();
-                    let sign = OrderedFloat(color.as_sign());
 
                     let allowed_moves = chess_board.get_allowed_moves(color);
 
@@ -56,13 +55,13 @@ impl<E: Evaluator> MinMax<E> {
                             break;
                         }
 
-                        let eval_search = sign * self.search_impl(
+                        let eval_search = self.search_impl(
                             &chess_board.next_state(&mv),
                             evaluator,
                             depth + 1,
                             start_time,
                             avail_time
-                        ).0;
+                        ).0.relative(color);
 
                         if eval_search > value || best_move.is_none() {
                             value = eval_search;
@@ -70,7 +69,7 @@ impl<E: Evaluator> MinMax<E> {
                         }
                     }
 
-                    (sign * value, best_move)
+                    (value.relative(color), best_move)
 
                 }
                 ChessStatus::BlackWon => (EVAL_BLACK_WON, None),
@@ -28,6 +28,26 @@ fn init_zobrist(local_state: u64) -> [u64; TABLE_SIZE] {
     output
 }
 
+fn make_indexer(index: u8, content: ColorPiece) -> usize {
+    let content_indexer = match content {
+        WHITE_KING => 0,
+        WHITE_QUEEN => 1,
+        WHITE_ROOK => 2,
+        WHITE_BISHOP => 3,
+        WHITE_KNIGHT => 4,
+        WHITE_PAWN => 5,
+        BLACK_KING => 6,
+        BLACK_QUEEN => 7,
+        BLACK_ROOK => 8,
+        BLACK_BISHOP => 9,
+        BLACK_KNIGHT => 10,
+        BLACK_PAWN => 11,
+        _ => unreachable!(),
+    };
+
+    1 + (index as usize) * 12 + content_indexer
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 struct BoardHash {
     value: u64,
@@ -37,26 +57,6 @@ impl BoardHash {
     fn new(chess_board: &ChessBoard, zobrist_table: [u64; TABLE_SIZE]) -> BoardHash {
         let mut state: u64 = 0;
 
-        let make_indexer = |index: u8, content: ColorPiece| {
-            let content_indexer = match content {
-                WHITE_KING => 0,
-                WHITE_QUEEN => 1,
-                WHITE_ROOK => 2,
-                WHITE_BISHOP => 3,
-                WHITE_KNIGHT => 4,
-                WHITE_PAWN => 5,
-                BLACK_KING => 6,
-                BLACK_QUEEN => 7,
-                BLACK_ROOK => 8,
-                BLACK_BISHOP => 9,
-                BLACK_KNIGHT => 10,
-                BLACK_PAWN => 11,
-                _ => unreachable!(),
-            };
-
-            1 + (index as usize) * 12 + content_indexer
-        };
-
         if chess_board.get_turn_color() == Color::White {
             state ^= zobrist_table[0];
         }
@@ -111,3 +111,50 @@ impl<V> ZobristHashMap<V> {
         self.cache.clear()
     }
 }
+
+/// Table of fixed size keyed by the pawns only, so that every position with
+/// the same pawn structure shares an entry. Entries are replaced when their
+/// slot is needed by another key.
+#[derive(Clone)]
+pub struct PawnHashMap<V> {
+    zobrist_table: [u64; TABLE_SIZE],
+    entries: Vec<Option<(u64, V)>>,
+}
+
+impl<V> PawnHashMap<V> {
+    // The capacity is rounded up to a power of two
+    pub fn with_capacity(capacity: usize) -> PawnHashMap<V> {
+        PawnHashMap {
+            zobrist_table: init_zobrist(ZOBRIST_SEED),
+            entries: (0..capacity.next_power_of_two()).map(|_| None).collect(),
+        }
+    }
+
+    pub fn get_key(&self, chess_board: &ChessBoard) -> u64 {
+        let mut key = 0;
+
+        for (coordinate, content) in chess_board.iter_coordinates() {
+            if let Some(content @ (WHITE_PAWN | BLACK_PAWN)) = content {
+                key ^= self.zobrist_table[make_indexer(coordinate.get_index(), content)];
+            }
+        }
+
+        key
+    }
+
+    pub fn get(&self, key: u64) -> Option<&V> {
+        match &self.entries[self.slot(key)] {
+            Some((entry_key, value)) if *entry_key == key => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, key: u64, value: V) {
+        let slot = self.slot(key);
+        self.entries[slot] = Some((key, value));
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+}
@@ -0,0 +1,271 @@
+use crate::chess_server::chess_types::{ChessBoard, ChessStatus, Color};
+use crate::chess_server::game::MoveRecord;
+use crate::engines::score::Score;
+
+/// Draw when both players agree that the game is level.
+///
+/// The last `move_count` scores of both players have to be within `score`
+/// pawns of 0, and the game has to be at least `min_ply` plies long.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DrawAdjudication {
+    pub score: f64,
+    pub move_count: usize,
+    pub min_ply: usize,
+}
+
+/// Loss for a player whose last `move_count` scores are all below `-score`
+/// pawns, while its opponent agrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResignAdjudication {
+    pub score: f64,
+    pub move_count: usize,
+}
+
+/// Rules used to end tournament games early.
+///
+/// The board doesn't know about repetitions or the fifty move rule, so
+/// without `repetition` and `max_plies` games between engines may never end.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AdjudicationConfig {
+    pub repetition: bool,
+    pub insufficient_material: bool,
+    pub max_plies: Option<usize>,
+    pub draw: Option<DrawAdjudication>,
+    pub resign: Option<ResignAdjudication>,
+}
+
+impl Default for AdjudicationConfig {
+    fn default() -> Self {
+        AdjudicationConfig {
+            repetition: true,
+            insufficient_material: true,
+            max_plies: Some(400),
+            draw: Some(DrawAdjudication {
+                score: 0.1,
+                move_count: 8,
+                min_ply: 80,
+            }),
+            resign: Some(ResignAdjudication {
+                score: 10.,
+                move_count: 4,
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Adjudication {
+    Repetition,
+    InsufficientMaterial,
+    MaxPlies,
+    DrawScore,
+    ResignScore,
+}
+
+/// Looks at the game after every move and decides whether it can be ended.
+#[derive(Clone, Copy, Debug)]
+pub struct Adjudicator {
+    config: AdjudicationConfig,
+}
+
+impl Adjudicator {
+    pub fn new(config: AdjudicationConfig) -> Adjudicator {
+        Adjudicator { config }
+    }
+
+    pub fn adjudicate(
+        &self,
+        chess_board: &ChessBoard,
+        records: &[MoveRecord],
+    ) -> Option<(ChessStatus, Adjudication)> {
+        if self.config.insufficient_material
+            && !chess_board.has_mating_material(Color::White)
+            && !chess_board.has_mating_material(Color::Black)
+        {
+            return Some((ChessStatus::Draw, Adjudication::InsufficientMaterial));
+        }
+
+        if self.config.repetition {
+            let repetitions = records
+                .iter()
+                .filter(|record| record.chess_board == *chess_board)
+                .count();
+
+            if repetitions >= 2 {
+                return Some((ChessStatus::Draw, Adjudication::Repetition));
+            }
+        }
+
+        if let Some(resign) = self.config.resign {
+            if let Some(status) = Self::resign_status(&resign, records) {
+                return Some((status, Adjudication::ResignScore));
+            }
+        }
+
+        if let Some(draw) = self.config.draw {
+            if records.len() >= draw.min_ply
+                && Self::last_scores(records, 2 * draw.move_count).is_some_and(|scores| {
+                    let bound = Score::from_pawns(draw.score);
+                    scores.iter().all(|&score| -bound <= score && score <= bound)
+                })
+            {
+                return Some((ChessStatus::Draw, Adjudication::DrawScore));
+            }
+        }
+
+        match self.config.max_plies {
+            Some(max_plies) if records.len() >= max_plies => {
+                Some((ChessStatus::Draw, Adjudication::MaxPlies))
+            }
+            _ => None,
+        }
+    }
+
+    // Scores of the last `count` moves, from the point of view of the player
+    // that made the last move
+    fn last_scores(records: &[MoveRecord], count: usize) -> Option<Vec<Score>> {
+        if count == 0 || records.len() < count {
+            return None;
+        }
+
+        records[records.len() - count..]
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(index, record)| {
+                record
+                    .score
+                    .map(|score| if index % 2 == 0 { score } else { -score })
+            })
+            .collect()
+    }
+
+    fn resign_status(resign: &ResignAdjudication, records: &[MoveRecord]) -> Option<ChessStatus> {
+        let scores = Self::last_scores(records, 2 * resign.move_count)?;
+        let last_mover = records.last()?.chess_board.get_turn_color();
+        let threshold = Score::from_pawns(resign.score);
+
+        if scores.iter().all(|&score| score <= -threshold) {
+            Some(winner_status(last_mover.opposite()))
+        } else if scores.iter().all(|&score| score >= threshold) {
+            Some(winner_status(last_mover))
+        } else {
+            None
+        }
+    }
+}
+
+fn winner_status(color: Color) -> ChessStatus {
+    match color {
+        Color::White => ChessStatus::WhiteWon,
+        Color::Black => ChessStatus::BlackWon,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_server::io::utils::parse_uci_move;
+
+    use std::time::Duration;
+
+    // Records of the moves played from the starting position, each with the
+    // score of the player making it
+    fn records(moves: &[(&str, Option<i32>)]) -> (ChessBoard, Vec<MoveRecord>) {
+        let mut chess_board = ChessBoard::starting_position();
+        let mut records = Vec::new();
+
+        for &(mv, score) in moves {
+            let mv = parse_uci_move(&chess_board, mv).unwrap();
+            records.push(MoveRecord {
+                mv,
+                chess_board,
+                time_spent: Duration::ZERO,
+                score: score.map(Score::cp),
+                draw_offer: false,
+                clock: None,
+            });
+            chess_board = chess_board.next_state(&mv);
+        }
+
+        (chess_board, records)
+    }
+
+    const NO_RULES: AdjudicationConfig = AdjudicationConfig {
+        repetition: false,
+        insufficient_material: false,
+        max_plies: None,
+        draw: None,
+        resign: None,
+    };
+
+    #[test]
+    fn draws_the_third_repetition() {
+        let adjudicator = Adjudicator::new(AdjudicationConfig::default());
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        let moves: Vec<(&str, Option<i32>)> = shuffle.iter().map(|&mv| (mv, None)).collect();
+
+        let (chess_board, twice) = records(&moves);
+        assert_eq!(adjudicator.adjudicate(&chess_board, &twice), None);
+
+        let (chess_board, thrice) = records(&[moves.clone(), moves].concat());
+        assert_eq!(
+            adjudicator.adjudicate(&chess_board, &thrice),
+            Some((ChessStatus::Draw, Adjudication::Repetition))
+        );
+    }
+
+    #[test]
+    fn draws_long_games() {
+        let adjudicator = Adjudicator::new(AdjudicationConfig {
+            max_plies: Some(4),
+            ..NO_RULES
+        });
+        let moves = [("e2e4", None), ("e7e5", None), ("g1f3", None), ("b8c6", None)];
+
+        let (chess_board, records_before) = records(&moves[..3]);
+        assert_eq!(adjudicator.adjudicate(&chess_board, &records_before), None);
+        let (chess_board, records_at) = records(&moves);
+        assert_eq!(
+            adjudicator.adjudicate(&chess_board, &records_at),
+            Some((ChessStatus::Draw, Adjudication::MaxPlies))
+        );
+    }
+
+    #[test]
+    fn resigns_when_both_players_agree() {
+        let adjudicator = Adjudicator::new(AdjudicationConfig {
+            resign: Some(ResignAdjudication {
+                score: 5.,
+                move_count: 2,
+            }),
+            ..NO_RULES
+        });
+        let mut moves = [
+            ("e2e4", Some(600)),
+            ("e7e5", Some(-600)),
+            ("g1f3", Some(700)),
+            ("b8c6", Some(-550)),
+        ];
+
+        let (chess_board, records_won) = records(&moves);
+        assert_eq!(
+            adjudicator.adjudicate(&chess_board, &records_won),
+            Some((ChessStatus::WhiteWon, Adjudication::ResignScore))
+        );
+
+        // Both players need `move_count` scores
+        let (chess_board, records_early) = records(&moves[..3]);
+        assert_eq!(adjudicator.adjudicate(&chess_board, &records_early), None);
+
+        // White doesn't think it is winning by enough
+        moves[2].1 = Some(300);
+        let (chess_board, records_unsure) = records(&moves);
+        assert_eq!(adjudicator.adjudicate(&chess_board, &records_unsure), None);
+
+        // Without a score nobody agrees
+        moves[2].1 = None;
+        let (chess_board, records_unknown) = records(&moves);
+        assert_eq!(adjudicator.adjudicate(&chess_board, &records_unknown), None);
+    }
+}
@@ -0,0 +1,98 @@
+use super::elo::MatchScore;
+
+use std::fmt;
+
+/// Scores of every player against every other one.
+#[derive(Clone, Debug)]
+pub struct Crosstable {
+    names: Vec<String>,
+    // scores[i][j] are the games of player i against player j
+    scores: Vec<Vec<MatchScore>>,
+}
+
+impl Crosstable {
+    pub fn new(names: Vec<String>) -> Crosstable {
+        let size = names.len();
+        Crosstable {
+            names,
+            scores: vec![vec![MatchScore::new(); size]; size],
+        }
+    }
+
+    // `points` are the points of `player` in the game
+    pub fn add_game(&mut self, player: usize, opponent: usize, points: f64) {
+        self.scores[player][opponent].add(points);
+        self.scores[opponent][player] = self.scores[player][opponent].reversed();
+    }
+
+    pub fn get_names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn get(&self, player: usize, opponent: usize) -> MatchScore {
+        self.scores[player][opponent]
+    }
+
+    pub fn total(&self, player: usize) -> MatchScore {
+        let mut total = MatchScore::new();
+        for score in self.scores[player].iter() {
+            total.merge(score);
+        }
+        total
+    }
+
+    // Players sorted by points, best first
+    pub fn ranking(&self) -> Vec<usize> {
+        let mut ranking: Vec<usize> = (0..self.names.len()).collect();
+        ranking.sort_by(|&a, &b| {
+            self.total(b)
+                .points()
+                .total_cmp(&self.total(a).points())
+                .then(a.cmp(&b))
+        });
+        ranking
+    }
+}
+
+impl fmt::Display for Crosstable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranking = self.ranking();
+        let name_width = self.names.iter().map(String::len).max().unwrap_or(0).max(4);
+
+        write!(
+            f,
+            "{:>3} {:<name_width$} {:>6} {:>5} {:>14} {:>16}",
+            "#", "Name", "Points", "Games", "+/=/-", "Elo"
+        )?;
+        for rank in 0..ranking.len() {
+            write!(f, " {:>9}", rank + 1)?;
+        }
+        writeln!(f)?;
+
+        for (rank, &player) in ranking.iter().enumerate() {
+            let total = self.total(player);
+            write!(
+                f,
+                "{:>3} {:<name_width$} {:>6.1} {:>5} {:>14} {:>16}",
+                rank + 1,
+                self.names[player],
+                total.points(),
+                total.games(),
+                format!("{}/{}/{}", total.wins, total.draws, total.losses),
+                format!("{:+.1} +/- {:.1}", total.elo_difference(), total.elo_error()),
+            )?;
+
+            for &opponent in ranking.iter() {
+                let score = self.get(player, opponent);
+                if opponent == player || score.games() == 0 {
+                    write!(f, " {:>9}", "-")?;
+                } else {
+                    write!(f, " {:>9}", format!("{:.1}/{}", score.points(), score.games()))?;
+                }
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
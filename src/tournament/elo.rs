@@ -0,0 +1,120 @@
+use std::fmt;
+
+// Two sided 95% confidence
+const CONFIDENCE_Z: f64 = 1.959964;
+
+/// Expected score of a player that is `elo` points stronger than its opponent.
+pub fn elo_to_score(elo: f64) -> f64 {
+    1. / (1. + 10f64.powf(-elo / 400.))
+}
+
+pub fn score_to_elo(score: f64) -> f64 {
+    -400. * (1. / score - 1.).log10()
+}
+
+/// Wins, draws and losses of a player, or of a player against another one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    pub fn new() -> MatchScore {
+        MatchScore::default()
+    }
+
+    // `points` is 1 for a win, 0.5 for a draw and 0 for a loss
+    pub fn add(&mut self, points: f64) {
+        if points > 0.5 {
+            self.wins += 1;
+        } else if points < 0.5 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &MatchScore) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+
+    // The same games seen from the opponent
+    pub fn reversed(&self) -> MatchScore {
+        MatchScore {
+            wins: self.losses,
+            draws: self.draws,
+            losses: self.wins,
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + 0.5 * self.draws as f64
+    }
+
+    pub fn score(&self) -> f64 {
+        match self.games() {
+            0 => 0.5,
+            games => self.points() / games as f64,
+        }
+    }
+
+    pub fn draw_ratio(&self) -> f64 {
+        match self.games() {
+            0 => 0.,
+            games => self.draws as f64 / games as f64,
+        }
+    }
+
+    pub fn elo_difference(&self) -> f64 {
+        score_to_elo(self.score())
+    }
+
+    /// Half width of the 95% confidence interval of the Elo difference,
+    /// from the variance of the score of a single game.
+    pub fn elo_error(&self) -> f64 {
+        let games = self.games() as f64;
+        if games == 0. {
+            return f64::INFINITY;
+        }
+
+        let score = self.score();
+        let variance = (self.wins as f64 * (1. - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / games;
+
+        let margin = CONFIDENCE_Z * (variance / games).sqrt();
+        let (low, high) = (score - margin, score + margin);
+
+        // Too few games to bound the difference
+        if low <= 0. || high >= 1. {
+            return f64::INFINITY;
+        }
+
+        (score_to_elo(high) - score_to_elo(low)) / 2.
+    }
+}
+
+impl fmt::Display for MatchScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "+{} ={} -{} ({:.1}/{}), Elo {:+.1} +/- {:.1}",
+            self.wins,
+            self.draws,
+            self.losses,
+            self.points(),
+            self.games(),
+            self.elo_difference(),
+            self.elo_error()
+        )
+    }
+}
@@ -0,0 +1,305 @@
+use super::ratings::{elo_ratings, glicko2_ratings, RatedGame, Rating};
+use super::runner::TournamentResults;
+
+use csv::{ReaderBuilder, WriterBuilder};
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::path::Path;
+
+/// A game of the ladder file. `score` is the points of white, and games of
+/// the same match share their `period`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LadderGame {
+    pub period: u32,
+    pub white: String,
+    pub black: String,
+    pub score: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RatingSystem {
+    Elo,
+    Glicko2,
+}
+
+/// Results of every match played between the bots, kept in a CSV file.
+///
+/// Ratings are computed again from all the games every time, so a new bot
+/// is placed as soon as its first match is added.
+#[derive(Clone, Debug, Default)]
+pub struct RatingLadder {
+    games: Vec<LadderGame>,
+}
+
+impl RatingLadder {
+    pub fn new() -> RatingLadder {
+        RatingLadder::default()
+    }
+
+    // A missing file is an empty ladder
+    pub fn load(path: &str) -> Result<RatingLadder, String> {
+        if !Path::new(path).exists() {
+            return Ok(RatingLadder::new());
+        }
+
+        let mut reader = ReaderBuilder::new()
+            .from_path(path)
+            .map_err(|err| format!("{}: {}", path, err))?;
+
+        let games = reader
+            .deserialize()
+            .collect::<Result<Vec<LadderGame>, _>>()
+            .map_err(|err| format!("{}: {}", path, err))?;
+
+        // The file may have been edited by hand
+        if let Some(game) = games.iter().find(|game| !(0. ..=1.).contains(&game.score)) {
+            return Err(format!(
+                "{}: invalid score {} of {} - {}",
+                path, game.score, game.white, game.black
+            ));
+        }
+
+        Ok(RatingLadder { games })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut writer = WriterBuilder::new()
+            .from_path(path)
+            .map_err(|err| format!("{}: {}", path, err))?;
+
+        for game in self.games.iter() {
+            writer.serialize(game).map_err(|err| err.to_string())?;
+        }
+
+        writer.flush().map_err(|err| err.to_string())
+    }
+
+    fn next_period(&self) -> u32 {
+        self.games.iter().map(|game| game.period + 1).max().unwrap_or(0)
+    }
+
+    /// Adds the games, given as (white, black, points of white), as a new
+    /// rating period.
+    pub fn add_match(&mut self, games: &[(String, String, f64)]) {
+        let period = self.next_period();
+
+        self.games.extend(games.iter().map(|(white, black, score)| LadderGame {
+            period,
+            white: white.clone(),
+            black: black.clone(),
+            score: *score,
+        }));
+    }
+
+    pub fn add_tournament(&mut self, results: &TournamentResults) {
+        let names = results.crosstable.get_names();
+
+        let games: Vec<(String, String, f64)> = results
+            .games
+            .iter()
+            .map(|result| {
+                (
+                    names[result.game.white].clone(),
+                    names[result.game.black].clone(),
+                    result.white_points(),
+                )
+            })
+            .collect();
+
+        self.add_match(&games);
+    }
+
+    // Names of the players, in order of first appearance
+    pub fn get_players(&self) -> Vec<String> {
+        let mut players: Vec<String> = Vec::new();
+
+        for game in self.games.iter() {
+            for name in [&game.white, &game.black] {
+                if !players.contains(name) {
+                    players.push(name.clone());
+                }
+            }
+        }
+
+        players
+    }
+
+    pub fn ratings(&self, system: RatingSystem) -> Result<Vec<(String, Rating)>, String> {
+        let players = self.get_players();
+        let index = |name: &String| {
+            players
+                .iter()
+                .position(|player| player == name)
+                .ok_or_else(|| format!("Unknown player {}", name))
+        };
+
+        let rated_game = |game: &LadderGame| {
+            Ok(RatedGame {
+                player: index(&game.white)?,
+                opponent: index(&game.black)?,
+                score: game.score,
+            })
+        };
+
+        let ratings = match system {
+            RatingSystem::Elo => {
+                let games = self
+                    .games
+                    .iter()
+                    .map(rated_game)
+                    .collect::<Result<Vec<RatedGame>, String>>()?;
+                elo_ratings(players.len(), &games)
+            }
+            RatingSystem::Glicko2 => {
+                let mut periods: Vec<Vec<RatedGame>> = vec![Vec::new(); self.next_period() as usize];
+                for game in self.games.iter() {
+                    periods[game.period as usize].push(rated_game(game)?);
+                }
+                glicko2_ratings(players.len(), &periods)
+            }
+        };
+
+        Ok(players.into_iter().zip(ratings).collect())
+    }
+
+    pub fn leaderboard(&self, system: RatingSystem) -> Result<Leaderboard, String> {
+        let mut entries: Vec<LeaderboardEntry> = self
+            .ratings(system)?
+            .into_iter()
+            .map(|(name, rating)| {
+                let mut entry = LeaderboardEntry {
+                    name,
+                    rating,
+                    games: 0,
+                    points: 0.,
+                };
+
+                for game in self.games.iter() {
+                    if game.white == entry.name {
+                        entry.games += 1;
+                        entry.points += game.score;
+                    } else if game.black == entry.name {
+                        entry.games += 1;
+                        entry.points += 1. - game.score;
+                    }
+                }
+
+                entry
+            })
+            .collect();
+
+        entries.sort_by(|a, b| b.rating.rating.total_cmp(&a.rating.rating));
+
+        Ok(Leaderboard { system, entries })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub rating: Rating,
+    pub games: u32,
+    pub points: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Leaderboard {
+    pub system: RatingSystem,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl fmt::Display for Leaderboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name_width = self
+            .entries
+            .iter()
+            .map(|entry| entry.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+
+        writeln!(
+            f,
+            "{:>3} {:<name_width$} {:>7} {:>7} {:>6} {:>7}",
+            "#",
+            "Name",
+            format!("{:?}", self.system),
+            "95%",
+            "Games",
+            "Score"
+        )?;
+
+        for (rank, entry) in self.entries.iter().enumerate() {
+            let score = match entry.games {
+                0 => 0.,
+                games => 100. * entry.points / games as f64,
+            };
+
+            writeln!(
+                f,
+                "{:>3} {:<name_width$} {:>7.0} {:>7} {:>6} {:>6.1}%",
+                rank + 1,
+                entry.name,
+                entry.rating.rating,
+                format!("+/-{:.0}", entry.rating.interval),
+                entry.games,
+                score
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    fn games(games: &[(&str, &str, f64)]) -> Vec<(String, String, f64)> {
+        games
+            .iter()
+            .map(|&(white, black, score)| (white.to_owned(), black.to_owned(), score))
+            .collect()
+    }
+
+    #[test]
+    fn ladders_survive_a_round_trip_and_rank_the_winner_first() {
+        let mut ladder = RatingLadder::new();
+        ladder.add_match(&games(&[("a", "b", 1.), ("b", "a", 0.5)]));
+        ladder.add_match(&games(&[("a", "c", 1.), ("c", "b", 0.)]));
+
+        let path = env::temp_dir().join(format!("ladder_{}.csv", process::id()));
+        let path = path.to_str().unwrap();
+        ladder.save(path).unwrap();
+        let loaded = RatingLadder::load(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.games, ladder.games);
+        assert_eq!(loaded.next_period(), 2);
+        for system in [RatingSystem::Elo, RatingSystem::Glicko2] {
+            let leaderboard = loaded.leaderboard(system).unwrap();
+            let names: Vec<&str> = leaderboard
+                .entries
+                .iter()
+                .map(|entry| entry.name.as_str())
+                .collect();
+            assert_eq!(names, ["a", "b", "c"], "{:?}", system);
+        }
+    }
+
+    #[test]
+    fn hand_edited_scores_are_rejected() {
+        let path = env::temp_dir().join(format!("bad_ladder_{}.csv", process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, "period,white,black,score\n0,a,b,1\n0,b,a,2\n").unwrap();
+        let loaded = RatingLadder::load(path);
+        fs::remove_file(path).unwrap();
+
+        assert!(loaded.is_err());
+    }
+}
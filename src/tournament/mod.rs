@@ -0,0 +1,14 @@
+pub mod adjudication;
+pub mod crosstable;
+pub mod elo;
+pub mod ladder;
+pub mod openings;
+pub mod ratings;
+pub mod runner;
+pub mod sprt;
+
+pub use elo::MatchScore;
+pub use ladder::{RatingLadder, RatingSystem};
+pub use openings::OpeningBook;
+pub use runner::{Participant, Tournament, TournamentConfig, TournamentFormat};
+pub use sprt::SprtConfig;
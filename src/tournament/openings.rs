@@ -0,0 +1,282 @@
+use crate::chess_server::chess_types::ChessBoard;
+use crate::chess_server::io::utils::parse_san;
+
+use log::info;
+
+use std::fs;
+use std::path::Path;
+
+/// Position the games of a pairing start from.
+#[derive(Clone, Debug)]
+pub struct Opening {
+    pub name: String,
+    pub chess_board: ChessBoard,
+}
+
+impl Opening {
+    pub fn new(name: &str, chess_board: ChessBoard) -> Opening {
+        Opening {
+            name: name.to_owned(),
+            chess_board,
+        }
+    }
+}
+
+/// Suite of openings, read from FEN, EPD or PGN files.
+///
+/// Openings are handed out in order and the suite starts over once every
+/// opening has been used.
+#[derive(Clone, Debug)]
+pub struct OpeningBook {
+    openings: Vec<Opening>,
+}
+
+impl OpeningBook {
+    pub fn new(openings: Vec<Opening>) -> Result<OpeningBook, String> {
+        if openings.is_empty() {
+            return Err(String::from("The opening suite is empty"));
+        }
+
+        Ok(OpeningBook { openings })
+    }
+
+    pub fn starting_position() -> OpeningBook {
+        OpeningBook {
+            openings: vec![Opening::new("startpos", ChessBoard::starting_position())],
+        }
+    }
+
+    /// The format is taken from the extension, `.epd`, `.pgn` or else one FEN
+    /// per line.
+    pub fn from_file(path: &str) -> Result<OpeningBook, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("epd") => OpeningBook::from_epd_str(&text),
+            Some("pgn") => OpeningBook::from_pgn_str(&text),
+            _ => OpeningBook::from_fen_str(&text),
+        }
+    }
+
+    // One FEN per line, empty lines and lines starting with # are skipped
+    pub fn from_fen_str(text: &str) -> Result<OpeningBook, String> {
+        let openings = suite_lines(text)
+            .map(|line| Ok(Opening::new(line, ChessBoard::from_fen(line)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+
+        OpeningBook::new(openings)
+    }
+
+    /// One EPD record per line. The `id` operation, when there is one, names
+    /// the opening.
+    pub fn from_epd_str(text: &str) -> Result<OpeningBook, String> {
+        let mut openings = Vec::new();
+
+        for line in suite_lines(text) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 4 {
+                return Err(format!("Invalid EPD: {}", line));
+            }
+
+            let position = fields[..4].join(" ");
+            let operations = fields[4..].join(" ");
+            let name = epd_operation(&operations, "id").unwrap_or(&position);
+
+            openings.push(Opening::new(name, ChessBoard::from_fen(&position)?));
+        }
+
+        OpeningBook::new(openings)
+    }
+
+    /// Every game of the PGN is an opening, starting from its `FEN` tag or
+    /// from the starting position. The movetext is played until its first
+    /// move that can't be played on this board, such as castling.
+    pub fn from_pgn_str(text: &str) -> Result<OpeningBook, String> {
+        let mut openings = Vec::new();
+        let mut tags: Vec<(String, String)> = Vec::new();
+        let mut movetext = String::new();
+
+        for line in text.lines().map(str::trim) {
+            if line.starts_with('[') {
+                if !movetext.trim().is_empty() {
+                    openings.push(pgn_opening(&tags, &movetext, openings.len())?);
+                    tags.clear();
+                    movetext.clear();
+                }
+
+                if let Some(tag) = parse_pgn_tag(line) {
+                    tags.push(tag);
+                }
+            } else {
+                movetext += line;
+                movetext += " ";
+            }
+        }
+
+        if !movetext.trim().is_empty() || !tags.is_empty() {
+            openings.push(pgn_opening(&tags, &movetext, openings.len())?);
+        }
+
+        OpeningBook::new(openings)
+    }
+
+    pub fn len(&self) -> usize {
+        self.openings.len()
+    }
+
+    pub fn get(&self, index: usize) -> &Opening {
+        &self.openings[index % self.openings.len()]
+    }
+}
+
+fn suite_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+// Operand of an EPD operation such as `id "name";`
+fn epd_operation<'a>(operations: &'a str, opcode: &str) -> Option<&'a str> {
+    operations.split(';').find_map(|operation| {
+        let (name, operand) = operation.trim().split_once(' ')?;
+        (name == opcode).then(|| operand.trim().trim_matches('"'))
+    })
+}
+
+fn parse_pgn_tag(line: &str) -> Option<(String, String)> {
+    let line = line.trim_start_matches('[').trim_end_matches(']');
+    let (name, value) = line.split_once(' ')?;
+
+    Some((name.to_owned(), value.trim().trim_matches('"').to_owned()))
+}
+
+fn pgn_opening(
+    tags: &[(String, String)],
+    movetext: &str,
+    index: usize,
+) -> Result<Opening, String> {
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    let mut chess_board = match tag("FEN") {
+        Some(fen) => ChessBoard::from_fen(fen)?,
+        None => ChessBoard::starting_position(),
+    };
+
+    let default_name = format!("pgn #{}", index + 1);
+    let name = tag("Opening").or(tag("Event")).unwrap_or(&default_name);
+
+    for san in movetext_moves(movetext) {
+        match parse_san(&chess_board, &san) {
+            Some(mv) => chess_board = chess_board.next_state(&mv),
+            None => {
+                info!("Opening {} stops before {}", name, san);
+                break;
+            }
+        }
+    }
+
+    Ok(Opening::new(name, chess_board))
+}
+
+// Moves of the main line, without comments, variations, NAGs, move numbers
+// and results
+fn movetext_moves(movetext: &str) -> Vec<String> {
+    let mut cleaned = String::new();
+    let mut comment = false;
+    let mut variation_depth = 0;
+
+    for ch in movetext.chars() {
+        match ch {
+            '{' => comment = true,
+            '}' => comment = false,
+            '(' if !comment => variation_depth += 1,
+            ')' if !comment => variation_depth -= 1,
+            _ if comment || variation_depth > 0 => (),
+            _ => cleaned.push(ch),
+        }
+    }
+
+    cleaned
+        .split_whitespace()
+        .filter(|token| !["1-0", "0-1", "1/2-1/2", "*"].contains(token) && !token.starts_with('$'))
+        .map(|token| token.rsplit_once('.').map_or(token, |(_, mv)| mv))
+        .filter(|token| !token.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board_after(moves: &[&str]) -> ChessBoard {
+        moves
+            .iter()
+            .fold(ChessBoard::starting_position(), |chess_board, san| {
+                chess_board.next_state(&parse_san(&chess_board, san).unwrap())
+            })
+    }
+
+    #[test]
+    fn epd_openings_are_named_by_their_id() {
+        let epd = "# Two openings\n\
+            rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - id \"King's pawn\"; c0 \"1.e4\";\n\
+            \n\
+            rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq - hmvc 0;\n";
+        let book = OpeningBook::from_epd_str(epd).unwrap();
+
+        assert_eq!(book.len(), 2);
+        assert_eq!(book.get(0).name, "King's pawn");
+        assert_eq!(book.get(0).chess_board, board_after(&["e4"]));
+        assert_eq!(
+            book.get(1).name,
+            "rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq -"
+        );
+
+        // The suite starts over
+        assert_eq!(book.get(2).name, "King's pawn");
+
+        assert!(OpeningBook::from_epd_str("8/8/8/8 w").is_err());
+        assert!(OpeningBook::from_epd_str("# Nothing\n").is_err());
+        assert!(OpeningBook::new(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn pgn_openings_follow_the_main_line() {
+        let pgn = r#"[Event "Match"]
+[Opening "Italian"]
+
+1. e4 {the best} e5 (1... c5 2. Nf3) 2. Nf3 $1 Nc6
+3. Bc4 1-0
+
+[Event "From a position"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+
+1. e4 Kd7 *
+"#;
+        let book = OpeningBook::from_pgn_str(pgn).unwrap();
+
+        assert_eq!(book.len(), 2);
+        assert_eq!(book.get(0).name, "Italian");
+        assert_eq!(
+            book.get(0).chess_board,
+            board_after(&["e4", "e5", "Nf3", "Nc6", "Bc4"])
+        );
+
+        let chess_board = ChessBoard::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        let chess_board = chess_board.next_state(&parse_san(&chess_board, "e4").unwrap());
+        let chess_board = chess_board.next_state(&parse_san(&chess_board, "Kd7").unwrap());
+        assert_eq!(book.get(1).name, "From a position");
+        assert_eq!(book.get(1).chess_board, chess_board);
+    }
+
+    #[test]
+    fn movetext_keeps_only_the_moves() {
+        let movetext = "1. d4 $2 {a comment} d5 (1... Nf6 (1... e6) 2. c4) 2.c4 1/2-1/2";
+        assert_eq!(movetext_moves(movetext), ["d4", "d5", "c4"]);
+    }
+}
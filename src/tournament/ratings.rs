@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::f64::consts::{LN_10, PI};
+
+// Ratings are centered like Glicko ratings, so that both systems can be
+// compared on the leaderboard
+pub const BASE_RATING: f64 = 1500.;
+
+// Two sided 95% confidence
+const CONFIDENCE_Z: f64 = 1.959964;
+
+const ELO_ITERATIONS: usize = 1000;
+const ELO_TOLERANCE: f64 = 1e-9;
+
+const GLICKO_SCALE: f64 = 173.7178;
+const GLICKO_INITIAL_RD: f64 = 350.;
+const GLICKO_INITIAL_VOLATILITY: f64 = 0.06;
+// Constrains the change of volatility over time
+const GLICKO_TAU: f64 = 0.5;
+const GLICKO_TOLERANCE: f64 = 1e-6;
+
+/// A game between two players, `score` being the points of the first one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RatedGame {
+    pub player: usize,
+    pub opponent: usize,
+    pub score: f64,
+}
+
+/// Rating of a player, with the half width of its 95% confidence interval.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rating {
+    pub rating: f64,
+    pub interval: f64,
+}
+
+/// Elo ratings that best explain all the games at once (Bradley-Terry model).
+///
+/// Every player gets a virtual draw against a `BASE_RATING` player, which
+/// keeps the ratings finite for players that won or lost all their games.
+pub fn elo_ratings(player_count: usize, games: &[RatedGame]) -> Vec<Rating> {
+    let mut points = vec![0.5; player_count];
+    let mut game_counts = vec![vec![0.; player_count]; player_count];
+
+    for game in games {
+        points[game.player] += game.score;
+        points[game.opponent] += 1. - game.score;
+        game_counts[game.player][game.opponent] += 1.;
+        game_counts[game.opponent][game.player] += 1.;
+    }
+
+    // Minorization-maximization updates of the strengths, the virtual
+    // opponent has strength 1
+    let mut strengths = vec![1.; player_count];
+    for _ in 0..ELO_ITERATIONS {
+        let mut change: f64 = 0.;
+
+        for player in 0..player_count {
+            let mut denominator = 1. / (strengths[player] + 1.);
+            for opponent in 0..player_count {
+                denominator +=
+                    game_counts[player][opponent] / (strengths[player] + strengths[opponent]);
+            }
+
+            let strength = points[player] / denominator;
+            change = change.max((strength / strengths[player]).ln().abs());
+            strengths[player] = strength;
+        }
+
+        if change < ELO_TOLERANCE {
+            break;
+        }
+    }
+
+    let expected = |a: f64, b: f64| a / (a + b);
+
+    (0..player_count)
+        .map(|player| {
+            let strength = strengths[player];
+
+            let mut information = expected(strength, 1.) * expected(1., strength);
+            for opponent in 0..player_count {
+                let probability = expected(strength, strengths[opponent]);
+                information += game_counts[player][opponent] * probability * (1. - probability);
+            }
+
+            let elo_per_unit = 400. / LN_10;
+            Rating {
+                rating: BASE_RATING + elo_per_unit * strength.ln(),
+                interval: CONFIDENCE_Z * elo_per_unit / information.sqrt(),
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug)]
+struct GlickoPlayer {
+    mu: f64,
+    phi: f64,
+    sigma: f64,
+}
+
+impl GlickoPlayer {
+    fn new() -> GlickoPlayer {
+        GlickoPlayer {
+            mu: 0.,
+            phi: GLICKO_INITIAL_RD / GLICKO_SCALE,
+            sigma: GLICKO_INITIAL_VOLATILITY,
+        }
+    }
+}
+
+fn glicko_g(phi: f64) -> f64 {
+    1. / (1. + 3. * phi * phi / (PI * PI)).sqrt()
+}
+
+fn glicko_expected(mu: f64, opponent_mu: f64, opponent_phi: f64) -> f64 {
+    1. / (1. + (-glicko_g(opponent_phi) * (mu - opponent_mu)).exp())
+}
+
+// New volatility, by the Illinois algorithm of the Glicko-2 paper
+fn glicko_volatility(player: &GlickoPlayer, delta: f64, variance: f64) -> f64 {
+    let phi2 = player.phi * player.phi;
+    let a = (player.sigma * player.sigma).ln();
+    let tau2 = GLICKO_TAU * GLICKO_TAU;
+
+    let f = |x: f64| {
+        let ex = x.exp();
+        ex * (delta * delta - phi2 - variance - ex) / (2. * (phi2 + variance + ex).powi(2))
+            - (x - a) / tau2
+    };
+
+    let mut lower = a;
+    let mut upper = if delta * delta > phi2 + variance {
+        (delta * delta - phi2 - variance).ln()
+    } else {
+        let mut k = 1.;
+        while f(a - k * GLICKO_TAU) < 0. {
+            k += 1.;
+        }
+        a - k * GLICKO_TAU
+    };
+
+    let (mut f_lower, mut f_upper) = (f(lower), f(upper));
+    while (upper - lower).abs() > GLICKO_TOLERANCE {
+        let candidate = lower + (lower - upper) * f_lower / (f_upper - f_lower);
+        let f_candidate = f(candidate);
+
+        if f_candidate * f_upper <= 0. {
+            lower = upper;
+            f_lower = f_upper;
+        } else {
+            f_lower /= 2.;
+        }
+
+        upper = candidate;
+        f_upper = f_candidate;
+    }
+
+    (lower / 2.).exp()
+}
+
+// Player after a rating period with these games against the opponents as
+// they were at the start of the period
+fn glicko_update(player: &GlickoPlayer, games: &[(GlickoPlayer, f64)]) -> GlickoPlayer {
+    let mut inverse_variance = 0.;
+    let mut improvement = 0.;
+    for (opponent, score) in games {
+        let g = glicko_g(opponent.phi);
+        let expected = glicko_expected(player.mu, opponent.mu, opponent.phi);
+
+        inverse_variance += g * g * expected * (1. - expected);
+        improvement += g * (score - expected);
+    }
+
+    let variance = 1. / inverse_variance;
+    let sigma = glicko_volatility(player, variance * improvement, variance);
+
+    let phi_star = (player.phi * player.phi + sigma * sigma).sqrt();
+    let phi = 1. / (1. / (phi_star * phi_star) + inverse_variance).sqrt();
+
+    GlickoPlayer {
+        mu: player.mu + phi * phi * improvement,
+        phi,
+        sigma,
+    }
+}
+
+/// Glicko-2 ratings, updated once per rating period.
+///
+/// `periods` are played in order. Players start with the default rating
+/// deviation, which shrinks as they play and grows again in the periods
+/// they sit out.
+pub fn glicko2_ratings(player_count: usize, periods: &[Vec<RatedGame>]) -> Vec<Rating> {
+    let mut players = vec![GlickoPlayer::new(); player_count];
+    let mut seen = vec![false; player_count];
+
+    for period in periods {
+        // Games of every player, from its point of view
+        let mut results: HashMap<usize, Vec<(usize, f64)>> = HashMap::new();
+        for game in period {
+            results.entry(game.player).or_default().push((game.opponent, game.score));
+            results.entry(game.opponent).or_default().push((game.player, 1. - game.score));
+            seen[game.player] = true;
+            seen[game.opponent] = true;
+        }
+
+        let previous = players.clone();
+        for (index, player) in players.iter_mut().enumerate() {
+            if !seen[index] {
+                continue;
+            }
+
+            let Some(games) = results.get(&index) else {
+                let phi = (player.phi * player.phi + player.sigma * player.sigma).sqrt();
+                player.phi = phi.min(GLICKO_INITIAL_RD / GLICKO_SCALE);
+                continue;
+            };
+
+            let games: Vec<(GlickoPlayer, f64)> = games
+                .iter()
+                .map(|&(opponent, score)| (previous[opponent], score))
+                .collect();
+            *player = glicko_update(player, &games);
+        }
+    }
+
+    players
+        .iter()
+        .map(|player| Rating {
+            rating: BASE_RATING + GLICKO_SCALE * player.mu,
+            interval: CONFIDENCE_Z * GLICKO_SCALE * player.phi,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glicko_player(rating: f64, deviation: f64) -> GlickoPlayer {
+        GlickoPlayer {
+            mu: (rating - BASE_RATING) / GLICKO_SCALE,
+            phi: deviation / GLICKO_SCALE,
+            sigma: GLICKO_INITIAL_VOLATILITY,
+        }
+    }
+
+    // The example of Glickman's "Example of the Glicko-2 system"
+    #[test]
+    fn glicko2_matches_the_paper_example() {
+        let player = glicko_player(1500., 200.);
+        let games = [
+            (glicko_player(1400., 30.), 1.),
+            (glicko_player(1550., 100.), 0.),
+            (glicko_player(1700., 300.), 0.),
+        ];
+
+        let player = glicko_update(&player, &games);
+
+        assert!((BASE_RATING + GLICKO_SCALE * player.mu - 1464.06).abs() < 0.01);
+        assert!((GLICKO_SCALE * player.phi - 151.52).abs() < 0.01);
+        assert!((player.sigma - 0.05999).abs() < 1e-5);
+    }
+
+    #[test]
+    fn glicko2_deviation_grows_while_sitting_out() {
+        let game = RatedGame {
+            player: 0,
+            opponent: 1,
+            score: 1.,
+        };
+        let played = glicko2_ratings(3, &[vec![game], vec![game]]);
+        let sat_out = glicko2_ratings(3, &[vec![game], Vec::new()]);
+
+        assert!(played[0].rating > played[1].rating);
+        assert!(sat_out[0].interval > played[0].interval);
+        // Never played, never rated
+        assert_eq!(played[2].rating, BASE_RATING);
+    }
+
+    #[test]
+    fn elo_ranks_winners_first_with_finite_intervals() {
+        let game = RatedGame {
+            player: 0,
+            opponent: 1,
+            score: 1.,
+        };
+        let ratings = elo_ratings(2, &[game; 3]);
+
+        assert!(ratings[0].rating > ratings[1].rating);
+        assert!((ratings[0].rating + ratings[1].rating - 2. * BASE_RATING).abs() < 1e-3);
+        for rating in ratings {
+            assert!(rating.rating.is_finite());
+            assert!(rating.interval.is_finite() && rating.interval > 0.);
+        }
+    }
+}
@@ -0,0 +1,594 @@
+use crate::chess_server::chess_types::ChessStatus;
+use crate::chess_server::clock::TimeControl;
+use crate::chess_server::game::{GameManager, Player};
+use crate::chess_server::game_events::GameOverReason;
+use crate::engines::bots::{find_bot, BotInfo, TimeControlClass};
+use crate::engines::engine_config::BotConfig;
+
+use super::adjudication::{Adjudication, AdjudicationConfig, Adjudicator};
+use super::crosstable::Crosstable;
+use super::elo::MatchScore;
+use super::openings::{Opening, OpeningBook};
+use super::sprt::{SprtConfig, SprtResults, SprtStatus};
+
+use log::warn;
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+pub type PlayerFactory = Box<dyn Fn() -> Box<dyn Player> + Send + Sync>;
+
+/// A named player of the tournament.
+///
+/// Players keep state between moves and can't be shared between threads,
+/// so a fresh one is built for every game. `bot` is the entry of the
+/// registry the participant comes from, if any.
+pub struct Participant {
+    pub name: String,
+    pub bot: Option<&'static BotInfo>,
+    factory: PlayerFactory,
+}
+
+impl Participant {
+    pub fn new<P, F>(name: &str, factory: F) -> Participant
+    where
+        P: Player + 'static,
+        F: Fn() -> P + Send + Sync + 'static,
+    {
+        Participant {
+            name: name.to_owned(),
+            bot: None,
+            factory: Box::new(move || Box::new(factory())),
+        }
+    }
+
+    pub fn from_bot(bot: &'static BotInfo) -> Participant {
+        Participant {
+            bot: Some(bot),
+            ..Participant::new(bot.name, bot.constructor)
+        }
+    }
+
+    // Bot of the registry with that name
+    pub fn from_name(name: &str) -> Option<Participant> {
+        find_bot(name).map(Participant::from_bot)
+    }
+
+    // The config is built once here, so that errors show up before the games
+    pub fn from_config(config: BotConfig) -> Result<Participant, String> {
+        config.build()?;
+
+        let name = config.name.clone();
+        Ok(Participant::new(&name, move || config.build().unwrap()))
+    }
+
+    pub fn create_player(&self) -> Box<dyn Player> {
+        (self.factory)()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TournamentFormat {
+    // The first participant plays every other one
+    Gauntlet,
+    RoundRobin,
+}
+
+/// `rounds` is the number of openings every pairing plays. With
+/// `swap_colors` each opening is played twice, once with each color.
+#[derive(Clone, Copy, Debug)]
+pub struct TournamentConfig {
+    pub format: TournamentFormat,
+    pub rounds: usize,
+    pub swap_colors: bool,
+    pub threads: usize,
+    pub time_control: Option<TimeControl>,
+    pub adjudication: AdjudicationConfig,
+}
+
+impl Default for TournamentConfig {
+    fn default() -> Self {
+        TournamentConfig {
+            format: TournamentFormat::RoundRobin,
+            rounds: 1,
+            swap_colors: true,
+            threads: 1,
+            time_control: None,
+            adjudication: AdjudicationConfig::default(),
+        }
+    }
+}
+
+/// A game of the schedule. The two games played on the same opening with
+/// swapped colors share their `pair`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScheduledGame {
+    pub pair: usize,
+    pub white: usize,
+    pub black: usize,
+    pub opening: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GameResult {
+    pub game: ScheduledGame,
+    pub status: ChessStatus,
+    pub reason: GameOverReason,
+    pub adjudication: Option<Adjudication>,
+    pub plies: usize,
+}
+
+impl GameResult {
+    pub fn white_points(&self) -> f64 {
+        match self.status {
+            ChessStatus::WhiteWon => 1.,
+            ChessStatus::BlackWon => 0.,
+            ChessStatus::Draw | ChessStatus::Ongoing => 0.5,
+        }
+    }
+
+    pub fn result_string(&self) -> &'static str {
+        match self.status {
+            ChessStatus::WhiteWon => "1-0",
+            ChessStatus::BlackWon => "0-1",
+            ChessStatus::Draw | ChessStatus::Ongoing => "1/2-1/2",
+        }
+    }
+}
+
+/// Plays one game from the opening, ending it early when the adjudicator
+/// says so.
+pub fn play_game(
+    player_white: &dyn Player,
+    player_black: &dyn Player,
+    opening: &Opening,
+    time_control: Option<TimeControl>,
+    adjudicator: &Adjudicator,
+) -> (ChessStatus, GameOverReason, Option<Adjudication>, usize) {
+    let mut game_manager = match time_control {
+        Some(time_control) => GameManager::with_position_and_time_control(
+            player_white,
+            player_black,
+            opening.chess_board,
+            time_control,
+        ),
+        None => GameManager::with_position(player_white, player_black, opening.chess_board),
+    };
+
+    let mut adjudication = None;
+
+    while game_manager.is_game_ongoing() {
+        game_manager.make_move();
+
+        if game_manager.is_game_ongoing() {
+            let verdict =
+                adjudicator.adjudicate(&game_manager.get_board(), game_manager.get_records());
+
+            if let Some((status, reason)) = verdict {
+                adjudication = Some(reason);
+                game_manager.adjudicate(status);
+            }
+        }
+    }
+
+    // A game over position given as opening ends without a reason
+    let reason = game_manager
+        .get_game_over_reason()
+        .unwrap_or(match game_manager.get_game_status() {
+            ChessStatus::Draw => GameOverReason::Stalemate,
+            _ => GameOverReason::Checkmate,
+        });
+
+    (
+        game_manager.get_game_status(),
+        reason,
+        adjudication,
+        game_manager.get_ply(),
+    )
+}
+
+/// Gauntlet or round robin between participants, played on several threads.
+pub struct Tournament {
+    participants: Vec<Participant>,
+    openings: OpeningBook,
+    config: TournamentConfig,
+}
+
+impl Tournament {
+    pub fn new(
+        participants: Vec<Participant>,
+        openings: OpeningBook,
+        config: TournamentConfig,
+    ) -> Tournament {
+        // The games are still played, bots that are too slow just flag
+        if let Some(time_control) = config.time_control {
+            for participant in &participants {
+                if participant.bot.is_some_and(|bot| !bot.suits(&time_control)) {
+                    warn!(
+                        "{} is too slow for {:?}",
+                        participant.name,
+                        TimeControlClass::from_time_control(&time_control)
+                    );
+                }
+            }
+        }
+
+        Tournament {
+            participants,
+            openings,
+            config,
+        }
+    }
+
+    fn pairings(&self) -> Vec<(usize, usize)> {
+        let count = self.participants.len();
+
+        match self.config.format {
+            TournamentFormat::Gauntlet => (1..count).map(|opponent| (0, opponent)).collect(),
+            TournamentFormat::RoundRobin => (0..count)
+                .flat_map(|player| (player + 1..count).map(move |opponent| (player, opponent)))
+                .collect(),
+        }
+    }
+
+    pub fn schedule(&self) -> Vec<ScheduledGame> {
+        let mut games = Vec::new();
+        let mut pair = 0;
+
+        for round in 0..self.config.rounds {
+            let opening = round % self.openings.len();
+
+            for (player, opponent) in self.pairings() {
+                games.push(ScheduledGame {
+                    pair,
+                    white: player,
+                    black: opponent,
+                    opening,
+                });
+
+                if self.config.swap_colors {
+                    games.push(ScheduledGame {
+                        pair,
+                        white: opponent,
+                        black: player,
+                        opening,
+                    });
+                }
+
+                pair += 1;
+            }
+        }
+
+        games
+    }
+
+    pub fn play_scheduled_game(&self, game: &ScheduledGame) -> GameResult {
+        let player_white = self.participants[game.white].create_player();
+        let player_black = self.participants[game.black].create_player();
+
+        let (status, reason, adjudication, plies) = play_game(
+            player_white.as_ref(),
+            player_black.as_ref(),
+            self.openings.get(game.opening),
+            self.config.time_control,
+            &Adjudicator::new(self.config.adjudication),
+        );
+
+        GameResult {
+            game: *game,
+            status,
+            reason,
+            adjudication,
+            plies,
+        }
+    }
+
+    pub fn run(&self) -> TournamentResults {
+        let schedule = self.schedule();
+        let next_game = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(schedule.len()));
+
+        thread::scope(|scope| {
+            for _ in 0..self.config.threads.max(1) {
+                scope.spawn(|| loop {
+                    let index = next_game.fetch_add(1, Ordering::SeqCst);
+                    let Some(game) = schedule.get(index) else {
+                        break;
+                    };
+
+                    let result = self.play_scheduled_game(game);
+
+                    let mut results = results.lock().unwrap();
+                    results.push((index, result));
+                    self.print_progress(&result, results.len(), schedule.len());
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(index, _)| *index);
+
+        TournamentResults::new(
+            self.participants.iter().map(|participant| participant.name.clone()).collect(),
+            results.into_iter().map(|(_, result)| result).collect(),
+        )
+    }
+
+    /// Plays game pairs between the first participant, the tested one, and
+    /// the second one until the SPRT accepts one of its hypotheses. The
+    /// format and the number of rounds of the config are not used.
+    pub fn run_sprt(&self, sprt: SprtConfig) -> Result<SprtResults, String> {
+        if self.participants.len() != 2 {
+            return Err(format!(
+                "SPRT needs exactly two participants, got {}",
+                self.participants.len()
+            ));
+        }
+
+        let next_pair = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+        let results = Mutex::new(SprtResults::new(sprt));
+
+        thread::scope(|scope| {
+            for _ in 0..self.config.threads.max(1) {
+                scope.spawn(|| {
+                    while !stop.load(Ordering::SeqCst) {
+                        let pair = next_pair.fetch_add(1, Ordering::SeqCst);
+                        if sprt.max_pairs.is_some_and(|max_pairs| pair >= max_pairs) {
+                            break;
+                        }
+
+                        let opening = pair % self.openings.len();
+                        let first = self.play_scheduled_game(&ScheduledGame {
+                            pair,
+                            white: 0,
+                            black: 1,
+                            opening,
+                        });
+                        let second = self.play_scheduled_game(&ScheduledGame {
+                            pair,
+                            white: 1,
+                            black: 0,
+                            opening,
+                        });
+
+                        let mut results = results.lock().unwrap();
+                        // Pairs still running when the test ended don't count
+                        if results.status != SprtStatus::Running {
+                            break;
+                        }
+
+                        results.add_pair(first.white_points(), 1. - second.white_points());
+                        let finished = 2 * results.pentanomial.pairs() as usize;
+                        self.print_progress(&first, finished - 1, 0);
+                        self.print_progress(&second, finished, 0);
+                        println!("{}", results);
+
+                        if results.status != SprtStatus::Running {
+                            stop.store(true, Ordering::SeqCst);
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(results.into_inner().unwrap())
+    }
+
+    // Without a total, the number of games isn't known in advance
+    fn print_progress(&self, result: &GameResult, finished: usize, total: usize) {
+        let reason = match result.adjudication {
+            Some(adjudication) => format!("{:?}", adjudication),
+            None => format!("{:?}", result.reason),
+        };
+
+        let progress = match total {
+            0 => finished.to_string(),
+            _ => format!("{}/{}", finished, total),
+        };
+
+        println!(
+            "Game {}: {} - {} {} ({}, {} plies, {})",
+            progress,
+            self.participants[result.game.white].name,
+            self.participants[result.game.black].name,
+            result.result_string(),
+            reason,
+            result.plies,
+            self.openings.get(result.game.opening).name,
+        );
+    }
+}
+
+pub struct TournamentResults {
+    pub games: Vec<GameResult>,
+    pub crosstable: Crosstable,
+}
+
+impl TournamentResults {
+    pub fn new(names: Vec<String>, games: Vec<GameResult>) -> TournamentResults {
+        let mut crosstable = Crosstable::new(names);
+        for result in games.iter() {
+            crosstable.add_game(result.game.white, result.game.black, result.white_points());
+        }
+
+        TournamentResults { games, crosstable }
+    }
+
+    pub fn match_score(&self, player: usize, opponent: usize) -> MatchScore {
+        self.crosstable.get(player, opponent)
+    }
+}
+
+impl fmt::Display for TournamentResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self.crosstable.get_names();
+
+        for player in 0..names.len() {
+            for opponent in player + 1..names.len() {
+                let score = self.match_score(player, opponent);
+                if score.games() > 0 {
+                    writeln!(f, "{} vs {}: {}", names[player], names[opponent], score)?;
+                }
+            }
+        }
+
+        writeln!(f)?;
+        write!(f, "{}", self.crosstable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_server::chess_types::{ChessBoard, Color};
+    use crate::chess_server::game::{Decision, GameContext};
+    use crate::engines::random_engine::RandomEngine;
+
+    use std::sync::Arc;
+
+    // Resigns at once, noting its color and the opening of the game
+    struct Resigner {
+        games: Arc<Mutex<Vec<(Color, ChessBoard)>>>,
+    }
+
+    impl Player for Resigner {
+        fn decide(&self, context: &GameContext) -> Decision {
+            let opening = context.history.first().unwrap_or(&context.chess_board);
+            self.games.lock().unwrap().push((context.get_turn_color(), *opening));
+            Decision::Resign
+        }
+    }
+
+    fn tournament(players: usize, openings: usize, config: TournamentConfig) -> Tournament {
+        let participants = (0..players)
+            .map(|player| Participant::new(&player.to_string(), RandomEngine::new))
+            .collect();
+        let openings = (0..openings)
+            .map(|opening| Opening::new(&opening.to_string(), ChessBoard::starting_position()))
+            .collect();
+
+        Tournament::new(participants, OpeningBook::new(openings).unwrap(), config)
+    }
+
+    // (white, black, opening) of every game
+    fn games(tournament: &Tournament) -> Vec<(usize, usize, usize)> {
+        tournament
+            .schedule()
+            .iter()
+            .map(|game| (game.white, game.black, game.opening))
+            .collect()
+    }
+
+    #[test]
+    fn gauntlets_pair_the_first_participant_with_the_others() {
+        let config = TournamentConfig {
+            format: TournamentFormat::Gauntlet,
+            rounds: 3,
+            swap_colors: false,
+            ..TournamentConfig::default()
+        };
+        let tournament = tournament(3, 2, config);
+
+        // Openings start over once used
+        let expected = [(0, 1, 0), (0, 2, 0), (0, 1, 1), (0, 2, 1), (0, 1, 0), (0, 2, 0)];
+        assert_eq!(games(&tournament), expected);
+    }
+
+    #[test]
+    fn round_robins_play_every_pairing_with_both_colors() {
+        let config = TournamentConfig {
+            format: TournamentFormat::RoundRobin,
+            rounds: 2,
+            swap_colors: true,
+            ..TournamentConfig::default()
+        };
+        let tournament = tournament(3, 2, config);
+
+        let expected = [
+            (0, 1, 0),
+            (1, 0, 0),
+            (0, 2, 0),
+            (2, 0, 0),
+            (1, 2, 0),
+            (2, 1, 0),
+            (0, 1, 1),
+            (1, 0, 1),
+            (0, 2, 1),
+            (2, 0, 1),
+            (1, 2, 1),
+            (2, 1, 1),
+        ];
+        assert_eq!(games(&tournament), expected);
+
+        // Both games on an opening share their pair
+        let pairs: Vec<usize> = tournament.schedule().iter().map(|game| game.pair).collect();
+        assert_eq!(pairs, [0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5]);
+    }
+
+    #[test]
+    fn sprt_needs_two_participants() {
+        let tournament = tournament(3, 1, TournamentConfig::default());
+        assert!(tournament.run_sprt(SprtConfig::new(0., 5., 0.05, 0.05)).is_err());
+    }
+
+    #[test]
+    fn tournaments_play_the_schedule() {
+        let start = ChessBoard::starting_position();
+        let e4 = ChessBoard::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+            .unwrap();
+        let openings = vec![Opening::new("start", start), Opening::new("e4", e4)];
+
+        let games = Arc::new(Mutex::new(Vec::new()));
+        let resigner_games = games.clone();
+        let participants = vec![
+            Participant::new("random", RandomEngine::new),
+            Participant::new("resigner", move || Resigner {
+                games: resigner_games.clone(),
+            }),
+        ];
+        let config = TournamentConfig {
+            rounds: 2,
+            ..TournamentConfig::default()
+        };
+        let tournament =
+            Tournament::new(participants, OpeningBook::new(openings).unwrap(), config);
+
+        let results = tournament.run();
+
+        // Each opening is played with both colors, the games in schedule order
+        let expected = [
+            (Color::Black, start),
+            (Color::White, start),
+            (Color::Black, e4),
+            (Color::White, e4),
+        ];
+        assert_eq!(*games.lock().unwrap(), expected);
+
+        let statuses: Vec<ChessStatus> = results.games.iter().map(|game| game.status).collect();
+        let expected = [
+            ChessStatus::WhiteWon,
+            ChessStatus::BlackWon,
+            ChessStatus::WhiteWon,
+            ChessStatus::BlackWon,
+        ];
+        assert_eq!(statuses, expected);
+        assert!(results
+            .games
+            .iter()
+            .all(|game| game.reason == GameOverReason::Resignation));
+
+        let score = results.match_score(0, 1);
+        assert_eq!((score.wins, score.draws, score.losses), (4, 0, 0));
+        assert_eq!(results.match_score(1, 0), score.reversed());
+        assert_eq!(results.crosstable.ranking(), [0, 1]);
+        assert!(score.elo_difference() > 0.);
+
+        let output = results.to_string();
+        assert!(output.contains("random vs resigner: +4 =0 -0 (4.0/4), Elo +inf"));
+        assert!(output.contains("1 random      4.0     4          4/0/0"));
+        assert!(output.contains("2 resigner    0.0     4          0/0/4"));
+    }
+}
@@ -0,0 +1,228 @@
+use super::elo::{elo_to_score, MatchScore};
+
+use std::fmt;
+
+// Prior count added to every pair outcome. Without it a few pairs with the
+// same result have almost no variance and the test stops right away.
+const PRIOR_COUNT: f64 = 0.5;
+
+/// Sequential probability ratio test between `elo0`, the null hypothesis,
+/// and `elo1`. `alpha` and `beta` are the false positive and false negative
+/// rates. The test gives up after `max_pairs` game pairs, if set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SprtConfig {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    pub max_pairs: Option<usize>,
+}
+
+impl SprtConfig {
+    pub fn new(elo0: f64, elo1: f64, alpha: f64, beta: f64) -> SprtConfig {
+        SprtConfig {
+            elo0,
+            elo1,
+            alpha,
+            beta,
+            max_pairs: None,
+        }
+    }
+
+    // (lower, upper) bounds of the log likelihood ratio
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1. - self.alpha)).ln(),
+            ((1. - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    pub fn status(&self, llr: f64) -> SprtStatus {
+        let (lower, upper) = self.bounds();
+
+        if llr >= upper {
+            SprtStatus::H1Accepted
+        } else if llr <= lower {
+            SprtStatus::H0Accepted
+        } else {
+            SprtStatus::Running
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SprtStatus {
+    Running,
+    // The change is at least elo1 better
+    H1Accepted,
+    // The change is at most elo0 better
+    H0Accepted,
+}
+
+/// Results of game pairs, counted by the points of the tested player over
+/// the pair: 0, 0.5, 1, 1.5 and 2.
+///
+/// Both games of a pair start from the same opening with swapped colors, so
+/// looking at pairs instead of games takes out most of the opening bias.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Pentanomial {
+    pub counts: [u32; 5],
+}
+
+impl Pentanomial {
+    pub fn new() -> Pentanomial {
+        Pentanomial::default()
+    }
+
+    pub fn add_pair(&mut self, first_points: f64, second_points: f64) {
+        let index = ((first_points + second_points) * 2.).round() as usize;
+        self.counts[index.min(4)] += 1;
+    }
+
+    pub fn pairs(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    /// Log likelihood ratio of `elo1` against `elo0`, with the normal
+    /// approximation of the pair score distribution.
+    pub fn llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let pairs = self.pairs() as f64;
+        if pairs == 0. {
+            return 0.;
+        }
+
+        let counts: Vec<f64> = self
+            .counts
+            .iter()
+            .map(|&count| count as f64 + PRIOR_COUNT)
+            .collect();
+        let total: f64 = counts.iter().sum();
+
+        // Pair scores are scaled to [0, 1] like game scores
+        let scores = [0., 0.25, 0.5, 0.75, 1.];
+
+        let mean: f64 = counts.iter().zip(scores).map(|(count, score)| count * score).sum::<f64>()
+            / total;
+        let variance: f64 = counts
+            .iter()
+            .zip(scores)
+            .map(|(count, score)| count * (score - mean).powi(2))
+            .sum::<f64>()
+            / total;
+
+        let (score0, score1) = (elo_to_score(elo0), elo_to_score(elo1));
+
+        pairs * (score1 - score0) * (2. * mean - score0 - score1) / (2. * variance)
+    }
+}
+
+impl fmt::Display for Pentanomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}, {}, {}, {}, {}]",
+            self.counts[0], self.counts[1], self.counts[2], self.counts[3], self.counts[4]
+        )
+    }
+}
+
+/// State of the test after the last finished pair.
+#[derive(Clone, Copy, Debug)]
+pub struct SprtResults {
+    pub config: SprtConfig,
+    pub status: SprtStatus,
+    pub llr: f64,
+    pub pentanomial: Pentanomial,
+    pub score: MatchScore,
+}
+
+impl SprtResults {
+    pub fn new(config: SprtConfig) -> SprtResults {
+        SprtResults {
+            config,
+            status: SprtStatus::Running,
+            llr: 0.,
+            pentanomial: Pentanomial::new(),
+            score: MatchScore::new(),
+        }
+    }
+
+    // Points of the tested player in both games of a pair
+    pub fn add_pair(&mut self, first_points: f64, second_points: f64) {
+        self.score.add(first_points);
+        self.score.add(second_points);
+        self.pentanomial.add_pair(first_points, second_points);
+
+        self.llr = self.pentanomial.llr(self.config.elo0, self.config.elo1);
+        self.status = self.config.status(self.llr);
+    }
+}
+
+impl fmt::Display for SprtResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (lower, upper) = self.config.bounds();
+
+        write!(
+            f,
+            "SPRT elo0 {} elo1 {}: LLR {:.2} ({:.2}, {:.2}) {:?}, pairs {} {}, {}, draws {:.1}%",
+            self.config.elo0,
+            self.config.elo1,
+            self.llr,
+            lower,
+            upper,
+            self.status,
+            self.pentanomial.pairs(),
+            self.pentanomial,
+            self.score,
+            100. * self.score.draw_ratio()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn llr_follows_the_normal_approximation() {
+        let pentanomial = Pentanomial {
+            counts: [10, 40, 120, 50, 15],
+        };
+
+        // With the prior the pair score has mean 0.52105 and variance
+        // 0.050873 over 237.5 pairs, and elo 5 is a score of 0.50720, so
+        // 235 * 0.00720 * (2 * 0.52105 - 0.5 - 0.50720) / (2 * 0.050873)
+        assert!((pentanomial.llr(0., 5.) - 0.58015).abs() < 1e-4);
+
+        // Mirrored results give the same evidence for the other side
+        let mirrored = Pentanomial {
+            counts: [15, 50, 120, 40, 10],
+        };
+        assert!(mirrored.llr(0., 5.) < 0.);
+        assert_eq!(Pentanomial::new().llr(0., 5.), 0.);
+    }
+
+    #[test]
+    fn hypotheses_are_accepted_at_the_bounds() {
+        let config = SprtConfig::new(0., 5., 0.05, 0.05);
+        let (lower, upper) = config.bounds();
+
+        assert!((upper - 19f64.ln()).abs() < 1e-12);
+        assert!((lower + 19f64.ln()).abs() < 1e-12);
+        assert_eq!(config.status(upper), SprtStatus::H1Accepted);
+        assert_eq!(config.status(lower), SprtStatus::H0Accepted);
+        assert_eq!(config.status(upper - 1e-9), SprtStatus::Running);
+        assert_eq!(config.status(lower + 1e-9), SprtStatus::Running);
+
+        // A much stronger engine passes, a much weaker one fails
+        let mut stronger = SprtResults::new(config);
+        let mut weaker = SprtResults::new(config);
+        while stronger.status == SprtStatus::Running {
+            stronger.add_pair(1., 0.5);
+            weaker.add_pair(0., 0.5);
+        }
+        assert_eq!(stronger.status, SprtStatus::H1Accepted);
+        assert_eq!(weaker.status, SprtStatus::H0Accepted);
+        assert!(stronger.llr >= upper);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::chess_server::chess_types::{ChessBoard, Color};
+use crate::engines::score::Score;
+
+use std::fs;
+
+/// What is known about a position: the result of the game it was played in,
+/// from 0 to 1 for white, or a search score in centipawns for white.
+///
+/// Score labels are read from the point of view of the side to move, as
+/// UCI engines report them, and turned to the point of view of white.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Label {
+    Result(f64),
+    Score(f64),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LabeledPosition {
+    pub chess_board: ChessBoard,
+    pub label: Label,
+}
+
+fn parse_label(label: &str, turn: Color) -> Option<Label> {
+    let label = label.trim().trim_matches(|ch| ch == '"' || ch == '[' || ch == ']');
+
+    match label {
+        "1-0" => return Some(Label::Result(1.)),
+        "0-1" => return Some(Label::Result(0.)),
+        "1/2-1/2" => return Some(Label::Result(0.5)),
+        _ => (),
+    }
+
+    if let Some(score) = label.strip_prefix("cp") {
+        let score: f64 = score.trim().parse().ok()?;
+        return Some(Label::Score(score * turn.as_sign()));
+    }
+
+    // Mates are labeled with their encoding, far beyond any evaluation
+    if label.starts_with("mate") {
+        let score = Score::from_uci(label).ok()?.relative(turn);
+        return Some(Label::Score(score.get_centipawns() as f64));
+    }
+
+    label
+        .parse()
+        .ok()
+        .filter(|result| (0. ..=1.).contains(result))
+        .map(Label::Result)
+}
+
+/// Reads a position and its label from a line, either `<fen>; <label>` with
+/// a label like `1-0`, `0.5`, `[1.0]`, `cp 35` or `mate 3`, or an EPD line
+/// with the result in a `c9` operation, e.g. `<epd> c9 "1/2-1/2";`.
+pub fn parse_labeled_line(line: &str) -> Result<LabeledPosition, String> {
+    let invalid = || format!("Invalid labeled position: {}", line);
+
+    let (fen, label) = match line.split_once(" c9 ") {
+        Some((epd, operations)) => (epd, operations.split(';').next().unwrap_or("")),
+        None => line.rsplit_once(';').ok_or_else(invalid)?,
+    };
+
+    let chess_board = ChessBoard::from_fen(fen.trim())?;
+
+    Ok(LabeledPosition {
+        chess_board,
+        label: parse_label(label, chess_board.get_turn_color()).ok_or_else(invalid)?,
+    })
+}
+
+// Empty lines and lines starting with # are skipped
+pub fn load_labeled_positions(path: &str) -> Result<Vec<LabeledPosition>, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            parse_labeled_line(line).map_err(|err| format!("{}:{}: {}", path, index + 1, err))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE_TO_MOVE: &str = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+    const BLACK_TO_MOVE: &str = "4k3/8/8/8/4P3/8/8/4K3 b - - 0 1";
+
+    fn label(line: &str) -> Label {
+        parse_labeled_line(line).unwrap().label
+    }
+
+    #[test]
+    fn reads_results_and_scores() {
+        assert_eq!(label(&format!("{}; 1-0", WHITE_TO_MOVE)), Label::Result(1.));
+        assert_eq!(label(&format!("{};\"0-1\"", WHITE_TO_MOVE)), Label::Result(0.));
+        assert_eq!(label(&format!("{}; [0.5]", WHITE_TO_MOVE)), Label::Result(0.5));
+
+        // Scores are turned to the point of view of white
+        assert_eq!(label(&format!("{}; cp 35", WHITE_TO_MOVE)), Label::Score(35.));
+        assert_eq!(label(&format!("{}; cp 35", BLACK_TO_MOVE)), Label::Score(-35.));
+
+        let mate = Score::mate_in(5).get_centipawns() as f64;
+        assert_eq!(label(&format!("{}; mate 3", WHITE_TO_MOVE)), Label::Score(mate));
+        assert_eq!(label(&format!("{}; mate 3", BLACK_TO_MOVE)), Label::Score(-mate));
+    }
+
+    #[test]
+    fn reads_the_result_of_epd_lines() {
+        let line = "4k3/8/8/8/4P3/8/8/4K3 b - - c9 \"1/2-1/2\"; c0 \"drawn\";";
+        let position = parse_labeled_line(line).unwrap();
+
+        assert_eq!(position.label, Label::Result(0.5));
+        assert_eq!(position.chess_board, ChessBoard::from_fen(BLACK_TO_MOVE).unwrap());
+    }
+
+    #[test]
+    fn rejects_lines_without_a_valid_label() {
+        for label in ["", "1.5", "-0.5", "2-0", "cp", "cp x", "mate"] {
+            let line = format!("{}; {}", WHITE_TO_MOVE, label);
+            assert!(parse_labeled_line(&line).is_err(), "{}", line);
+        }
+        assert!(parse_labeled_line(WHITE_TO_MOVE).is_err());
+        assert!(parse_labeled_line("not a fen; 1-0").is_err());
+    }
+}
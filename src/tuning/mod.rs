@@ -0,0 +1,5 @@
+pub mod dataset;
+pub mod texel;
+
+pub use dataset::load_labeled_positions;
+pub use texel::{TexelConfig, TexelTuner};
@@ -0,0 +1,517 @@
+use crate::chess_server::chess_types::{ChessBoard, Color, Piece};
+use crate::engines::evaluators::activity_evaluator::{
+    activity_counts, ActivityParams, ACTIVITY_TERMS,
+};
+use crate::engines::evaluators::pesto_evaluator::PestoParams;
+
+use super::dataset::{Label, LabeledPosition};
+
+use log::info;
+
+use std::f64::consts::LN_10;
+use std::thread;
+
+// Layout of the parameter vector, values then tables, then the middlegame
+// and endgame weights of every activity term
+const MG_VALUE: usize = 0;
+const EG_VALUE: usize = 6;
+const MG_TABLES: usize = 12;
+const EG_TABLES: usize = MG_TABLES + 6 * 64;
+const ACTIVITY: usize = EG_TABLES + 6 * 64;
+const PARAM_COUNT: usize = ACTIVITY + 2 * ACTIVITY_TERMS;
+
+// Game phase of every piece, as in the PestoEvaluator
+const PHASE_INCREMENTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+const MAX_PHASE: i32 = 24;
+
+// Scaling of an Elo difference, where 400 centipawns are ten times the odds
+const DEFAULT_SCALING: f64 = LN_10 / 400.;
+const SCALING_BOUNDS: (f64, f64) = (1e-4, 0.05);
+const SCALING_ITERATIONS: usize = 100;
+
+const ADAM_BETA1: f64 = 0.9;
+const ADAM_BETA2: f64 = 0.999;
+const ADAM_EPSILON: f64 = 1e-8;
+
+/// `learning_rate` is in centipawns. Without `fit_scaling` the sigmoid maps
+/// scores to results like Elo differences.
+#[derive(Clone, Copy, Debug)]
+pub struct TexelConfig {
+    pub epochs: usize,
+    pub learning_rate: f64,
+    pub threads: usize,
+    pub fit_scaling: bool,
+}
+
+impl Default for TexelConfig {
+    fn default() -> Self {
+        TexelConfig {
+            epochs: 500,
+            learning_rate: 1.,
+            threads: 1,
+            fit_scaling: true,
+        }
+    }
+}
+
+fn piece_index(piece: Piece) -> usize {
+    match piece {
+        Piece::Pawn => 0,
+        Piece::Knight => 1,
+        Piece::Bishop => 2,
+        Piece::Rook => 3,
+        Piece::Queen => 4,
+        Piece::King => 5,
+    }
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1. / (1. + (-x).exp())
+}
+
+/// The PeSTO and activity evaluations are linear in their parameters once
+/// the game phase is known, so a position is kept as the coefficient of
+/// every parameter it uses, from the point of view of white.
+fn features(chess_board: &ChessBoard, activity: bool) -> Vec<(u16, f32)> {
+    let mut phase = 0;
+    for (_, content) in chess_board.iter_coordinates() {
+        if let Some(content) = content {
+            phase += PHASE_INCREMENTS[piece_index(content.get_piece())];
+        }
+    }
+    let mg_weight = phase.min(MAX_PHASE) as f64 / MAX_PHASE as f64;
+    let eg_weight = 1. - mg_weight;
+
+    let mut coefficients = vec![0.; PARAM_COUNT];
+    for (square, content) in chess_board.iter_coordinates() {
+        let Some(content) = content else {
+            continue;
+        };
+
+        let piece = piece_index(content.get_piece());
+        // Tables are from the point of view of white, black squares are
+        // mirrored
+        let (sign, table_square) = match content.get_color() {
+            Color::White => (1., square.get_index() as usize),
+            Color::Black => (-1., square.get_index() as usize ^ 56),
+        };
+
+        coefficients[MG_VALUE + piece] += sign * mg_weight;
+        coefficients[EG_VALUE + piece] += sign * eg_weight;
+        coefficients[MG_TABLES + 64 * piece + table_square] += sign * mg_weight;
+        coefficients[EG_TABLES + 64 * piece + table_square] += sign * eg_weight;
+    }
+
+    if activity {
+        for (term, count) in activity_counts(chess_board).into_iter().enumerate() {
+            coefficients[ACTIVITY + 2 * term] += count as f64 * mg_weight;
+            coefficients[ACTIVITY + 2 * term + 1] += count as f64 * eg_weight;
+        }
+    }
+
+    coefficients
+        .into_iter()
+        .enumerate()
+        .filter(|(_, coefficient)| *coefficient != 0.)
+        .map(|(index, coefficient)| (index as u16, coefficient as f32))
+        .collect()
+}
+
+fn params_to_vec(params: &PestoParams, activity_params: &ActivityParams) -> Vec<f64> {
+    let mut vec = vec![0.; PARAM_COUNT];
+
+    for (term, weights) in activity_params.to_terms().iter().enumerate() {
+        vec[ACTIVITY + 2 * term] = weights[0] as f64;
+        vec[ACTIVITY + 2 * term + 1] = weights[1] as f64;
+    }
+
+    for piece in 0..6 {
+        vec[MG_VALUE + piece] = params.mg_value[piece] as f64;
+        vec[EG_VALUE + piece] = params.eg_value[piece] as f64;
+
+        for square in 0..64 {
+            vec[MG_TABLES + 64 * piece + square] = params.mg_tables[piece][square] as f64;
+            vec[EG_TABLES + 64 * piece + square] = params.eg_tables[piece][square] as f64;
+        }
+    }
+
+    vec
+}
+
+// Adding a constant to a piece value and taking it from every square of its
+// table changes no evaluation, so the tables are kept centered for the
+// values to carry the material. Both kings are always on the board, so
+// the king value cancels out and is left alone.
+fn center_tables(vec: &mut [f64]) {
+    for piece in 0..5 {
+        // Pawns never stand on the first and last ranks
+        let squares = if piece == 0 { 8..56 } else { 0..64 };
+
+        for (value, tables) in [(MG_VALUE, MG_TABLES), (EG_VALUE, EG_TABLES)] {
+            let start = tables + 64 * piece;
+            let table = &mut vec[start + squares.start..start + squares.end];
+
+            let mean = table.iter().sum::<f64>() / table.len() as f64;
+            table.iter_mut().for_each(|entry| *entry -= mean);
+            vec[value + piece] += mean;
+        }
+    }
+}
+
+fn vec_to_params(vec: &[f64]) -> PestoParams {
+    let mut params = PestoParams::default();
+
+    for piece in 0..6 {
+        params.mg_value[piece] = vec[MG_VALUE + piece].round() as i32;
+        params.eg_value[piece] = vec[EG_VALUE + piece].round() as i32;
+
+        for square in 0..64 {
+            params.mg_tables[piece][square] = vec[MG_TABLES + 64 * piece + square].round() as i32;
+            params.eg_tables[piece][square] = vec[EG_TABLES + 64 * piece + square].round() as i32;
+        }
+    }
+
+    params
+}
+
+fn vec_to_activity_params(vec: &[f64]) -> ActivityParams {
+    let mut terms = [[0; 2]; ACTIVITY_TERMS];
+
+    for (term, weights) in terms.iter_mut().enumerate() {
+        weights[0] = vec[ACTIVITY + 2 * term].round() as i32;
+        weights[1] = vec[ACTIVITY + 2 * term + 1].round() as i32;
+    }
+
+    ActivityParams::from_terms(&terms)
+}
+
+/// Texel tuning of the PeSTO parameters, and of the piece activity weights
+/// if asked, the evaluation being the sum of both.
+///
+/// The evaluation of every position is turned into an expected result by a
+/// sigmoid, and the parameters are fitted with Adam to minimise the mean
+/// squared error against the labels. Search scores are turned into results
+/// by the same sigmoid.
+pub struct TexelTuner {
+    positions: Vec<(Vec<(u16, f32)>, Label)>,
+    params: Vec<f64>,
+    scaling: f64,
+    config: TexelConfig,
+}
+
+impl TexelTuner {
+    pub fn new(
+        positions: &[LabeledPosition],
+        params: &PestoParams,
+        config: TexelConfig,
+    ) -> TexelTuner {
+        TexelTuner::build(positions, params, None, config)
+    }
+
+    pub fn with_activity(
+        positions: &[LabeledPosition],
+        params: &PestoParams,
+        activity_params: &ActivityParams,
+        config: TexelConfig,
+    ) -> TexelTuner {
+        TexelTuner::build(positions, params, Some(activity_params), config)
+    }
+
+    // Without activity parameters their weights are left out of the
+    // features, so they get no gradient and keep their default values
+    fn build(
+        positions: &[LabeledPosition],
+        params: &PestoParams,
+        activity_params: Option<&ActivityParams>,
+        config: TexelConfig,
+    ) -> TexelTuner {
+        let activity = activity_params.is_some();
+
+        let default_activity = ActivityParams::default();
+        let mut params = params_to_vec(params, activity_params.unwrap_or(&default_activity));
+        center_tables(&mut params);
+
+        TexelTuner {
+            positions: positions
+                .iter()
+                .map(|position| (features(&position.chess_board, activity), position.label))
+                .collect(),
+            params,
+            scaling: DEFAULT_SCALING,
+            config,
+        }
+    }
+
+    pub fn get_params(&self) -> PestoParams {
+        vec_to_params(&self.params)
+    }
+
+    pub fn get_activity_params(&self) -> ActivityParams {
+        vec_to_activity_params(&self.params)
+    }
+
+    pub fn get_scaling(&self) -> f64 {
+        self.scaling
+    }
+
+    fn evaluate(&self, features: &[(u16, f32)]) -> f64 {
+        features
+            .iter()
+            .map(|&(index, coefficient)| self.params[index as usize] * coefficient as f64)
+            .sum()
+    }
+
+    fn target(&self, label: Label, scaling: f64) -> f64 {
+        match label {
+            Label::Result(result) => result,
+            Label::Score(score) => sigmoid(scaling * score),
+        }
+    }
+
+    fn loss_with_scaling(&self, scaling: f64, results_only: bool) -> f64 {
+        let mut total = 0.;
+        let mut count = 0;
+
+        for (features, label) in self.positions.iter() {
+            if results_only && !matches!(label, Label::Result(_)) {
+                continue;
+            }
+
+            let error = self.target(*label, scaling) - sigmoid(scaling * self.evaluate(features));
+            total += error * error;
+            count += 1;
+        }
+
+        total / count.max(1) as f64
+    }
+
+    pub fn loss(&self) -> f64 {
+        self.loss_with_scaling(self.scaling, false)
+    }
+
+    /// Finds the scaling of the sigmoid that best predicts the game results
+    /// with the current parameters, by golden section search. Search scores
+    /// don't say anything about it, so without results it is left as is.
+    pub fn fit_scaling(&mut self) -> f64 {
+        if !self
+            .positions
+            .iter()
+            .any(|(_, label)| matches!(label, Label::Result(_)))
+        {
+            return self.scaling;
+        }
+
+        let ratio = (5_f64.sqrt() - 1.) / 2.;
+        let (mut lower, mut upper) = SCALING_BOUNDS;
+
+        for _ in 0..SCALING_ITERATIONS {
+            let left = upper - ratio * (upper - lower);
+            let right = lower + ratio * (upper - lower);
+
+            if self.loss_with_scaling(left, true) < self.loss_with_scaling(right, true) {
+                upper = right;
+            } else {
+                lower = left;
+            }
+        }
+
+        self.scaling = (lower + upper) / 2.;
+        self.scaling
+    }
+
+    // Gradient of the loss over some of the positions, not yet divided by
+    // the number of positions
+    fn partial_gradient(&self, positions: &[(Vec<(u16, f32)>, Label)]) -> Vec<f64> {
+        let mut gradient = vec![0.; PARAM_COUNT];
+
+        for (features, label) in positions {
+            let predicted = sigmoid(self.scaling * self.evaluate(features));
+            let error = predicted - self.target(*label, self.scaling);
+            let factor = 2. * error * predicted * (1. - predicted) * self.scaling;
+
+            for &(index, coefficient) in features {
+                gradient[index as usize] += factor * coefficient as f64;
+            }
+        }
+
+        gradient
+    }
+
+    fn gradient(&self) -> Vec<f64> {
+        let threads = self.config.threads.max(1);
+        let chunk_size = self.positions.len().div_ceil(threads).max(1);
+
+        let partial_gradients: Vec<Vec<f64>> = thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .positions
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || self.partial_gradient(chunk)))
+                .collect();
+
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+
+        let count = self.positions.len().max(1) as f64;
+        let mut gradient = vec![0.; PARAM_COUNT];
+        for partial_gradient in partial_gradients {
+            for (total, partial) in gradient.iter_mut().zip(partial_gradient) {
+                *total += partial / count;
+            }
+        }
+
+        gradient
+    }
+
+    /// Fits the scaling if asked, then runs Adam for the configured number
+    /// of epochs. Returns the final loss.
+    pub fn run(&mut self) -> f64 {
+        if self.config.fit_scaling {
+            self.fit_scaling();
+        }
+        info!("Scaling {:.6}, loss {:.6}", self.scaling, self.loss());
+
+        let mut first_moment = vec![0.; PARAM_COUNT];
+        let mut second_moment = vec![0.; PARAM_COUNT];
+
+        for epoch in 1..=self.config.epochs {
+            let gradient = self.gradient();
+
+            let correction1 = 1. - ADAM_BETA1.powi(epoch as i32);
+            let correction2 = 1. - ADAM_BETA2.powi(epoch as i32);
+
+            for index in 0..PARAM_COUNT {
+                first_moment[index] =
+                    ADAM_BETA1 * first_moment[index] + (1. - ADAM_BETA1) * gradient[index];
+                second_moment[index] = ADAM_BETA2 * second_moment[index]
+                    + (1. - ADAM_BETA2) * gradient[index] * gradient[index];
+
+                let step = (first_moment[index] / correction1)
+                    / ((second_moment[index] / correction2).sqrt() + ADAM_EPSILON);
+                self.params[index] -= self.config.learning_rate * step;
+            }
+            center_tables(&mut self.params);
+
+            if epoch % 50 == 0 || epoch == self.config.epochs {
+                info!("Epoch {}: loss {:.6}", epoch, self.loss());
+            }
+        }
+
+        self.loss()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engines::engine_traits::Evaluator;
+    use crate::engines::evaluators::{ActivityEvaluator, PestoEvaluator};
+
+    const FENS: [&str; 5] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+        "2r3k1/pp3ppp/8/3p4/3P4/2P5/PP3PPP/4R1K1 b - - 0 1",
+        "8/5k2/8/3P4/8/8/5K2/8 w - - 0 1",
+        "6k1/5ppp/8/8/8/8/1q3PPP/6K1 w - - 0 1",
+    ];
+
+    fn boards() -> Vec<ChessBoard> {
+        FENS.iter().map(|fen| ChessBoard::from_fen(fen).unwrap()).collect()
+    }
+
+    fn dot(features: &[(u16, f32)], params: &[f64]) -> f64 {
+        features
+            .iter()
+            .map(|&(index, coefficient)| params[index as usize] * coefficient as f64)
+            .sum()
+    }
+
+    #[test]
+    fn features_give_the_pesto_evaluation() {
+        let params = params_to_vec(&PestoParams::default(), &ActivityParams::default());
+        let evaluator = PestoEvaluator::new();
+
+        for chess_board in boards() {
+            let expected = evaluator.evaluate_white(&chess_board).get_centipawns() as f64;
+            let linear = dot(&features(&chess_board, false), &params);
+
+            // The evaluator rounds towards zero when tapering
+            assert!((linear - expected).abs() < 1., "{}: {}", chess_board.to_fen(), linear);
+        }
+    }
+
+    #[test]
+    fn activity_features_give_the_activity_evaluation() {
+        let positions: Vec<LabeledPosition> = boards()
+            .into_iter()
+            .map(|chess_board| LabeledPosition {
+                chess_board,
+                label: Label::Result(0.5),
+            })
+            .collect();
+        let tuner = TexelTuner::with_activity(
+            &positions,
+            &PestoParams::default(),
+            &ActivityParams::default(),
+            TexelConfig::default(),
+        );
+        let pesto = PestoEvaluator::new();
+        let activity = ActivityEvaluator::new();
+
+        for (position, (features, _)) in positions.iter().zip(&tuner.positions) {
+            let chess_board = &position.chess_board;
+            let expected = (pesto.evaluate_white(chess_board).get_centipawns()
+                + activity.evaluate_white(chess_board).get_centipawns())
+                as f64;
+            let linear = tuner.evaluate(features);
+
+            // Both evaluators round to centipawns
+            assert!((linear - expected).abs() < 2., "{}: {}", chess_board.to_fen(), linear);
+        }
+        assert!(positions
+            .iter()
+            .any(|position| activity.evaluate(&position.chess_board).get_centipawns() != 0));
+    }
+
+    #[test]
+    fn centering_the_tables_changes_no_evaluation() {
+        let params = params_to_vec(&PestoParams::default(), &ActivityParams::default());
+        let mut centered = params.clone();
+        center_tables(&mut centered);
+        assert_ne!(params, centered);
+
+        for chess_board in boards() {
+            let features = features(&chess_board, true);
+            assert!((dot(&features, &params) - dot(&features, &centered)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn adam_lowers_the_loss() {
+        // Results that the default parameters don't quite predict
+        let labels = [
+            Label::Result(0.5),
+            Label::Result(1.),
+            Label::Score(-150.),
+            Label::Result(0.5),
+            Label::Result(0.),
+        ];
+        let positions: Vec<LabeledPosition> = boards()
+            .into_iter()
+            .zip(labels)
+            .map(|(chess_board, label)| LabeledPosition { chess_board, label })
+            .collect();
+        let config = TexelConfig {
+            epochs: 20,
+            learning_rate: 1.,
+            threads: 2,
+            fit_scaling: false,
+        };
+
+        let mut tuner = TexelTuner::new(&positions, &PestoParams::default(), config);
+        let initial_loss = tuner.loss();
+        let final_loss = tuner.run();
+
+        assert!(final_loss < initial_loss, "{} >= {}", final_loss, initial_loss);
+        assert_eq!(tuner.get_scaling(), DEFAULT_SCALING);
+    }
+}